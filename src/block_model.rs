@@ -0,0 +1,474 @@
+// foreign imports
+use bevy::prelude::IVec3;
+use serde::Deserialize;
+
+// 'self' imports
+use crate::blocks::Facing;
+use crate::chunk_logic::rendering::rendering_const::FACE_AXES;
+
+/// The amount of model units along every edge of a block, model boxes are placed on this grid (like the 16 pixels of a texture)
+pub const MODEL_RESOLUTION: u8 = 16;
+
+/// # Usage:
+/// An axis aligned box of a ```BlockModel```, from its lowest to its highest corner in model units (```0..=MODEL_RESOLUTION```)
+/// # Format:
+/// ```
+/// (from: (0, 0, 0), to: (16, 8, 16))
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+pub struct ModelBox {
+    pub from: [u8; 3],
+    pub to: [u8; 3],
+}
+
+impl ModelBox {
+    /// # Usage:
+    /// the box that fills the whole block
+    pub const FULL: ModelBox = ModelBox { from: [0; 3], to: [MODEL_RESOLUTION; 3] };
+
+    /// # Usage:
+    /// the same box, turned like every local face in ```Facing::rotated```
+    pub fn rotated(self, orientation: Facing) -> ModelBox {
+        // doubled coordinates around the center of the block, so the rotation stays in integers
+        let centered = |corner: [u8; 3]| IVec3::from_array(corner.map(|c| c as i32)) * 2 - IVec3::splat(MODEL_RESOLUTION as i32);
+        let a = orientation.rotate_vector(centered(self.from));
+        let b = orientation.rotate_vector(centered(self.to));
+        let uncentered = |corner: IVec3| ((corner + IVec3::splat(MODEL_RESOLUTION as i32)) / 2).to_array().map(|c| c as u8);
+        ModelBox { from: uncentered(a.min(b)), to: uncentered(a.max(b)) }
+    }
+
+    /// # Usage:
+    /// The ```facing``` face of the box: the position of its plane along the face normal and its extent along the u and v axes of ```FACE_AXES```
+    pub fn face(&self, facing: Facing) -> (u8, FaceRect) {
+        let [u_axis, v_axis] = FACE_AXES[facing.index()];
+        let normal_axis = 3 - u_axis - v_axis;
+        let plane = if facing.offset()[normal_axis] > 0 { self.to[normal_axis] } else { self.from[normal_axis] };
+        (plane, FaceRect { u: [self.from[u_axis], self.to[u_axis]], v: [self.from[v_axis], self.to[v_axis]] })
+    }
+
+    /// # Usage:
+    /// the part of the box that lies behind ```rect``` on its ```facing``` face, e.g. the visible part of a face that is partly covered
+    pub fn with_face_rect(self, facing: Facing, rect: FaceRect) -> ModelBox {
+        let [u_axis, v_axis] = FACE_AXES[facing.index()];
+        let mut part = self;
+        (part.from[u_axis], part.to[u_axis]) = (rect.u[0], rect.u[1]);
+        (part.from[v_axis], part.to[v_axis]) = (rect.v[0], rect.v[1]);
+        part
+    }
+
+    /// # Usage:
+    /// returns ```true``` if the ```facing``` face of the box lies on the border of the block
+    pub fn touches_side(&self, facing: Facing) -> bool {
+        let plane = self.face(facing).0;
+        plane == if facing.offset().max_element() > 0 { MODEL_RESOLUTION } else { 0 }
+    }
+
+    /// # Usage:
+    /// returns why the box cannot be used, if it cannot
+    pub fn problem(&self) -> Option<&'static str> {
+        if self.to.iter().any(|c| *c > MODEL_RESOLUTION) {
+            Some("a model box has to stay inside of the block")
+        } else if self.from.iter().zip(self.to).any(|(from, to)| *from >= to) {
+            Some("a model box has to span at least one unit along every axis")
+        } else {
+            None
+        }
+    }
+}
+
+/// # Usage:
+/// A rectangle on a face plane in model units, ```u``` and ```v``` run along the axes of ```FACE_AXES``` and are half open (```[start, end)```)
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FaceRect {
+    pub u: [u8; 2],
+    pub v: [u8; 2],
+}
+
+/// # Usage:
+/// Which model units of a face plane are covered, one bit per unit and one row per ```v```
+/// # Structure:
+/// ```
+/// pub struct FaceMask([u16; 16]);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct FaceMask([u16; MODEL_RESOLUTION as usize]);
+
+impl FaceMask {
+    /// # Usage:
+    /// nothing is covered
+    pub const EMPTY: FaceMask = FaceMask([0; MODEL_RESOLUTION as usize]);
+    /// # Usage:
+    /// the whole side of the block is covered
+    pub const FULL: FaceMask = FaceMask([u16::MAX; MODEL_RESOLUTION as usize]);
+
+    fn row_bits(rect: FaceRect) -> u16 {
+        let width = rect.u[1] - rect.u[0];
+        let bits = if width >= 16 { u16::MAX } else { (1u16 << width) - 1 };
+        bits << rect.u[0]
+    }
+
+    /// # Usage:
+    /// marks every unit of ```rect``` as covered
+    pub fn add(&mut self, rect: FaceRect) {
+        let bits = Self::row_bits(rect);
+        for row in &mut self.0[rect.v[0] as usize..rect.v[1] as usize] {
+            *row |= bits;
+        }
+    }
+
+    /// # Usage:
+    /// returns ```true``` if every unit of ```rect``` is covered
+    pub fn covers(&self, rect: FaceRect) -> bool {
+        let bits = Self::row_bits(rect);
+        self.0[rect.v[0] as usize..rect.v[1] as usize].iter().all(|row| row & bits == bits)
+    }
+
+    /// # Usage:
+    /// the union of both masks
+    pub fn union(self, other: FaceMask) -> FaceMask {
+        let mut union = self;
+        for (row, other) in union.0.iter_mut().zip(other.0) {
+            *row |= other;
+        }
+        union
+    }
+
+    /// # Usage:
+    /// the units of this mask that ```other``` does not cover
+    pub fn without(self, other: FaceMask) -> FaceMask {
+        let mut difference = self;
+        for (row, other) in difference.0.iter_mut().zip(other.0) {
+            *row &= !other;
+        }
+        difference
+    }
+
+    /// # Usage:
+    /// The covered units as rectangles that do not overlap, each one grown along ```u``` first and then along ```v```
+    pub fn rects(&self) -> Vec<FaceRect> {
+        let mut rows = self.0;
+        let mut rects = Vec::new();
+        for v in 0..rows.len() {
+            while rows[v] != 0 {
+                let start = rows[v].trailing_zeros() as u8;
+                let u = [start, start + (rows[v] >> start).trailing_ones() as u8];
+                let bits = Self::row_bits(FaceRect { u, v: [0, 0] });
+                let end = (v + 1..rows.len()).find(|row| rows[*row] & bits != bits).unwrap_or(rows.len());
+                for row in &mut rows[v..end] {
+                    *row &= !bits;
+                }
+                rects.push(FaceRect { u, v: [v as u8, end as u8] });
+            }
+        }
+        rects
+    }
+
+    /// # Usage:
+    /// returns ```true``` if the whole side is covered
+    pub fn is_full(&self) -> bool { *self == FaceMask::FULL }
+}
+
+/// # Usage:
+/// The horizontal sides of a block that a connecting ```BlockModel``` (```Fence``` and ```Pane```) reaches out to, one bit per side of ```SIDES```
+/// # Structure:
+/// ```
+/// pub struct Connections(u8);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Connections(u8);
+
+impl Connections {
+    /// # Usage:
+    /// the sides a model can connect to, in the order of the bits
+    pub const SIDES: [Facing; 4] = [Facing::XPositive, Facing::XNegative, Facing::ZPositive, Facing::ZNegative];
+    /// # Usage:
+    /// connected to nothing
+    pub const NONE: Connections = Connections(0);
+    /// # Usage:
+    /// the amount of different ```Connections```
+    pub const COUNT: usize = 1 << Self::SIDES.len();
+
+    /// # Usage:
+    /// the sides for which ```connects``` returns ```true```
+    pub fn from_fn(mut connects: impl FnMut(Facing) -> bool) -> Self {
+        Connections(Self::SIDES.iter().enumerate().filter(|(_, side)| connects(**side)).fold(0, |bits, (index, _)| bits | 1 << index))
+    }
+
+    /// # Usage:
+    /// the ```Connections``` with the bits of ```index```, the inverse of ```index()```
+    pub fn from_index(index: usize) -> Self {
+        Connections((index % Self::COUNT) as u8)
+    }
+
+    /// # Usage:
+    /// a unique number below ```COUNT```
+    pub fn index(self) -> usize { self.0 as usize }
+
+    /// # Usage:
+    /// returns ```true``` if the model reaches out to its ```facing``` side
+    pub fn contains(self, facing: Facing) -> bool {
+        Self::SIDES.iter().position(|side| *side == facing).is_some_and(|index| self.0 >> index & 1 == 1)
+    }
+}
+
+/// # Usage:
+/// The geometry of a block type, declared in its block definition. Everything except ```Cross``` is made of ```ModelBox```es.
+/// # Format:
+/// ```
+/// pub enum BlockModel {
+///     Cube,
+///     Slab,
+///     Stairs,
+///     Fence,
+///     Pane,
+///     Cross,
+///     Boxes(Vec<ModelBox>)
+/// }
+/// ```
+/// The built-in models are laid out with their front at ```XPositive```, e.g. the high step of ```Stairs``` and the width of a ```Pane```,
+/// so they turn with the ```Facing``` of the ```Block```. ```Cross``` is two crossed quads, like the classic minecraft plants.
+/// ```Fence``` and ```Pane``` connect to the blocks next to them (see ```connected_boxes()```), a pane that connects to nothing stands across its ```Facing```.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize)]
+pub enum BlockModel {
+    #[default]
+    Cube,
+    Slab,
+    Stairs,
+    Fence,
+    Pane,
+    Cross,
+    Boxes(Vec<ModelBox>)
+}
+
+impl BlockModel {
+    /// # Usage:
+    /// the boxes of the model in the local space of the block, empty for ```Cross```
+    pub fn boxes(&self) -> Vec<ModelBox> {
+        let half = MODEL_RESOLUTION / 2;
+        match self {
+            BlockModel::Cube => vec![ModelBox::FULL],
+            BlockModel::Slab => vec![ModelBox { from: [0, 0, 0], to: [16, half, 16] }],
+            BlockModel::Stairs => vec![
+                ModelBox { from: [0, 0, 0], to: [16, half, 16] },
+                ModelBox { from: [half, half, 0], to: [16, 16, 16] },
+            ],
+            BlockModel::Fence => vec![ModelBox { from: [6, 0, 6], to: [10, 16, 10] }],
+            BlockModel::Pane => vec![ModelBox { from: [7, 0, 0], to: [9, 16, 16] }],
+            BlockModel::Cross => Vec::new(),
+            BlockModel::Boxes(boxes) => boxes.clone(),
+        }
+    }
+
+    /// # Usage:
+    /// returns ```true``` for the models that reach out to their neighbours, see ```connected_boxes()```
+    pub fn connects(&self) -> bool {
+        matches!(self, BlockModel::Fence | BlockModel::Pane)
+    }
+
+    /// # Usage:
+    /// The boxes of a connecting model in world orientation: the post in the middle of the block and an arm towards every side of ```connections```,
+    /// two rails for a ```Fence``` and a piece of glass for a ```Pane```. Models that do not connect return ```boxes()```.
+    pub fn connected_boxes(&self, connections: Connections) -> Vec<ModelBox> {
+        let (post, arms) = match self {
+            BlockModel::Fence => (self.boxes()[0], vec![ModelBox { from: [10, 6, 7], to: [16, 9, 9] }, ModelBox { from: [10, 12, 7], to: [16, 15, 9] }]),
+            BlockModel::Pane => (ModelBox { from: [7, 0, 7], to: [9, 16, 9] }, vec![ModelBox { from: [9, 0, 7], to: [16, 16, 9] }]),
+            _ => return self.boxes(),
+        };
+        let mut boxes = vec![post];
+        for side in Connections::SIDES.into_iter().filter(|side| connections.contains(*side)) {
+            boxes.extend(arms.iter().map(|arm| arm.rotated(side)));
+        }
+        boxes
+    }
+
+    /// # Usage:
+    /// returns why the model cannot be used, if it cannot
+    pub fn problem(&self) -> Option<&'static str> {
+        match self {
+            BlockModel::Boxes(boxes) if boxes.is_empty() => Some("a model needs at least one box"),
+            BlockModel::Boxes(boxes) => boxes.iter().find_map(ModelBox::problem),
+            _ => None,
+        }
+    }
+}
+
+/// # Usage:
+/// A ```BlockModel``` turned to one orientation, with the coverage of every side of the block precomputed for face culling.
+/// # Structure:
+/// ```
+/// pub struct ModelShape {
+///     boxes: Vec<ModelBox>,
+///     sides: [FaceMask; 6],
+///     cross: bool,
+/// }
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ModelShape {
+    boxes: Vec<ModelBox>,
+    sides: [FaceMask; 6],
+    cross: bool,
+}
+
+impl ModelShape {
+    /// # Usage:
+    /// the shape of ```model``` for a block whose front points to ```orientation```
+    pub fn new(model: &BlockModel, orientation: Facing) -> Self {
+        let boxes = model.boxes().into_iter().map(|model_box| model_box.rotated(orientation)).collect();
+        Self::from_boxes(boxes, *model == BlockModel::Cross)
+    }
+
+    /// # Usage:
+    /// the shape of a connecting ```model``` that reaches out to ```connections```, see ```BlockModel::connected_boxes()```
+    pub fn connected(model: &BlockModel, connections: Connections) -> Self {
+        Self::from_boxes(model.connected_boxes(connections), false)
+    }
+
+    fn from_boxes(boxes: Vec<ModelBox>, cross: bool) -> Self {
+        let mut sides = [FaceMask::EMPTY; 6];
+        for model_box in &boxes {
+            for facing in Facing::ALL {
+                if model_box.touches_side(facing) {
+                    sides[facing.index()].add(model_box.face(facing).1);
+                }
+            }
+        }
+        ModelShape { boxes, sides, cross }
+    }
+
+    /// # Usage:
+    /// the boxes of the shape in world orientation
+    pub fn boxes(&self) -> &[ModelBox] { &self.boxes }
+
+    /// # Usage:
+    /// returns ```true``` for the crossed quads of ```BlockModel::Cross```
+    pub fn is_cross(&self) -> bool { self.cross }
+
+    /// # Usage:
+    /// returns ```true``` if the shape fills the whole block, so the fast full-block paths of the meshers apply
+    pub fn is_full_cube(&self) -> bool { self.sides.iter().all(FaceMask::is_full) }
+
+    /// # Usage:
+    /// the part of the side ```facing``` of the block that the shape covers
+    pub fn side(&self, facing: Facing) -> FaceMask { self.sides[facing.index()] }
+
+    /// # Usage:
+    /// The part of the ```facing``` face plane of the box at ```index``` that the other boxes of the shape cover from the outside,
+    /// e.g. the lower step of a stair covers the bottom face of the upper step.
+    pub fn inner_cover(&self, index: usize, facing: Facing) -> FaceMask {
+        let (plane, _) = self.boxes[index].face(facing);
+        let mut cover = FaceMask::EMPTY;
+        for (other_index, other) in self.boxes.iter().enumerate() {
+            let (other_plane, rect) = other.face(facing.opposite());
+            if other_index != index && other_plane == plane {
+                cover.add(rect);
+            }
+        }
+        cover
+    }
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_face_mask() {
+        let mut mask = FaceMask::EMPTY;
+        let lower_half = FaceRect { u: [0, 16], v: [0, 8] };
+        mask.add(lower_half);
+        assert!(mask.covers(lower_half));
+        assert!(mask.covers(FaceRect { u: [3, 5], v: [2, 7] }));
+        assert!(!mask.covers(FaceRect { u: [0, 16], v: [7, 9] }));
+        assert!(!mask.is_full());
+        assert!(mask.union(FaceMask::FULL).is_full());
+        mask.add(FaceRect { u: [0, 16], v: [8, 16] });
+        assert!(mask.is_full());
+    }
+
+    #[test]
+    fn test_slab_sides() {
+        let slab = ModelShape::new(&BlockModel::Slab, Facing::XPositive);
+        assert!(!slab.is_full_cube());
+        assert!(slab.side(Facing::YNegative).is_full());
+        assert_eq!(FaceMask::EMPTY, slab.side(Facing::YPositive));
+        for facing in [Facing::XPositive, Facing::XNegative, Facing::ZPositive, Facing::ZNegative] {
+            assert!(slab.side(facing).covers(FaceRect { u: [0, 16], v: [0, 8] }), "{facing:?}");
+            assert!(!slab.side(facing).covers(FaceRect { u: [0, 16], v: [8, 9] }), "{facing:?}");
+        }
+        assert!(ModelShape::new(&BlockModel::Cube, Facing::ZNegative).is_full_cube());
+    }
+
+    #[test]
+    fn test_stairs_turn_with_orientation() {
+        // the high step sits at the front of the stairs
+        for orientation in [Facing::XPositive, Facing::XNegative, Facing::ZPositive, Facing::ZNegative] {
+            let stairs = ModelShape::new(&BlockModel::Stairs, orientation);
+            assert!(stairs.side(orientation).is_full(), "{orientation:?}");
+            assert!(!stairs.side(orientation.opposite()).is_full(), "{orientation:?}");
+            assert!(stairs.side(Facing::YNegative).is_full(), "{orientation:?}");
+        }
+        let turned = ModelBox { from: [8, 8, 0], to: [16, 16, 16] }.rotated(Facing::ZNegative);
+        assert_eq!(ModelBox { from: [0, 8, 0], to: [16, 16, 8] }, turned);
+    }
+
+    #[test]
+    fn test_inner_cover() {
+        let stairs = ModelShape::new(&BlockModel::Stairs, Facing::XPositive);
+        // the bottom of the upper step lies completely on the lower step, but the top of the lower step is only half covered
+        let upper_bottom = stairs.boxes()[1].face(Facing::YNegative).1;
+        assert!(stairs.inner_cover(1, Facing::YNegative).covers(upper_bottom));
+        let lower_top = stairs.boxes()[0].face(Facing::YPositive).1;
+        assert!(!stairs.inner_cover(0, Facing::YPositive).covers(lower_top));
+    }
+
+    #[test]
+    fn test_face_mask_rects() {
+        let mut mask = FaceMask::EMPTY;
+        mask.add(FaceRect { u: [6, 10], v: [0, 16] });
+        assert_eq!(vec![FaceRect { u: [6, 10], v: [0, 16] }], mask.rects());
+
+        // a hole in the middle leaves the parts below, above and on both sides of it
+        let hole = FaceRect { u: [7, 9], v: [6, 9] };
+        let mut cut = FaceMask::EMPTY;
+        cut.add(hole);
+        let rects = mask.without(cut).rects();
+        assert_eq!(4, rects.len());
+        let mut rebuilt = FaceMask::EMPTY;
+        for rect in &rects {
+            assert!(!cut.covers(FaceRect { u: [rect.u[0], rect.u[0] + 1], v: [rect.v[0], rect.v[0] + 1] }));
+            rebuilt.add(*rect);
+        }
+        assert_eq!(mask.without(cut), rebuilt);
+        assert!(FaceMask::EMPTY.rects().is_empty());
+        assert_eq!(vec![FaceRect { u: [0, 16], v: [0, 16] }], FaceMask::FULL.rects());
+    }
+
+    #[test]
+    fn test_connections() {
+        let connections = Connections::from_fn(|facing| matches!(facing, Facing::XNegative | Facing::ZPositive | Facing::YPositive));
+        assert!(connections.contains(Facing::XNegative) && connections.contains(Facing::ZPositive));
+        assert!(!connections.contains(Facing::XPositive) && !connections.contains(Facing::YPositive));
+        assert_eq!(connections, Connections::from_index(connections.index()));
+
+        // the rails of a fence reach the sides it connects to, the pane arms cover them from the floor to the top
+        let fence = ModelShape::connected(&BlockModel::Fence, connections);
+        assert_eq!(5, fence.boxes().len());
+        assert!(fence.side(Facing::XNegative).covers(FaceRect { u: [7, 9], v: [6, 9] }));
+        assert_eq!(FaceMask::EMPTY, fence.side(Facing::XPositive));
+        let pane = ModelShape::connected(&BlockModel::Pane, connections);
+        assert!(pane.side(Facing::ZPositive).covers(FaceRect { u: [7, 9], v: [0, 16] }));
+        assert!(!pane.side(Facing::ZPositive).covers(FaceRect { u: [6, 9], v: [0, 16] }));
+        assert_eq!(BlockModel::Slab.boxes(), BlockModel::Slab.connected_boxes(connections));
+    }
+
+    #[test]
+    fn test_invalid_boxes() {
+        assert!(BlockModel::Boxes(Vec::new()).problem().is_some());
+        assert!(BlockModel::Boxes(vec![ModelBox { from: [0, 0, 0], to: [17, 1, 1] }]).problem().is_some());
+        assert!(BlockModel::Boxes(vec![ModelBox { from: [4, 0, 0], to: [4, 1, 1] }]).problem().is_some());
+        assert_eq!(None, BlockModel::Fence.problem());
+    }
+}
@@ -0,0 +1,782 @@
+// foreign imports
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::Deserialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+// 'self' imports
+use crate::block_model::{BlockModel, Connections, FaceMask, ModelShape};
+use crate::block_state::{PropertyDefinition, PropertyValue, StateError, StateLayout, MAX_STATES};
+use crate::blocks::{Block, BlockId, Facing, LightEmission, RedstonePowerLvl, RenderLayer, MAX_LIGHT_OPACITY};
+use crate::redstone_logic::redstone::RedstoneComponent;
+
+/// The directory all block definition files are loaded from
+pub const BLOCK_DIRECTORY: &str = "assets/blocks";
+
+/// The highest light level a block can emit and the highest redstone power level
+pub const MAX_LEVEL: u8 = 15;
+
+/// # Usage:
+/// The texture names of the six faces of a block, in the block's own (local) space. ```XPositive``` is the front of the block.
+/// # Format:
+/// ```
+/// pub enum BlockTextures {
+///     Uniform(String),
+///     Column { top: String, sides: String, bottom: String },
+///     Axis { end: String, sides: String },
+///     Faces([String; 6])
+/// }
+/// ```
+/// ```Axis``` blocks (logs) show ```end``` on their front and back, so they follow the ```Facing``` of the ```Block```.
+/// ```Faces``` lists every face explicitly, ordered like ```Facing::ALL```.
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+pub enum BlockTextures {
+    Uniform(String),
+    Column { top: String, sides: String, bottom: String },
+    Axis { end: String, sides: String },
+    Faces([String; 6])
+}
+
+impl BlockTextures {
+    /// # Usage:
+    /// the texture name of every local face, ordered like ```Facing::ALL```
+    pub fn faces(&self) -> [&str; 6] {
+        match self {
+            BlockTextures::Uniform(name) => [name.as_str(); 6],
+            BlockTextures::Column { top, sides, bottom } => [sides, sides, top, bottom, sides, sides],
+            BlockTextures::Axis { end, sides } => [end, end, sides, sides, sides, sides],
+            BlockTextures::Faces(names) => [&names[0], &names[1], &names[2], &names[3], &names[4], &names[5]],
+        }
+    }
+}
+
+/// # Usage:
+/// The properties every ```Block``` of one type shares, as written in a block definition file.
+/// # Format:
+/// ```
+/// (
+///     name: "grass",
+///     textures: Some(Column(top: "grass_top", sides: "grass_side", bottom: "dirt")),
+///     render_layer: Solid,
+///     light_opacity: 15,
+///     light_emission: None,
+///     redstone_power_lvl: None,
+///     hardness: 0.6,
+///     collision: true,
+///     model: Cube,
+///     properties: [(name: "snowy", kind: Bool)],
+///     redstone: None,
+/// )
+/// ```
+/// ```model``` defaults to a full ```BlockModel::Cube```. ```properties``` are optional, every ```Block``` of the type stores one value for each of them (see ```PropertyDefinition```).
+/// ```redstone``` defaults to ```RedstoneComponent::None```, it decides how the block takes part in the redstone simulation.
+/// ```light_opacity``` is how much light (0 to ```MAX_LIGHT_OPACITY```) the block absorbs. It is independent of the ```render_layer```,
+/// e.g. leaves are drawn with holes but still dim the light passing through them.
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+pub struct BlockDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub textures: Option<BlockTextures>,
+    pub render_layer: RenderLayer,
+    pub light_opacity: u8,
+    pub light_emission: LightEmission,
+    pub redstone_power_lvl: RedstonePowerLvl,
+    pub hardness: f32,
+    pub collision: bool,
+    #[serde(default)]
+    pub model: BlockModel,
+    #[serde(default)]
+    pub properties: Vec<PropertyDefinition>,
+    #[serde(default)]
+    pub redstone: RedstoneComponent,
+}
+
+/// # Usage:
+/// Everything that can go wrong while loading the block definitions
+/// # Format:
+/// ```
+/// pub enum RegistryError {
+///     Io(PathBuf, String),
+///     Parse(PathBuf, String),
+///     InvalidFileName(PathBuf),
+///     NameMismatch { path: PathBuf, name: String },
+///     Misordered { path: PathBuf, expected: u16, found: u16 },
+///     DuplicateId { id: u16, first: PathBuf, second: PathBuf },
+///     DuplicateName { name: String, first: BlockId, second: BlockId },
+///     InvalidAir(String),
+///     ValueOutOfRange { name: String, property: &'static str, value: u8 },
+///     InvalidHardness { name: String, hardness: f32 },
+///     InvalidProperty { name: String, property: String, reason: &'static str },
+///     InvalidModel { name: String, reason: &'static str },
+///     TooManyStates { name: String, count: usize },
+///     TooManyBlocks(usize)
+/// }
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub enum RegistryError {
+    Io(PathBuf, String),
+    Parse(PathBuf, String),
+    InvalidFileName(PathBuf),
+    NameMismatch { path: PathBuf, name: String },
+    Misordered { path: PathBuf, expected: u16, found: u16 },
+    DuplicateId { id: u16, first: PathBuf, second: PathBuf },
+    DuplicateName { name: String, first: BlockId, second: BlockId },
+    InvalidAir(String),
+    ValueOutOfRange { name: String, property: &'static str, value: u8 },
+    InvalidHardness { name: String, hardness: f32 },
+    InvalidProperty { name: String, property: String, reason: &'static str },
+    InvalidModel { name: String, reason: &'static str },
+    TooManyStates { name: String, count: usize },
+    TooManyBlocks(usize)
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::Io(path, error) => write!(f, "could not read {}: {error}", path.display()),
+            RegistryError::Parse(path, error) => write!(f, "could not parse the block definition {}: {error}", path.display()),
+            RegistryError::InvalidFileName(path) => write!(f, "the block definition {} is not named like 'NNNN_name.ron' or 'NNNN_name.json'", path.display()),
+            RegistryError::NameMismatch { path, name } => write!(f, "the block definition {} defines the block '{name}', which does not match its file name", path.display()),
+            RegistryError::Misordered { path, expected, found } => write!(f, "the block definition {} has the ID {found}, but the next free ID is {expected} (IDs have to be contiguous and start at 0)", path.display()),
+            RegistryError::DuplicateId { id, first, second } => write!(f, "the block definitions {} and {} both claim the ID {id}", first.display(), second.display()),
+            RegistryError::DuplicateName { name, first, second } => write!(f, "the block '{name}' is defined twice, with the IDs {} and {}", first.0, second.0),
+            RegistryError::InvalidAir(name) => write!(f, "the block with ID 0 has to be the invisible 'air' block, found '{name}'"),
+            RegistryError::ValueOutOfRange { name, property, value } => write!(f, "the {property} of the block '{name}' is {value}, but may be at most {MAX_LEVEL}"),
+            RegistryError::InvalidHardness { name, hardness } => write!(f, "the hardness of the block '{name}' is {hardness}, but has to be a finite number of at least 0"),
+            RegistryError::InvalidProperty { name, property, reason } => write!(f, "the state property '{property}' of the block '{name}' is invalid: {reason}"),
+            RegistryError::InvalidModel { name, reason } => write!(f, "the model of the block '{name}' is invalid: {reason}"),
+            RegistryError::TooManyStates { name, count } => write!(f, "the state properties of the block '{name}' combine to {count} states, but at most {MAX_STATES} are possible"),
+            RegistryError::TooManyBlocks(count) => write!(f, "{count} block definitions exceed the {} available IDs", u16::MAX as usize + 1),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+/// # Usage:
+/// Holds the ```BlockDefinition``` of every block type. A ```Block``` only carries its ```BlockId```, which is the index of its definition in here.
+///
+/// IDs are assigned at load time from the numeric prefix of the definition files (```0000_air.ron```, ```0001_stone.ron```, ...).
+/// The prefixes have to be contiguous, start at 0 and ID 0 has to be air.
+/// # Structure:
+/// ```
+/// pub struct BlockRegistry {
+///     definitions: Vec<BlockDefinition>,
+///     layouts: Vec<StateLayout>,
+///     shapes: Vec<[ModelShape; 6]>,
+///     connected_shapes: Vec<Vec<ModelShape>>,
+///     ids: HashMap<String, BlockId>,
+/// }
+/// ```
+/// The ```ModelShape``` of every block type is precomputed for all six orientations,
+/// the one of connecting block types (see ```BlockModel::connects()```) also for every ```Connections```.
+#[derive(Resource, Clone, Debug)]
+pub struct BlockRegistry {
+    definitions: Vec<BlockDefinition>,
+    layouts: Vec<StateLayout>,
+    shapes: Vec<[ModelShape; 6]>,
+    connected_shapes: Vec<Vec<ModelShape>>,
+    ids: HashMap<String, BlockId>,
+}
+
+impl BlockRegistry {
+
+    /// # Usage:
+    /// Builds a registry from definitions that are already ordered by ID
+    /// # Errors:
+    /// fails if a name is used twice, if the first definition is not air or if a property is out of range
+    pub fn from_definitions(definitions: Vec<BlockDefinition>) -> Result<Self, RegistryError> {
+        if definitions.len() > u16::MAX as usize + 1 {
+            return Err(RegistryError::TooManyBlocks(definitions.len()));
+        }
+        match definitions.first() {
+            Some(air) if air.name == "air" && air.render_layer == RenderLayer::Invisible => {},
+            first => return Err(RegistryError::InvalidAir(first.map(|definition| definition.name.clone()).unwrap_or_default())),
+        }
+
+        let mut ids = HashMap::default();
+        let mut layouts = Vec::with_capacity(definitions.len());
+        let mut shapes = Vec::with_capacity(definitions.len());
+        let mut connected_shapes = Vec::with_capacity(definitions.len());
+        for (index, definition) in definitions.iter().enumerate() {
+            let id = BlockId(index as u16);
+            Self::validate(definition)?;
+            if let Some(first) = ids.insert(definition.name.clone(), id) {
+                return Err(RegistryError::DuplicateName { name: definition.name.clone(), first, second: id });
+            }
+            layouts.push(StateLayout::new(&definition.properties).map_err(|count| RegistryError::TooManyStates { name: definition.name.clone(), count })?);
+            shapes.push(Facing::ALL.map(|orientation| ModelShape::new(&definition.model, orientation)));
+            connected_shapes.push(match definition.model.connects() {
+                true => (0..Connections::COUNT).map(|index| ModelShape::connected(&definition.model, Connections::from_index(index))).collect(),
+                false => Vec::new(),
+            });
+        }
+        Ok(BlockRegistry { definitions, layouts, shapes, connected_shapes, ids })
+    }
+
+    fn validate(definition: &BlockDefinition) -> Result<(), RegistryError> {
+        let out_of_range = |property: &'static str, value: u8| RegistryError::ValueOutOfRange { name: definition.name.clone(), property, value };
+        if definition.light_opacity > MAX_LIGHT_OPACITY {
+            return Err(out_of_range("light opacity", definition.light_opacity));
+        }
+        if let LightEmission::Some(level) = definition.light_emission {
+            if level > MAX_LEVEL { return Err(out_of_range("light emission", level)) }
+        }
+        if let RedstonePowerLvl::Some(level) = definition.redstone_power_lvl {
+            if level > MAX_LEVEL { return Err(out_of_range("redstone power", level)) }
+        }
+        if !definition.hardness.is_finite() || definition.hardness < 0.0 {
+            return Err(RegistryError::InvalidHardness { name: definition.name.clone(), hardness: definition.hardness });
+        }
+        if let Some(reason) = definition.model.problem() {
+            return Err(RegistryError::InvalidModel { name: definition.name.clone(), reason });
+        }
+        for (index, property) in definition.properties.iter().enumerate() {
+            let duplicate = definition.properties[..index].iter().any(|other| other.name == property.name);
+            if let Some(reason) = property.problem().or(duplicate.then_some("the property is declared twice")) {
+                return Err(RegistryError::InvalidProperty { name: definition.name.clone(), property: property.name.clone(), reason });
+            }
+        }
+        for property in definition.redstone.required_properties() {
+            if !definition.properties.iter().any(|declared| declared.name == *property) {
+                let reason = "the redstone component of the block needs this state property";
+                return Err(RegistryError::InvalidProperty { name: definition.name.clone(), property: property.to_string(), reason });
+            }
+        }
+        Ok(())
+    }
+
+    /// # Usage:
+    /// Loads every ```.ron``` and ```.json``` block definition in ```directory```, see ```BLOCK_DIRECTORY```
+    /// # Errors:
+    /// fails if a file cannot be read or parsed, if its name does not follow ```NNNN_name.ron```, if two files claim the same ID,
+    /// if the IDs have a gap or if ```from_definitions``` rejects the definitions
+    pub fn load(directory: impl AsRef<Path>) -> Result<Self, RegistryError> {
+        let directory = directory.as_ref();
+        let entries = std::fs::read_dir(directory).map_err(|error| RegistryError::Io(directory.to_path_buf(), error.to_string()))?;
+
+        let mut files = Vec::new();
+        for entry in entries {
+            let path = entry.map_err(|error| RegistryError::Io(directory.to_path_buf(), error.to_string()))?.path();
+            if !matches!(path.extension().and_then(|extension| extension.to_str()), Some("ron" | "json")) {
+                continue;
+            }
+            let (id, name) = Self::parse_file_name(&path).ok_or_else(|| RegistryError::InvalidFileName(path.clone()))?;
+            files.push((id, name, path));
+        }
+        files.sort_by(|a, b| (a.0, &a.2).cmp(&(b.0, &b.2)));
+
+        // the IDs are checked before any file is parsed, so an ordering mistake is reported as such
+        for (index, (id, _, path)) in files.iter().enumerate() {
+            if index > 0 && files[index - 1].0 == *id {
+                return Err(RegistryError::DuplicateId { id: *id, first: files[index - 1].2.clone(), second: path.clone() });
+            }
+            if *id as usize != index {
+                return Err(RegistryError::Misordered { path: path.clone(), expected: index as u16, found: *id });
+            }
+        }
+
+        let mut definitions = Vec::with_capacity(files.len());
+        for (_, name, path) in &files {
+            let definition = Self::read_definition(path)?;
+            if definition.name != *name {
+                return Err(RegistryError::NameMismatch { path: path.clone(), name: definition.name });
+            }
+            definitions.push(definition);
+        }
+        Self::from_definitions(definitions)
+    }
+
+    /// splits ```0004_grass.ron``` into ```(4, "grass")```
+    fn parse_file_name(path: &Path) -> Option<(u16, String)> {
+        let stem = path.file_stem()?.to_str()?;
+        let (id, name) = stem.split_once('_')?;
+        if id.is_empty() || !id.bytes().all(|byte| byte.is_ascii_digit()) || name.is_empty() {
+            return None;
+        }
+        Some((id.parse().ok()?, name.to_string()))
+    }
+
+    fn read_definition(path: &Path) -> Result<BlockDefinition, RegistryError> {
+        let text = std::fs::read_to_string(path).map_err(|error| RegistryError::Io(path.to_path_buf(), error.to_string()))?;
+        let parse_error = |error: String| RegistryError::Parse(path.to_path_buf(), error);
+        if path.extension().is_some_and(|extension| extension == "json") {
+            serde_json::from_str(&text).map_err(|error| parse_error(error.to_string()))
+        } else {
+            ron::from_str(&text).map_err(|error| parse_error(error.to_string()))
+        }
+    }
+
+    /// # Usage:
+    /// the amount of registered block types
+    pub fn len(&self) -> usize { self.definitions.len() }
+
+    /// # Usage:
+    /// a registry always contains at least air
+    pub fn is_empty(&self) -> bool { self.definitions.is_empty() }
+
+    /// # Usage:
+    /// every ```BlockId``` together with its ```BlockDefinition```, in ID order
+    pub fn iter(&self) -> impl Iterator<Item = (BlockId, &BlockDefinition)> {
+        self.definitions.iter().enumerate().map(|(index, definition)| (BlockId(index as u16), definition))
+    }
+
+    /// # Usage:
+    /// the ```BlockDefinition``` of a block type
+    /// # Warning:
+    /// panics if ```id``` was not handed out by this registry
+    pub fn definition(&self, id: BlockId) -> &BlockDefinition {
+        &self.definitions[id.0 as usize]
+    }
+
+    /// # Usage:
+    /// looks up the ```BlockId``` of a block type by its name
+    pub fn id(&self, name: &str) -> Option<BlockId> {
+        self.ids.get(name).copied()
+    }
+
+    /// # Usage:
+    /// a ```Block``` of the named type facing ```Facing::XPositive```, or ```None``` if no such block type exists
+    pub fn block(&self, name: &str) -> Option<Block> {
+        self.id(name).map(|id| Block::new(id, Facing::XPositive))
+    }
+
+    /// # Usage:
+    /// the amount of valid states of a block type, 1 for block types without state properties
+    pub fn state_count(&self, id: BlockId) -> usize {
+        self.layouts[id.0 as usize].state_count()
+    }
+
+    /// # Usage:
+    /// every valid state of a block type as a ```Block``` facing ```Facing::XPositive```, starting with the default state
+    pub fn states(&self, id: BlockId) -> impl Iterator<Item = Block> {
+        (0..self.state_count(id)).map(move |state| Block::new(id, Facing::XPositive).with_state(state as u16))
+    }
+
+    /// # Usage:
+    /// the ```Block``` of type ```id``` with the packed state ID ```state_id``` (see ```Block::state_id```),
+    /// or ```None``` if the type does not exist or the ID holds no valid ```Facing``` or state for it
+    pub fn block_from_state_id(&self, id: BlockId, state_id: u16) -> Option<Block> {
+        let block = Block::from_state_id(id, state_id)?;
+        let layout = self.layouts.get(id.0 as usize)?;
+        ((block.state() as usize) < layout.state_count()).then_some(block)
+    }
+
+    /// the index of the state property ```name``` of the block type of ```block```
+    fn property_index(&self, block: &Block, name: &str) -> Result<usize, StateError> {
+        self.definition(block.block_type()).properties.iter().position(|property| property.name == name)
+            .ok_or_else(|| StateError::UnknownProperty(name.to_string()))
+    }
+
+    /// # Usage:
+    /// reads the value of the state property ```name``` of ```block```
+    /// # Errors:
+    /// fails if the block type has no such property
+    pub fn property(&self, block: &Block, name: &str) -> Result<PropertyValue<'_>, StateError> {
+        let index = self.property_index(block, name)?;
+        let properties = &self.definition(block.block_type()).properties;
+        Ok(properties[index].value(self.layouts[block.block_type().0 as usize].value_index(properties, block.state(), index)))
+    }
+
+    /// # Usage:
+    /// every state property of ```block``` with its current value, in declaration order
+    pub fn properties(&self, block: &Block) -> Vec<(&str, PropertyValue<'_>)> {
+        let properties = &self.definition(block.block_type()).properties;
+        let layout = &self.layouts[block.block_type().0 as usize];
+        properties.iter().enumerate()
+            .map(|(index, property)| (property.name.as_str(), property.value(layout.value_index(properties, block.state(), index))))
+            .collect()
+    }
+
+    /// # Usage:
+    /// returns ```block``` with the state property ```name``` set to ```value```, all other properties keep their value
+    /// # Errors:
+    /// fails if the block type has no such property or the property cannot take ```value```
+    pub fn with_property(&self, block: Block, name: &str, value: PropertyValue) -> Result<Block, StateError> {
+        let index = self.property_index(&block, name)?;
+        let properties = &self.definition(block.block_type()).properties;
+        let value_index = properties[index].index_of(value)
+            .ok_or_else(|| StateError::InvalidValue { property: name.to_string(), value: value.to_string() })?;
+        Ok(block.with_state(self.layouts[block.block_type().0 as usize].with_value_index(properties, block.state(), index, value_index)))
+    }
+
+    /// # Usage:
+    /// a readable description of ```block``` like ```leaves[distance=1, persistent=false]```, e.g. for debugging
+    pub fn describe(&self, block: &Block) -> String {
+        let name = &self.definition(block.block_type()).name;
+        let properties = self.properties(block);
+        if properties.is_empty() {
+            return name.clone();
+        }
+        let values: Vec<String> = properties.iter().map(|(property, value)| format!("{property}={value}")).collect();
+        format!("{name}[{}]", values.join(", "))
+    }
+
+    /// # Usage:
+    /// the light level ```block``` emits. Block types with a ```lit``` state property (e.g. lamps) only emit while it is ```true```
+    pub fn light_emission(&self, block: &Block) -> u8 {
+        match self.definition(block.block_type()).light_emission {
+            LightEmission::Some(level) if self.property(block, "lit") != Ok(PropertyValue::Bool(false)) => level,
+            _ => 0,
+        }
+    }
+
+    /// # Usage:
+    /// the ```RenderLayer``` of a ```Block```
+    pub fn render_layer(&self, block: &Block) -> RenderLayer {
+        self.definition(block.block_type()).render_layer
+    }
+
+    /// # Usage:
+    /// the ```ModelShape``` of ```block```, turned to its ```Facing```
+    pub fn shape(&self, block: &Block) -> &ModelShape {
+        &self.shapes[block.block_type().0 as usize][block.face_direction().index()]
+    }
+
+    /// # Usage:
+    /// Returns ```true``` if a connecting ```block``` (see ```BlockModel::connects()```) reaches out to ```neighbour``` on its ```facing``` side:
+    /// fences connect to fences, panes to panes and both to the full side of any ```Solid``` block
+    pub fn connects_to(&self, block: &Block, neighbour: &Block, facing: Facing) -> bool {
+        let model = &self.definition(block.block_type()).model;
+        model.connects() && (self.definition(neighbour.block_type()).model == *model
+            || self.render_layer(neighbour) == RenderLayer::Solid && self.shape(neighbour).side(facing.opposite()).is_full())
+    }
+
+    /// # Usage:
+    /// The ```ModelShape``` of ```block``` reaching out to the blocks next to it, ```neighbour``` returns the block on a side if it is known.
+    /// Blocks that do not connect, or connect to nothing, have their plain ```shape()```.
+    pub fn connected_shape<'a>(&self, block: &Block, mut neighbour: impl FnMut(Facing) -> Option<&'a Block>) -> &ModelShape {
+        let connected = &self.connected_shapes[block.block_type().0 as usize];
+        if connected.is_empty() {
+            return self.shape(block);
+        }
+        let connections = Connections::from_fn(|facing| neighbour(facing).is_some_and(|other| self.connects_to(block, other, facing)));
+        if connections == Connections::NONE { self.shape(block) } else { &connected[connections.index()] }
+    }
+
+    /// # Usage:
+    /// Returns ```true``` if the model of ```neighbour``` covers the sides of ```block``` it touches.
+    ///
+    /// ```Solid``` neighbours cover with their whole model, ```Translucent``` ones only cover blocks of the same type (so glass panes merge into one window)
+    /// and ```Cutout``` or ```Invisible``` neighbours never cover anything.
+    pub fn covers(&self, block: &Block, neighbour: &Block) -> bool {
+        match self.render_layer(neighbour) {
+            RenderLayer::Solid => true,
+            RenderLayer::Translucent => neighbour.block_type() == block.block_type(),
+            RenderLayer::Invisible | RenderLayer::Cutout => false,
+        }
+    }
+
+    /// # Usage:
+    /// Returns the part of the ```facing``` side of ```block``` that ```neighbour``` (the block in front of that side) covers, see ```covers()```.
+    /// The neighbour counts with its plain ```shape()```, ```ChunkNeighbourhood::face_cover()``` also takes its connections into account.
+    pub fn face_cover(&self, block: &Block, neighbour: &Block, facing: Facing) -> FaceMask {
+        if self.covers(block, neighbour) { self.shape(neighbour).side(facing.opposite()) } else { FaceMask::EMPTY }
+    }
+
+    /// # Usage:
+    /// Returns ```true``` if ```neighbour``` hides the whole ```facing``` side of ```block```, see ```face_cover```.
+    /// A full block next to a slab stays visible, a slab next to a full block does not.
+    pub fn is_face_hidden_by(&self, block: &Block, neighbour: &Block, facing: Facing) -> bool {
+        self.face_cover(block, neighbour, facing).is_full()
+    }
+
+    /// # Usage:
+    /// The texture name of the world face ```facing``` of ```block```, which takes the ```Facing``` of the ```Block``` into account.
+    /// Blocks without textures return an empty name, which the ```BlockAtlas``` shows as the missing texture.
+    pub fn face_texture(&self, block: &Block, facing: Facing) -> &str {
+        self.definition(block.block_type()).textures.as_ref()
+            .map_or("", |textures| textures.faces()[facing.unrotated(block.face_direction()).index()])
+    }
+}
+
+/// # Usage:
+/// The registry of ```BLOCK_DIRECTORY```, shared by all tests
+#[cfg(test)]
+pub(crate) fn test_registry() -> &'static BlockRegistry {
+    static REGISTRY: std::sync::OnceLock<BlockRegistry> = std::sync::OnceLock::new();
+    REGISTRY.get_or_init(|| BlockRegistry::load(BLOCK_DIRECTORY).expect("the bundled block definitions are valid"))
+}
+
+/// # Usage:
+/// shorthand for a ```Block``` of the named type from ```test_registry```
+#[cfg(test)]
+pub(crate) fn test_block(name: &str) -> Block {
+    test_registry().block(name).unwrap_or_else(|| panic!("no block named '{name}'"))
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use crate::block_model::FaceRect;
+    use crate::block_state::PropertyKind;
+    use super::*;
+
+    fn definition(name: &str, render_layer: RenderLayer) -> BlockDefinition {
+        BlockDefinition {
+            name: name.to_string(),
+            textures: None,
+            render_layer,
+            light_opacity: 0,
+            light_emission: LightEmission::None,
+            redstone_power_lvl: RedstonePowerLvl::None,
+            hardness: 0.0,
+            collision: false,
+            model: BlockModel::Cube,
+            properties: Vec::new(),
+            redstone: RedstoneComponent::None,
+        }
+    }
+
+    /// writes ```files``` into a fresh directory below the system temp directory
+    fn temp_directory(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("voxel_test_blocks_{test}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        for (name, content) in files {
+            std::fs::write(directory.join(name), content).unwrap();
+        }
+        directory
+    }
+
+    const AIR: &str = "(name: \"air\", render_layer: Invisible, light_opacity: 0, light_emission: None, redstone_power_lvl: None, hardness: 0.0, collision: false)";
+    const STONE: &str = "(name: \"stone\", textures: Some(Uniform(\"stone\")), render_layer: Solid, light_opacity: 15, light_emission: None, redstone_power_lvl: None, hardness: 1.5, collision: true)";
+
+    #[test]
+    fn test_load_bundled_blocks() {
+        let registry = test_registry();
+        assert_eq!(Some(BlockId::AIR), registry.id("air"));
+        assert_eq!(Some(BlockId(1)), registry.id("stone"));
+        assert_eq!(None, registry.id("unobtainium"));
+        for (id, definition) in registry.iter() {
+            assert_eq!(Some(id), registry.id(&definition.name));
+        }
+        let redstone = registry.definition(registry.id("redstone_block").unwrap());
+        assert_eq!(LightEmission::Some(7), redstone.light_emission);
+        assert_eq!(RedstonePowerLvl::Some(15), redstone.redstone_power_lvl);
+        assert!(redstone.collision);
+        assert!(!registry.definition(BlockId::AIR).collision);
+    }
+
+    #[test]
+    fn test_light_opacity_is_independent_of_render_layer() {
+        let registry = test_registry();
+        let glass = registry.definition(test_block("glass").block_type());
+        let leaves = registry.definition(test_block("leaves").block_type());
+        assert_eq!((RenderLayer::Translucent, 0), (glass.render_layer, glass.light_opacity));
+        assert_eq!((RenderLayer::Cutout, 1), (leaves.render_layer, leaves.light_opacity));
+        assert_eq!(MAX_LIGHT_OPACITY, registry.definition(test_block("stone").block_type()).light_opacity);
+    }
+
+    #[test]
+    fn test_face_hiding() {
+        let registry = test_registry();
+        let [air, stone, glass, leaves] = ["air", "stone", "glass", "leaves"].map(test_block);
+        let hidden = |block: &Block, neighbour: &Block| registry.is_face_hidden_by(block, neighbour, Facing::XPositive);
+        // neighbouring glass blocks merge into one window, stone behind glass stays visible
+        assert!(hidden(&glass, &glass));
+        assert!(hidden(&glass, &stone));
+        assert!(!hidden(&stone, &glass));
+        // leaves are see-through, even among each other
+        assert!(!hidden(&stone, &leaves));
+        assert!(!hidden(&leaves, &leaves));
+        assert!(!hidden(&stone, &air));
+        assert!(hidden(&stone, &stone));
+    }
+
+    #[test]
+    fn test_slab_only_hides_what_it_covers() {
+        let registry = test_registry();
+        let [stone, slab] = ["stone", "stone_slab"].map(test_block);
+        // the slab covers the top of the stone below it, but only half of the side of the stone next to it
+        assert!(registry.is_face_hidden_by(&stone, &slab, Facing::YPositive));
+        assert!(!registry.is_face_hidden_by(&stone, &slab, Facing::XPositive));
+        assert!(!registry.is_face_hidden_by(&stone, &slab, Facing::YNegative));
+        // every side of the slab that touches the stone is hidden
+        for facing in Facing::ALL {
+            assert!(registry.is_face_hidden_by(&slab, &stone, facing), "{facing:?}");
+        }
+        assert!(!registry.shape(&slab).is_full_cube());
+        assert!(registry.shape(&stone).is_full_cube());
+    }
+
+    #[test]
+    fn test_fences_and_panes_connect() {
+        let registry = test_registry();
+        let [stone, slab, glass, fence, pane] = ["stone", "stone_slab", "glass", "fence", "glass_pane"].map(test_block);
+        assert!(registry.connects_to(&fence, &fence, Facing::XPositive));
+        assert!(registry.connects_to(&fence, &stone, Facing::ZNegative));
+        assert!(registry.connects_to(&pane, &pane, Facing::ZPositive));
+        // panes do not reach out to fences, glass or the half covered side of a slab
+        assert!(!registry.connects_to(&pane, &fence, Facing::XPositive));
+        assert!(!registry.connects_to(&pane, &glass, Facing::XPositive));
+        assert!(!registry.connects_to(&fence, &slab, Facing::XPositive));
+        assert!(!registry.connects_to(&stone, &stone, Facing::XPositive));
+
+        let alone = registry.connected_shape(&pane, |_| None);
+        assert_eq!(registry.shape(&pane), alone);
+        let between_stone = registry.connected_shape(&pane, |facing| matches!(facing, Facing::ZPositive | Facing::ZNegative).then_some(&stone));
+        assert_eq!(3, between_stone.boxes().len());
+        assert!(!between_stone.side(Facing::XPositive).covers(FaceRect { u: [7, 9], v: [0, 16] }));
+        assert!(between_stone.side(Facing::ZNegative).covers(FaceRect { u: [7, 9], v: [0, 16] }));
+        assert_eq!(registry.shape(&slab), registry.connected_shape(&slab, |_| Some(&stone)));
+    }
+
+    #[test]
+    fn test_grass_textures() {
+        let registry = test_registry();
+        let grass = test_block("grass");
+        assert_eq!("grass_top", registry.face_texture(&grass, Facing::YPositive));
+        assert_eq!("dirt", registry.face_texture(&grass, Facing::YNegative));
+        for facing in [Facing::XPositive, Facing::XNegative, Facing::ZPositive, Facing::ZNegative] {
+            assert_eq!("grass_side", registry.face_texture(&grass, facing));
+        }
+        assert_eq!("", registry.face_texture(&test_block("air"), Facing::YPositive));
+    }
+
+    #[test]
+    fn test_log_textures_follow_face_direction() {
+        let registry = test_registry();
+        let log = registry.id("log").unwrap();
+        let along_z = Block::new(log, Facing::ZPositive);
+        assert_eq!("log_top", registry.face_texture(&along_z, Facing::ZPositive));
+        assert_eq!("log_top", registry.face_texture(&along_z, Facing::ZNegative));
+        assert_eq!("log_side", registry.face_texture(&along_z, Facing::XPositive));
+        assert_eq!("log_side", registry.face_texture(&along_z, Facing::YPositive));
+
+        let upright = Block::new(log, Facing::YPositive);
+        assert_eq!("log_top", registry.face_texture(&upright, Facing::YPositive));
+        assert_eq!("log_side", registry.face_texture(&upright, Facing::ZNegative));
+    }
+
+    #[test]
+    fn test_load_json() {
+        let json = r#"{"name": "stone", "render_layer": "Solid", "light_opacity": 15, "light_emission": "None", "redstone_power_lvl": "None", "hardness": 1.5, "collision": true}"#;
+        let directory = temp_directory("json", &[("0000_air.ron", AIR), ("0001_stone.json", json)]);
+        let registry = BlockRegistry::load(&directory).unwrap();
+        assert_eq!(Some(BlockId(1)), registry.id("stone"));
+        assert_eq!(None, registry.definition(BlockId(1)).textures);
+    }
+
+    #[test]
+    fn test_misordered_definitions() {
+        let directory = temp_directory("gap", &[("0000_air.ron", AIR), ("0002_stone.ron", STONE)]);
+        assert_eq!(
+            Err(RegistryError::Misordered { path: directory.join("0002_stone.ron"), expected: 1, found: 2 }),
+            BlockRegistry::load(&directory).map(|_| ())
+        );
+
+        let directory = temp_directory("duplicate_id", &[("0000_air.ron", AIR), ("0001_stone.ron", STONE), ("0001_dirt.ron", STONE)]);
+        assert_eq!(
+            Err(RegistryError::DuplicateId { id: 1, first: directory.join("0001_dirt.ron"), second: directory.join("0001_stone.ron") }),
+            BlockRegistry::load(&directory).map(|_| ())
+        );
+
+        let directory = temp_directory("name", &[("0000_air.ron", AIR), ("0001_dirt.ron", STONE)]);
+        assert_eq!(
+            Err(RegistryError::NameMismatch { path: directory.join("0001_dirt.ron"), name: "stone".to_string() }),
+            BlockRegistry::load(&directory).map(|_| ())
+        );
+
+        let directory = temp_directory("file_name", &[("0000_air.ron", AIR), ("stone.ron", STONE)]);
+        assert_eq!(Err(RegistryError::InvalidFileName(directory.join("stone.ron"))), BlockRegistry::load(&directory).map(|_| ()));
+    }
+
+    #[test]
+    fn test_invalid_definitions() {
+        let stone = definition("stone", RenderLayer::Solid);
+        assert_eq!(
+            Err(RegistryError::DuplicateName { name: "stone".to_string(), first: BlockId(1), second: BlockId(2) }),
+            BlockRegistry::from_definitions(vec![definition("air", RenderLayer::Invisible), stone.clone(), stone.clone()]).map(|_| ())
+        );
+        assert_eq!(
+            Err(RegistryError::InvalidAir("stone".to_string())),
+            BlockRegistry::from_definitions(vec![stone.clone(), definition("air", RenderLayer::Invisible)]).map(|_| ())
+        );
+        assert_eq!(Err(RegistryError::InvalidAir(String::new())), BlockRegistry::from_definitions(Vec::new()).map(|_| ()));
+
+        let glowing = BlockDefinition { light_emission: LightEmission::Some(16), ..stone.clone() };
+        assert!(matches!(
+            BlockRegistry::from_definitions(vec![definition("air", RenderLayer::Invisible), glowing]),
+            Err(RegistryError::ValueOutOfRange { property: "light emission", value: 16, .. })
+        ));
+
+        let lit = PropertyDefinition { name: "lit".to_string(), kind: PropertyKind::Bool };
+        let twice = BlockDefinition { properties: vec![lit.clone(), lit], ..stone.clone() };
+        assert!(matches!(
+            BlockRegistry::from_definitions(vec![definition("air", RenderLayer::Invisible), twice]),
+            Err(RegistryError::InvalidProperty { reason: "the property is declared twice", .. })
+        ));
+        let lamp = BlockDefinition { redstone: RedstoneComponent::Lamp, ..stone.clone() };
+        assert!(matches!(
+            BlockRegistry::from_definitions(vec![definition("air", RenderLayer::Invisible), lamp]),
+            Err(RegistryError::InvalidProperty { property, .. }) if property == "lit"
+        ));
+        let wide = PropertyDefinition { name: "wide".to_string(), kind: PropertyKind::Int { min: 0, max: 255 } };
+        let huge = BlockDefinition { properties: vec![wide, PropertyDefinition { name: "lit".to_string(), kind: PropertyKind::Bool }, PropertyDefinition { name: "powered".to_string(), kind: PropertyKind::Bool }], ..stone };
+        assert_eq!(
+            Err(RegistryError::TooManyStates { name: "stone".to_string(), count: 2 * MAX_STATES }),
+            BlockRegistry::from_definitions(vec![definition("air", RenderLayer::Invisible), huge]).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn test_block_state_properties() {
+        let registry = test_registry();
+        let leaves = registry.id("leaves").unwrap();
+        // distance 1..=7 times persistent
+        assert_eq!(14, registry.state_count(leaves));
+        assert_eq!(1, registry.state_count(registry.id("stone").unwrap()));
+
+        let default = test_block("leaves");
+        assert_eq!(Ok(PropertyValue::Int(1)), registry.property(&default, "distance"));
+        assert_eq!(Ok(PropertyValue::Bool(false)), registry.property(&default, "persistent"));
+
+        let placed = registry.with_property(default, "persistent", PropertyValue::Bool(true)).unwrap();
+        let far = registry.with_property(placed, "distance", PropertyValue::Int(7)).unwrap();
+        assert_eq!(Ok(PropertyValue::Bool(true)), registry.property(&far, "persistent"));
+        assert_eq!("leaves[distance=7, persistent=true]", registry.describe(&far));
+        assert_eq!("stone", registry.describe(&test_block("stone")));
+
+        assert_eq!(Err(StateError::UnknownProperty("age".to_string())), registry.property(&default, "age"));
+        assert_eq!(
+            Err(StateError::InvalidValue { property: "distance".to_string(), value: "8".to_string() }),
+            registry.with_property(default, "distance", PropertyValue::Int(8))
+        );
+        assert!(registry.with_property(default, "distance", PropertyValue::Bool(true)).is_err());
+
+        // the facing and the power level are part of the state ID, the state of the properties is checked against the block type
+        let turned = Block::new(leaves, Facing::ZNegative).with_state(far.state()).with_power_lvl(3);
+        assert_eq!(Some(turned), registry.block_from_state_id(leaves, turned.state_id()));
+        assert_eq!(None, registry.block_from_state_id(leaves, 14 << crate::blocks::STATE_SHIFT));
+        assert_eq!(None, registry.block_from_state_id(BlockId(500), 0));
+    }
+
+    #[test]
+    fn test_lit_property_switches_emission() {
+        let registry = test_registry();
+        let lamp = test_block("redstone_lamp");
+        assert_eq!(0, registry.light_emission(&lamp));
+        assert_eq!(15, registry.light_emission(&registry.with_property(lamp, "lit", PropertyValue::Bool(true)).unwrap()));
+        // blocks without the property always emit
+        assert_eq!(7, registry.light_emission(&test_block("redstone_block")));
+        assert_eq!(0, registry.light_emission(&test_block("stone")));
+    }
+
+    #[test]
+    fn test_states_enumerate_every_combination() {
+        let registry = test_registry();
+        let leaves = registry.id("leaves").unwrap();
+        let descriptions: std::collections::HashSet<String> = registry.states(leaves).map(|block| registry.describe(&block)).collect();
+        assert_eq!(registry.state_count(leaves), descriptions.len());
+        for distance in 1..=7 {
+            for persistent in [false, true] {
+                assert!(descriptions.contains(&format!("leaves[distance={distance}, persistent={persistent}]")));
+            }
+        }
+        assert_eq!(Some(test_block("leaves")), registry.states(leaves).next());
+    }
+}
@@ -0,0 +1,259 @@
+// foreign imports
+use serde::Deserialize;
+use std::fmt;
+
+// 'self' imports
+use crate::blocks::STATE_SHIFT;
+
+/// The highest amount of states a single block type may have, so a state always fits into the state ID of a ```Block``` next to its ```Facing``` and power level
+pub const MAX_STATES: usize = 1 << (u16::BITS - STATE_SHIFT);
+
+/// # Usage:
+/// The values a block state property can take
+/// # Format:
+/// ```
+/// pub enum PropertyKind {
+///     Enum(Vec<String>),
+///     Int { min: u8, max: u8 },
+///     Bool
+/// }
+/// ```
+/// ```Int``` ranges are inclusive on both ends. The first value (the first variant, ```min``` or ```false```) is the default.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize)]
+pub enum PropertyKind {
+    Enum(Vec<String>),
+    Int { min: u8, max: u8 },
+    Bool
+}
+
+/// # Usage:
+/// A single typed state property of a block type, as declared in its block definition file.
+/// # Format:
+/// ```
+/// (name: "distance", kind: Int(min: 1, max: 7))
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize)]
+pub struct PropertyDefinition {
+    pub name: String,
+    pub kind: PropertyKind,
+}
+
+/// # Usage:
+/// The value of a block state property
+/// # Format:
+/// ```
+/// pub enum PropertyValue<'a> {
+///     Enum(&'a str),
+///     Int(u8),
+///     Bool(bool)
+/// }
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PropertyValue<'a> {
+    Enum(&'a str),
+    Int(u8),
+    Bool(bool)
+}
+
+impl fmt::Display for PropertyValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PropertyValue::Enum(variant) => write!(f, "{variant}"),
+            PropertyValue::Int(value) => write!(f, "{value}"),
+            PropertyValue::Bool(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+/// # Usage:
+/// Everything that can go wrong when reading or changing the state of a ```Block```
+/// # Format:
+/// ```
+/// pub enum StateError {
+///     UnknownProperty(String),
+///     InvalidValue { property: String, value: String }
+/// }
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum StateError {
+    UnknownProperty(String),
+    InvalidValue { property: String, value: String }
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::UnknownProperty(property) => write!(f, "the block has no state property '{property}'"),
+            StateError::InvalidValue { property, value } => write!(f, "'{value}' is not a valid value of the state property '{property}'"),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+impl PropertyDefinition {
+    /// # Usage:
+    /// the amount of values the property can take
+    pub fn value_count(&self) -> usize {
+        match &self.kind {
+            PropertyKind::Enum(variants) => variants.len(),
+            PropertyKind::Int { min, max } => (*max as usize + 1).saturating_sub(*min as usize),
+            PropertyKind::Bool => 2,
+        }
+    }
+
+    /// # Usage:
+    /// the value with the given index, ```0..value_count()```
+    pub fn value(&self, index: usize) -> PropertyValue<'_> {
+        match &self.kind {
+            PropertyKind::Enum(variants) => PropertyValue::Enum(&variants[index]),
+            PropertyKind::Int { min, .. } => PropertyValue::Int(*min + index as u8),
+            PropertyKind::Bool => PropertyValue::Bool(index == 1),
+        }
+    }
+
+    /// # Usage:
+    /// the index of ```value```, or ```None``` if the property cannot take it
+    pub fn index_of(&self, value: PropertyValue) -> Option<usize> {
+        match (&self.kind, value) {
+            (PropertyKind::Enum(variants), PropertyValue::Enum(variant)) => variants.iter().position(|candidate| candidate == variant),
+            (PropertyKind::Int { min, max }, PropertyValue::Int(value)) => (*min..=*max).contains(&value).then(|| (value - min) as usize),
+            (PropertyKind::Bool, PropertyValue::Bool(value)) => Some(value as usize),
+            _ => None,
+        }
+    }
+
+    /// # Usage:
+    /// returns why the property cannot be used, if it cannot
+    pub fn problem(&self) -> Option<&'static str> {
+        match &self.kind {
+            PropertyKind::Enum(variants) if variants.is_empty() => Some("an enum property needs at least one variant"),
+            PropertyKind::Enum(variants) if variants.iter().enumerate().any(|(index, variant)| variants[..index].contains(variant)) => Some("an enum property may not repeat a variant"),
+            PropertyKind::Int { min, max } if min > max => Some("the minimum of an int property may not exceed its maximum"),
+            _ => None,
+        }
+    }
+}
+
+/// # Usage:
+/// Packs the property values of one block type into a single state index (mixed radix, the last property changes fastest).
+///
+/// State 0 is the default state, in which every property has its first value.
+/// # Structure:
+/// ```
+/// pub struct StateLayout {
+///     strides: Vec<u32>,
+///     state_count: usize,
+/// }
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct StateLayout {
+    strides: Vec<u32>,
+    state_count: usize,
+}
+
+impl StateLayout {
+    /// # Usage:
+    /// Lays out the states of the ```properties``` in declaration order, or returns the amount of states if they do not fit into ```MAX_STATES```
+    pub fn new(properties: &[PropertyDefinition]) -> Result<Self, usize> {
+        let mut strides = vec![0; properties.len()];
+        let mut state_count = 1usize;
+        for (stride, property) in strides.iter_mut().zip(properties).rev() {
+            *stride = state_count as u32;
+            state_count = state_count.saturating_mul(property.value_count());
+            if state_count > MAX_STATES {
+                return Err(state_count);
+            }
+        }
+        Ok(StateLayout { strides, state_count })
+    }
+
+    /// # Usage:
+    /// the amount of valid states, at least 1
+    pub fn state_count(&self) -> usize { self.state_count }
+
+    /// # Usage:
+    /// the value index of the property at ```property``` (in declaration order) stored in ```state```
+    pub fn value_index(&self, properties: &[PropertyDefinition], state: u16, property: usize) -> usize {
+        state as usize / self.strides[property] as usize % properties[property].value_count()
+    }
+
+    /// # Usage:
+    /// ```state``` with the property at ```property``` set to the value with the index ```value_index```
+    pub fn with_value_index(&self, properties: &[PropertyDefinition], state: u16, property: usize, value_index: usize) -> u16 {
+        let stride = self.strides[property] as usize;
+        let current = self.value_index(properties, state, property);
+        (state as usize - current * stride + value_index * stride) as u16
+    }
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn property(name: &str, kind: PropertyKind) -> PropertyDefinition {
+        PropertyDefinition { name: name.to_string(), kind }
+    }
+
+    #[test]
+    fn test_values_round_trip() {
+        let half = property("half", PropertyKind::Enum(vec!["bottom".to_string(), "top".to_string()]));
+        let age = property("age", PropertyKind::Int { min: 2, max: 5 });
+        let lit = property("lit", PropertyKind::Bool);
+        for property in [&half, &age, &lit] {
+            for index in 0..property.value_count() {
+                assert_eq!(Some(index), property.index_of(property.value(index)));
+            }
+        }
+        assert_eq!(4, age.value_count());
+        assert_eq!(None, age.index_of(PropertyValue::Int(6)));
+        assert_eq!(None, half.index_of(PropertyValue::Enum("double")));
+        assert_eq!(None, lit.index_of(PropertyValue::Int(1)));
+        assert_eq!(PropertyValue::Bool(false), lit.value(0));
+    }
+
+    #[test]
+    fn test_invalid_properties() {
+        assert!(property("empty", PropertyKind::Enum(Vec::new())).problem().is_some());
+        assert!(property("twice", PropertyKind::Enum(vec!["a".to_string(), "a".to_string()])).problem().is_some());
+        assert!(property("backwards", PropertyKind::Int { min: 3, max: 2 }).problem().is_some());
+        assert_eq!(None, property("single", PropertyKind::Int { min: 3, max: 3 }).problem());
+    }
+
+    #[test]
+    fn test_layout_packs_every_combination_once() {
+        let properties = [
+            property("shape", PropertyKind::Enum(vec!["straight".to_string(), "inner".to_string(), "outer".to_string()])),
+            property("age", PropertyKind::Int { min: 0, max: 7 }),
+            property("lit", PropertyKind::Bool),
+        ];
+        let layout = StateLayout::new(&properties).unwrap();
+        assert_eq!(3 * 8 * 2, layout.state_count());
+
+        let mut seen = std::collections::HashSet::new();
+        for shape in 0..3 {
+            for age in 0..8 {
+                for lit in 0..2 {
+                    let mut state = 0;
+                    for (index, value) in [shape, age, lit].into_iter().enumerate() {
+                        state = layout.with_value_index(&properties, state, index, value);
+                    }
+                    assert!((state as usize) < layout.state_count());
+                    assert!(seen.insert(state));
+                    assert_eq!([shape, age, lit], [0, 1, 2].map(|index| layout.value_index(&properties, state, index)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_layout_limit() {
+        let wide = property("wide", PropertyKind::Int { min: 0, max: 255 });
+        assert_eq!(MAX_STATES, StateLayout::new(&[wide.clone(), property("lit", PropertyKind::Bool)]).unwrap().state_count());
+        assert_eq!(Err(MAX_STATES * 2), StateLayout::new(&[wide, property("lit", PropertyKind::Bool), property("powered", PropertyKind::Bool)]));
+        assert_eq!(1, StateLayout::new(&[]).unwrap().state_count());
+    }
+}
@@ -1,18 +1,34 @@
 use bevy::prelude::*;
+use serde::Deserialize;
 
 /// # Usage:
 /// This is the general Block data format which is used in the world. It is designed to minimise the data that is carried by each instance/entity
 /// 
+/// Everything besides the block type is packed into a single state ID, from the lowest bit up:
+/// ```
+/// power_lvl (4 bits) | face_direction (3 bits) | state (9 bits)
+/// ```
+/// The ```state``` packs the values of the state properties of the block type (see ```BlockRegistry::property```), 0 is the default state.
 /// # Fields:
-#[derive(Component, PartialEq, Debug, Clone, Copy)]
+#[derive(Component, PartialEq, Eq, Hash, Debug, Clone, Copy)]
 pub struct Block {
-    block_type: BlockType,
-    face_direction: Facing,
-    power_lvl: u8,
+    block_type: BlockId,
+    state_id: u16,
 }
 
+/// The bits of the state ID of a ```Block``` that hold its redstone power level
+const POWER_BITS: u32 = 4;
+/// The bits of the state ID of a ```Block``` that hold its ```Facing```
+const FACING_BITS: u32 = 3;
+/// Where the state of the properties starts in the state ID of a ```Block```, every bit above belongs to it
+pub const STATE_SHIFT: u32 = POWER_BITS + FACING_BITS;
+/// The highest redstone power level the state ID of a ```Block``` can hold
+pub const MAX_POWER_LVL: u8 = (1 << POWER_BITS) - 1;
+
 /// # Usage:
-/// This ```Facing``` enum dictates what texture to render based on the direction
+/// This ```Facing``` enum dictates what texture to render based on the direction.
+/// 
+/// It names the six faces of a block as well, every per-face table (e.g. ```rendering_const::VERTICES```) is indexed with ```Facing::index```.
 /// # Format:
 /// ```
 /// pub enum Facing {
@@ -24,7 +40,7 @@ pub struct Block {
 ///     ZNegative
 /// }
 /// ```
-#[derive(PartialEq, Debug, Clone, Copy)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
 pub enum Facing {
     XPositive,
     XNegative,
@@ -33,25 +49,108 @@ pub enum Facing {
     ZPositive,
     ZNegative
 }
+
+impl Facing {
+    /// # Usage:
+    /// every ```Facing``` in enum order
+    pub const ALL: [Facing; 6] = [Facing::XPositive, Facing::XNegative, Facing::YPositive, Facing::YNegative, Facing::ZPositive, Facing::ZNegative];
+
+    /// # Usage:
+    /// the position of the ```Facing``` in the enum, used to index per-face tables
+    pub fn index(self) -> usize { self as usize }
+
+    /// # Usage:
+    /// the unit offset the ```Facing``` points to
+    pub fn offset(self) -> IVec3 {
+        match self {
+            Facing::XPositive => IVec3::X,
+            Facing::XNegative => IVec3::NEG_X,
+            Facing::YPositive => IVec3::Y,
+            Facing::YNegative => IVec3::NEG_Y,
+            Facing::ZPositive => IVec3::Z,
+            Facing::ZNegative => IVec3::NEG_Z,
+        }
+    }
+
+    /// # Usage:
+    /// the ```Facing``` that points to ```offset```, if it is a unit offset along one axis
+    pub fn from_offset(offset: IVec3) -> Option<Facing> {
+        Facing::ALL.into_iter().find(|facing| facing.offset() == offset)
+    }
+
+    /// # Usage:
+    /// the ```Facing``` pointing the other way
+    pub fn opposite(self) -> Facing {
+        Facing::from_offset(-self.offset()).expect("every facing has an opposite")
+    }
+
+    /// # Usage:
+    /// Turns a face of a block in its own (local) space into the world, for a block whose front (```XPositive``` in local space) points to ```orientation```.
+    /// 
+    /// Horizontal orientations turn the block around the y axis, vertical ones tip its front up or down.
+    /// ```XPositive``` leaves every face where it is.
+    pub fn rotated(self, orientation: Facing) -> Facing {
+        Facing::from_offset(orientation.rotate_vector(self.offset())).expect("rotations map unit offsets to unit offsets")
+    }
+
+    /// # Usage:
+    /// Applies the rotation of ```rotated``` to any vector in the local space of a block whose front points to ```self```
+    pub fn rotate_vector(self, vector: IVec3) -> IVec3 {
+        let IVec3 { x, y, z } = vector;
+        match self {
+            Facing::XPositive => IVec3::new(x, y, z),
+            Facing::XNegative => IVec3::new(-x, y, -z),
+            Facing::ZPositive => IVec3::new(-z, y, x),
+            Facing::ZNegative => IVec3::new(z, y, -x),
+            Facing::YPositive => IVec3::new(-y, x, z),
+            Facing::YNegative => IVec3::new(y, -x, z),
+        }
+    }
+
+    /// # Usage:
+    /// The inverse of ```rotated```: the local face of a block pointing to ```orientation``` that ends up at the world face ```self```
+    pub fn unrotated(self, orientation: Facing) -> Facing {
+        Facing::ALL.into_iter().find(|local| local.rotated(orientation) == self).expect("rotations are bijective")
+    }
+}
 /// # Usage:
-/// This ```Transparency``` enum exist to mimic the classical minecraft behavior of semi-transparent blocks
+/// This ```RenderLayer``` enum decides how a block is drawn and which faces it hides, similar to the classic minecraft render layers
 /// # Format:
 /// ```
-/// pub enum Transparency {
-///     NonOpaque,
-///     Opaque
+/// pub enum RenderLayer {
+///     Invisible,
+///     Solid,
+///     Cutout,
+///     Translucent
 /// }
 /// ```
-#[derive(Clone, Copy, PartialEq, Debug)]
-pub enum Transparency {
-    NonOpaque,
-    Opaque
+/// ```Invisible``` blocks (air) have no geometry. ```Solid``` blocks are fully opaque and hide every face behind them.
+/// ```Cutout``` blocks (leaves) are either fully opaque or fully see-through per pixel, ```Translucent``` blocks (glass) are alpha blended.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
+pub enum RenderLayer {
+    Invisible,
+    Solid,
+    Cutout,
+    Translucent
 }
 
+impl RenderLayer {
+    /// # Usage:
+    /// returns ```true``` if blocks on this layer have any geometry
+    pub fn is_visible(self) -> bool { self != RenderLayer::Invisible }
+
+    /// # Usage:
+    /// returns ```true``` if the geometry of this layer goes into the alpha blended mesh of a chunk
+    pub fn is_translucent(self) -> bool { self == RenderLayer::Translucent }
+}
+
+/// The light opacity of a block that blocks all light
+pub const MAX_LIGHT_OPACITY: u8 = 15;
+
 /// # Usage:
 /// This ```LightEmission``` enum exist to mimic the classical minecraft behavior of semi-transparent blocks that emmit light, such as glowstone.
 /// 
-/// It will later be used to make the light engine
+/// The light engine (see ```light_logic::light```) spreads the block light of emitting blocks into their surroundings.
 /// # Format:
 /// ```
 /// pub enum LightEmission {
@@ -59,7 +158,7 @@ pub enum Transparency {
 ///     Some(u8)
 /// }
 /// ```
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize)]
 pub enum LightEmission {
     None,
     Some(u8)
@@ -73,75 +172,85 @@ pub enum LightEmission {
 ///     Some(u8)
 /// }
 /// ```
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize)]
 pub enum RedstonePowerLvl {
     None,
     Some(u8)
 }
 
 /// # Usage:
-/// This enumerates all listed ```BlockTypes``` to minimise the data size
-/// # Format:
-/// Just a list of Enums
-#[derive(Clone, Copy, PartialEq, Debug)]
-pub enum BlockType {
-    Air,
-    Stone,
-    Dirt,
-    RedstoneBlock
-}
-
-/// # Usage:
-/// Merges all the data that can vary from block to block into one struct
+/// The compact numeric ID of a block type. IDs are assigned by the ```BlockRegistry``` in the order of the block definition files,
+/// the properties of a block type are looked up there.
 /// # Format:
 /// ```
-/// pub struct BlockResource {
-///     pub block_type: BlockType,
-///     pub transparency: Transparency,
-///     pub light_emission: LightEmission,
-///     pub redstone_power_lvl: RedstonePowerLvl,
-/// }
+/// pub struct BlockId(pub u16);
 /// ```
-#[derive(Clone, Copy, PartialEq, Debug)]
-pub struct BlockResource {
-    pub block_type: BlockType,
-    pub transparency: Transparency,
-    pub light_emission: LightEmission,
-    pub redstone_power_lvl: RedstonePowerLvl,
-}
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Default)]
+pub struct BlockId(pub u16);
 
-/// # Usage:
-/// Merges the possible states into one ```LOOKUPTABLE``` for ease of use and repeated access
-/// # Format:
-/// ```
-/// BlockResource{ block_type: BlockType::Air, transparency: Transparency::Opaque, light_emission: LightEmission::None, redstone_power_lvl: RedstonePowerLvl::None}
-/// 
-/// ```
-/// # Warning:
-/// the items in the ```LOOKUPTABLE ``` must be in the same order as the ```BlockType``` enum
-pub const LOOKUPTABLE: [BlockResource; 4] = [
-    BlockResource{ block_type: BlockType::Air,              transparency: Transparency::Opaque,    light_emission: LightEmission::None,     redstone_power_lvl: RedstonePowerLvl::None},
-    BlockResource{ block_type: BlockType::Stone,            transparency: Transparency::NonOpaque, light_emission: LightEmission::None,     redstone_power_lvl: RedstonePowerLvl::None},
-    BlockResource{ block_type: BlockType::Dirt,             transparency: Transparency::NonOpaque, light_emission: LightEmission::None,     redstone_power_lvl: RedstonePowerLvl::None},
-    BlockResource{ block_type: BlockType::RedstoneBlock,    transparency: Transparency::NonOpaque, light_emission: LightEmission::Some(7),  redstone_power_lvl: RedstonePowerLvl::Some(15)}
-    ];
+impl BlockId {
+    /// # Usage:
+    /// air always has the first ID, so empty cells can be recognised without a ```BlockRegistry```
+    pub const AIR: BlockId = BlockId(0);
+}
 
 impl Block {
     /// # Usage:
     /// just some boilerplate code. You can also just use a filled-out ```Block``` struct
-    pub fn new(block_type: BlockType, face_direction: Facing ) -> Block { 
+    pub fn new(block_type: BlockId, face_direction: Facing ) -> Block { 
         Block { 
             block_type, 
-            face_direction, 
-            power_lvl: 0
+            state_id: (face_direction.index() as u16) << POWER_BITS
         } 
     }
 
     /// # Usage:
-    /// returns the properties of the ```BlockType``` 
-    /// # Warning: 
-    /// it assumes the ```BlockType``` and ```LOOKUPTABLE``` are in the same order
-    pub fn get_base_properties(&self) -> BlockResource{ LOOKUPTABLE[self.block_type as usize] }
+    /// rebuilds a ```Block``` from its type and packed state ID, or ```None``` if the ID holds no valid ```Facing```.
+    /// Whether the state is valid for the block type is checked by ```BlockRegistry::block_from_state_id```.
+    pub(crate) fn from_state_id(block_type: BlockId, state_id: u16) -> Option<Block> {
+        let block = Block { block_type, state_id };
+        Facing::ALL.get(block.facing_index()).map(|_| block)
+    }
+
+    /// # Usage:
+    /// returns the ```BlockId``` of the ```Block```, its properties are stored in the ```BlockRegistry```
+    pub fn block_type(&self) -> BlockId { self.block_type }
+
+    /// # Usage:
+    /// returns the ```Facing``` of the ```Block```
+    pub fn face_direction(&self) -> Facing { Facing::ALL[self.facing_index()] }
+
+    /// # Usage:
+    /// returns the current redstone power level of the ```Block```
+    pub fn power_lvl(&self) -> u8 { (self.state_id & MAX_POWER_LVL as u16) as u8 }
+
+    /// # Usage:
+    /// the same ```Block``` with another redstone power level, which is set by the redstone simulation. Levels above ```MAX_POWER_LVL``` are cut down to it.
+    pub fn with_power_lvl(self, power_lvl: u8) -> Block {
+        Block { state_id: self.state_id & !(MAX_POWER_LVL as u16) | power_lvl.min(MAX_POWER_LVL) as u16, ..self }
+    }
+
+    /// # Usage:
+    /// returns the packed state of the properties of the ```Block```, they can be read through the ```BlockRegistry```
+    pub fn state(&self) -> u16 { self.state_id >> STATE_SHIFT }
+
+    /// # Usage:
+    /// the same ```Block``` with another packed property state. It is not checked against the ```StateLayout``` of the block type,
+    /// outside of the registry ```BlockRegistry::with_property``` is the way to change the state.
+    pub(crate) fn with_state(self, state: u16) -> Block {
+        debug_assert!((state as usize) < crate::block_state::MAX_STATES, "state {state} does not fit into the state ID");
+        Block { state_id: self.state_id & ((1 << STATE_SHIFT) - 1) | state << STATE_SHIFT, ..self }
+    }
+
+    /// # Usage:
+    /// returns the whole packed state ID of the ```Block```: its power level, ```Facing``` and state
+    pub fn state_id(&self) -> u16 { self.state_id }
+
+    fn facing_index(&self) -> usize { (self.state_id >> POWER_BITS & ((1 << FACING_BITS) - 1)) as usize }
+
+    /// # Usage:
+    /// shorthand for checking against ```BlockId::AIR```
+    pub fn is_air(&self) -> bool { self.block_type == BlockId::AIR }
 }
 
 
@@ -153,39 +262,46 @@ mod tests {
 
     #[test]
     fn test_create() {
-        let test_instance = Block::new(BlockType::Air, Facing::XPositive);
-        let test_instance2 = Block {block_type: BlockType::Air, face_direction: Facing::XPositive, power_lvl: 0};
+        let test_instance = Block::new(BlockId::AIR, Facing::XPositive);
+        let test_instance2 = Block {block_type: BlockId::AIR, state_id: 0};
         assert_eq!(test_instance, test_instance2)
     }
-
-    #[test]
-    fn test_get_properties() {
-        let test_instance = Block::new(BlockType::Air, Facing::XPositive);
-        let test_type = test_instance.get_base_properties();
-        assert_eq!(BlockType::Air, test_type.block_type)
-    }
     #[test]
-    fn test_get_light_lvl_none() {
-        let test_instance = Block::new(BlockType::Air, Facing::XPositive);
-        let test_type = test_instance.get_base_properties();
-        assert_eq!(LightEmission::None, test_type.light_emission)
+    fn test_state_id_packing() {
+        for facing in Facing::ALL {
+            let block = Block::new(BlockId(3), facing).with_power_lvl(11).with_state(300);
+            assert_eq!((facing, 11, 300), (block.face_direction(), block.power_lvl(), block.state()));
+            assert_eq!(Some(block), Block::from_state_id(BlockId(3), block.state_id()));
+            // each part can be changed without touching the others
+            let changed = block.with_state(7).with_power_lvl(40);
+            assert_eq!((facing, MAX_POWER_LVL, 7), (changed.face_direction(), changed.power_lvl(), changed.state()));
+        }
+        assert_eq!(None, Block::from_state_id(BlockId(3), 6 << POWER_BITS));
     }
     #[test]
-    fn test_get_light_lvl_some() {
-        let test_instance = Block::new(BlockType::RedstoneBlock, Facing::XPositive);
-        let test_type = test_instance.get_base_properties();
-        assert_eq!(LightEmission::Some(7), test_type.light_emission)
+    fn test_is_air() {
+        assert!(Block::new(BlockId::AIR, Facing::YNegative).is_air());
+        assert!(!Block::new(BlockId(1), Facing::XPositive).is_air());
     }
     #[test]
-    fn test_get_redstone_power_lvl_none() {
-        let test_instance = Block::new(BlockType::Air, Facing::XPositive);
-        let test_type = test_instance.get_base_properties();
-        assert_eq!(RedstonePowerLvl::None, test_type.redstone_power_lvl)
+    fn test_facing_index_and_offset() {
+        for (index, facing) in Facing::ALL.into_iter().enumerate() {
+            assert_eq!(index, facing.index());
+            assert_eq!(Some(facing), Facing::from_offset(facing.offset()));
+            assert_eq!(-facing.offset(), facing.opposite().offset());
+        }
     }
     #[test]
-    fn test_get_redstone_power_lvl_some() {
-        let test_instance = Block::new(BlockType::RedstoneBlock, Facing::XPositive);
-        let test_type = test_instance.get_base_properties();
-        assert_eq!(RedstonePowerLvl::Some(15), test_type.redstone_power_lvl)
+    fn test_rotation_moves_front_to_orientation() {
+        for orientation in Facing::ALL {
+            assert_eq!(orientation, Facing::XPositive.rotated(orientation));
+            assert_eq!(orientation.opposite(), Facing::XNegative.rotated(orientation));
+            for local in Facing::ALL {
+                assert_eq!(local, local.rotated(orientation).unrotated(orientation));
+            }
+        }
+        // turning around the y axis keeps the top on top
+        assert_eq!(Facing::YPositive, Facing::YPositive.rotated(Facing::ZNegative));
+        assert_eq!(Facing::XPositive, Facing::ZPositive.rotated(Facing::ZNegative));
     }
-}
\ No newline at end of file
+}
@@ -1,48 +1,57 @@
 // crate modifiers
 #![allow(dead_code)]
 
-// foreign imports
-use bevy::prelude::Mesh;
-
 // 'self' imports
-use crate::blocks::{self, Block, BlockType};
-// #[path ="./rendering/rendering_const.rs"]
-// mod rendering_const;
-
-#[path ="./rendering/chunk_mesh_builder.rs"]
-mod chunk_mesh_builder;
-//use super::chunks::chunk_mesh_builder;
+use crate::blocks::{self, Block};
+use crate::chunk_logic::palette::PalettedStorage;
+use crate::chunk_logic::position_handling::LocalPos;
+use crate::light_logic::light::ChunkLight;
+use crate::tick_logic::scheduler::ChunkTicks;
 
 
 //chunk constants
 pub const CHUNK_SIZE_HORIZONTAL: usize = 16;
 pub const CHUNK_HEIGHT: usize = 5; 
+pub const CHUNK_VOLUME: usize = CHUNK_SIZE_HORIZONTAL * CHUNK_HEIGHT * CHUNK_SIZE_HORIZONTAL;
+
+/// # Description:
+/// The uncompressed layout of a ```Chunk```, indexed as ```[x][y][z]```
+pub type DenseBlocks = [[[blocks::Block; CHUNK_SIZE_HORIZONTAL]; CHUNK_HEIGHT]; CHUNK_SIZE_HORIZONTAL];
 
 /// # Description:
 /// This struct holds the ```Block``` struct and thus the base configuration of elements in a ```Chunk```. This struct later on needs to be saved to a file to allow for loading and saving worlds.
+///
+/// The ```Block```s are palette-compressed, so a chunk that only contains a handful of distinct ```Block```s only needs a few bits per cell.
 /// # Structure:
 /// ```
 /// pub struct Chunk {
-///     blocks: [[[blocks::Block; CHUNK_SIZE_HORIZONTAL]; CHUNK_HEIGHT]; CHUNK_SIZE_HORIZONTAL],
+///     blocks: PalettedStorage<blocks::Block>,
 /// }
 /// ```
+#[derive(Clone, Debug)]
 pub struct Chunk {
-    blocks: [[[blocks::Block; CHUNK_SIZE_HORIZONTAL]; CHUNK_HEIGHT]; CHUNK_SIZE_HORIZONTAL],
+    blocks: PalettedStorage<blocks::Block>,
 }
 
 /// # Description:
-/// This struct holds the ```Chunk``` struct along with the ```ChunkMeshBuilder``` struct. The ```ChunkMeshBuilder``` is later used to render the entity to the screen.
-/// The grouping of the ```Chunk``` and ```ChunkMeshBuilder``` makes access later on more convienient
+/// This struct holds the ```Chunk``` struct along with any other per-chunk data the world keeps around.
+/// The meshes are built from a read-only view by a ```Mesher```, so a ```ChunkComp``` can be remeshed as often as needed.
+///
+/// The ```light``` is kept up to date by the light engine (see ```light_logic::light```), a new ```ChunkComp``` starts out completely dark.
+/// The ```scheduled``` block updates of the chunk (see ```tick_logic::scheduler```) stay with it while it is unloaded.
 /// # Structure:
 /// ```
 /// pub struct ChunkComp {
 ///     chunk: Chunk,
-///     chunk_mesh: chunk_mesh_builder::ChunkMeshBuilder,
+///     light: ChunkLight,
+///     scheduled: ChunkTicks,
 /// }
 /// ```
+#[derive(Clone)]
 pub struct ChunkComp {
     chunk: Chunk,
-    chunk_mesh: chunk_mesh_builder::ChunkMeshBuilder,
+    light: ChunkLight,
+    scheduled: ChunkTicks,
 }
 
 impl Chunk {
@@ -50,113 +59,288 @@ impl Chunk {
     /// # Description:
     /// Takes in a ```Block``` struct and fills and entire chunk with said ```Block```
     pub fn new_simple(block: blocks::Block) -> Self {
-        Chunk { blocks: [[[block; CHUNK_SIZE_HORIZONTAL]; CHUNK_HEIGHT]; CHUNK_SIZE_HORIZONTAL] }
+        Chunk { blocks: PalettedStorage::new(CHUNK_VOLUME, block) }
     }
 
     /// # Description:
-    /// Does the same as ```new_simple(block: blocks::Block::new(BlockType::Air, Facing::XPositive))```
-    pub fn new_simple_stone() -> Self {
-        Chunk { blocks: [[[Block::new(BlockType::Stone, blocks::Facing::XPositive); CHUNK_SIZE_HORIZONTAL]; CHUNK_HEIGHT]; CHUNK_SIZE_HORIZONTAL] }
+    /// Compresses the uncompressed ```[x][y][z]``` layout into a ```Chunk```
+    pub fn from_dense(dense: &DenseBlocks) -> Self {
+        let mut chunk = Self::new_simple(dense[0][0][0]);
+        for (x, plane) in dense.iter().enumerate() {
+            for (y, row) in plane.iter().enumerate() {
+                for (z, block) in row.iter().enumerate() {
+                    chunk.blocks.set(Self::index(x, y, z), *block);
+                }
+            }
+        }
+        chunk
     }
-}
 
-impl ChunkComp {
-    
     /// # Description:
-    /// Creates a simple ```Chunk``` filled to the brim with ```BlockType::Stone```. It also includes an empty ```ChunkMeshBuilder``` for further processing
-    pub fn new_simple() -> Self {        
-        ChunkComp {
-            chunk: Chunk::new_simple_stone(),
-            chunk_mesh: chunk_mesh_builder::ChunkMeshBuilder::new()
+    /// Fills the ```Chunk``` cell by cell in the order of ```iter()```. Cells that ```blocks``` does not reach are ```fill```, blocks past the last cell are ignored.
+    pub fn from_cells(blocks: impl IntoIterator<Item = Block>, fill: Block) -> Self {
+        let mut chunk = Self::new_simple(fill);
+        for (index, block) in blocks.into_iter().take(CHUNK_VOLUME).enumerate() {
+            chunk.blocks.set(index, block);
         }
+        chunk
     }
 
     /// # Description:
-    /// Builds the mesh based on the ```Chunk``` data, ignoring any ```Blocks``` with ```Transparency::Opaque```.
-    /// 
-    /// This funcion uses basic culling to reduce the poly count.
-    pub fn build_mesh_culling(mut self) -> Mesh {
-        for x in 0..CHUNK_SIZE_HORIZONTAL {
-            for y in 0..CHUNK_HEIGHT {
-                for z in 0..CHUNK_SIZE_HORIZONTAL {
-
-                    // var due to repeated use
-                    let val = &mut self.chunk.blocks[x][y][z].get_base_properties().transparency;
-
-
-                    let coord = [x as u32, y as u32, z as u32];
-
-                    // makes the the code less verbose
-                    let opaque = blocks::Transparency::Opaque;
-
-                    // ignore any opaque cases
-                    // opaque blocks need to be handled seperately
-                    if *val == opaque {
-                        continue;
-                    }
-
-                    // mesh builder for the chunk
-                    if x == 0 || self.chunk.blocks[x - 1][y][z].get_base_properties().transparency == opaque {
-                        self.chunk_mesh.add_face(coord, 2);
-                    }
-                    
-                    if x == CHUNK_SIZE_HORIZONTAL-1 || self.chunk.blocks[x + 1][y][z].get_base_properties().transparency == opaque {
-                        self.chunk_mesh.add_face(coord, 3);
-                    }
-                    
-                    if y == 0 || self.chunk.blocks[x][y - 1][z].get_base_properties().transparency == opaque {
-                        self.chunk_mesh.add_face(coord, 5);
-                    }
-                    
-                    if y == CHUNK_HEIGHT-1 || self.chunk.blocks[x][y + 1][z].get_base_properties().transparency == opaque {
-                        self.chunk_mesh.add_face(coord, 0);
-                    }
-                    
-                    if z == 0 || self.chunk.blocks[x][y][z - 1].get_base_properties().transparency == opaque {
-                        self.chunk_mesh.add_face(coord, 1);
-                    }
-                    
-                    if z == CHUNK_SIZE_HORIZONTAL-1 || self.chunk.blocks[x][y][z + 1].get_base_properties().transparency == opaque {
-                        self.chunk_mesh.add_face(coord, 4);
-                    }
+    /// Unpacks the ```Chunk``` into the uncompressed ```[x][y][z]``` layout
+    pub fn to_dense(&self) -> DenseBlocks {
+        let mut dense = [[[*self.blocks.get(0); CHUNK_SIZE_HORIZONTAL]; CHUNK_HEIGHT]; CHUNK_SIZE_HORIZONTAL];
+        for (x, plane) in dense.iter_mut().enumerate() {
+            for (y, row) in plane.iter_mut().enumerate() {
+                for (z, block) in row.iter_mut().enumerate() {
+                    *block = *self.block(x, y, z);
                 }
             }
         }
+        dense
+    }
 
-        self.chunk_mesh.build()
+    /// # Description:
+    /// The amount of bits every ```Block``` in the ```Chunk``` currently occupies
+    pub fn bits_per_block(&self) -> u8 {
+        self.blocks.bits_per_index()
     }
 
     /// # Description:
-    /// Builds the mesh based on the ```Chunk``` data, ignoring any ```Blocks``` with ```Transparency::Opaque```.
-    /// 
-    /// This funcion uses basic culling to reduce the poly count.
-    pub fn build_mesh_base(mut self) -> Mesh {
-        for x in 0..CHUNK_SIZE_HORIZONTAL {
-            for y in 0..CHUNK_HEIGHT {
-                for z in 0..CHUNK_SIZE_HORIZONTAL {
+    /// Returns the ```Block``` at the local position
+    pub fn get(&self, local: LocalPos) -> &Block {
+        self.blocks.get(Self::local_index(local))
+    }
 
-                    // var due to repeated use
-                    let val = &mut self.chunk.blocks[x][y][z].get_base_properties().transparency;
+    /// # Description:
+    /// Replaces the ```Block``` at the local position and returns the ```Block``` that was there before
+    pub fn set(&mut self, local: LocalPos, block: Block) -> Block {
+        self.blocks.set(Self::local_index(local), block)
+    }
 
+    /// # Description:
+    /// Fills the box spanned by the two corners (both inclusive) with ```block```
+    pub fn fill_box(&mut self, corner_a: LocalPos, corner_b: LocalPos, block: Block) {
+        for x in corner_a.x().min(corner_b.x())..=corner_a.x().max(corner_b.x()) {
+            for y in corner_a.y().min(corner_b.y())..=corner_a.y().max(corner_b.y()) {
+                for z in corner_a.z().min(corner_b.z())..=corner_a.z().max(corner_b.z()) {
+                    self.blocks.set(Self::index(x as usize, y as usize, z as usize), block);
+                }
+            }
+        }
+    }
 
-                    let coord = [x as u32, y as u32, z as u32];
+    /// # Description:
+    /// Iterates over every cell of the ```Chunk``` as ```(local_pos, &Block)```
+    pub fn iter(&self) -> impl Iterator<Item = (LocalPos, &Block)> + '_ {
+        self.blocks.iter().enumerate().map(|(index, block)| (Self::local_from_index(index), block))
+    }
 
-                    // makes the the code less verbose
-                    let opaque = blocks::Transparency::Opaque;
+    /// # Description:
+    /// Same as ```iter()``` but skips all air cells
+    pub fn iter_non_air(&self) -> impl Iterator<Item = (LocalPos, &Block)> + '_ {
+        self.iter().filter(|(_, block)| !block.is_air())
+    }
 
-                    // ignore any opaque cases
-                    // opaque blocks need to be handled seperately
-                    if *val == opaque {
-                        continue;
-                    }
+    /// # Description:
+    /// The inverse of ```local_index()```
+    fn local_from_index(index: usize) -> LocalPos {
+        LocalPos::at(
+            (index / (CHUNK_HEIGHT * CHUNK_SIZE_HORIZONTAL)) as u32,
+            ((index / CHUNK_SIZE_HORIZONTAL) % CHUNK_HEIGHT) as u32,
+            (index % CHUNK_SIZE_HORIZONTAL) as u32
+        )
+    }
+
+    /// # Description:
+    /// The position of the local position in the packed storage, see ```index()```
+    pub(crate) fn local_index(local: LocalPos) -> usize {
+        Self::index(local.x() as usize, local.y() as usize, local.z() as usize)
+    }
+
+    /// # Description:
+    /// Maps a local coordinate to its position in the packed storage. The order matches the ```[x][y][z]``` layout of ```DenseBlocks```.
+    pub(crate) fn index(x: usize, y: usize, z: usize) -> usize {
+        (x * CHUNK_HEIGHT + y) * CHUNK_SIZE_HORIZONTAL + z
+    }
+
+    /// # Description:
+    /// Unchecked access for the mesh builders which only ever iterate over valid coordinates
+    fn block(&self, x: usize, y: usize, z: usize) -> &blocks::Block {
+        self.blocks.get(Self::index(x, y, z))
+    }
+}
+
+impl ChunkComp {
+    
+    /// # Description:
+    /// Creates a simple ```Chunk``` filled to the brim with ```block```
+    pub fn new_simple(block: Block) -> Self {
+        Self::new(Chunk::new_simple(block))
+    }
+
+    /// # Description:
+    /// Wraps an existing ```Chunk```
+    pub fn new(chunk: Chunk) -> Self {
+        ChunkComp { chunk, light: ChunkLight::new(), scheduled: ChunkTicks::new() }
+    }
+
+    /// # Description:
+    /// Read access to the underlying ```Chunk```
+    pub fn chunk(&self) -> &Chunk {
+        &self.chunk
+    }
 
-                    for i in 0..5 {
-                        self.chunk_mesh.add_face(coord, i);
-                    }
+    /// # Description:
+    /// Write access to the underlying ```Chunk```
+    pub fn chunk_mut(&mut self) -> &mut Chunk {
+        &mut self.chunk
+    }
+
+    /// # Description:
+    /// Read access to the light levels of the chunk
+    pub fn light(&self) -> &ChunkLight {
+        &self.light
+    }
+
+    /// # Description:
+    /// Write access to the light levels of the chunk. Only the light engine should change them.
+    pub fn light_mut(&mut self) -> &mut ChunkLight {
+        &mut self.light
+    }
+
+    /// # Description:
+    /// Read access to the scheduled block updates of the chunk
+    pub fn scheduled(&self) -> &ChunkTicks {
+        &self.scheduled
+    }
+
+    /// # Description:
+    /// Write access to the scheduled block updates of the chunk. Only the ```TickScheduler``` should change them.
+    pub fn scheduled_mut(&mut self) -> &mut ChunkTicks {
+        &mut self.scheduled
+    }
+
+    /// # Description:
+    /// See ```Chunk::get()```
+    pub fn get(&self, local: LocalPos) -> &Block {
+        self.chunk.get(local)
+    }
+
+    /// # Description:
+    /// See ```Chunk::set()```
+    pub fn set(&mut self, local: LocalPos, block: Block) -> Block {
+        self.chunk.set(local, block)
+    }
+
+    /// # Description:
+    /// See ```Chunk::fill_box()```
+    pub fn fill_box(&mut self, corner_a: LocalPos, corner_b: LocalPos, block: Block) {
+        self.chunk.fill_box(corner_a, corner_b, block)
+    }
+
+    /// # Description:
+    /// See ```Chunk::iter()```
+    pub fn iter(&self) -> impl Iterator<Item = (LocalPos, &Block)> + '_ {
+        self.chunk.iter()
+    }
+
+    /// # Description:
+    /// See ```Chunk::iter_non_air()```
+    pub fn iter_non_air(&self) -> impl Iterator<Item = (LocalPos, &Block)> + '_ {
+        self.chunk.iter_non_air()
+    }
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{BlockId, Facing};
+
+    // the chunk does not care what the IDs stand for, so no ```BlockRegistry``` is needed
+    const STONE: BlockId = BlockId(1);
+    const DIRT: BlockId = BlockId(2);
+    const TYPES: [BlockId; 4] = [BlockId::AIR, STONE, DIRT, BlockId(3)];
+    const FACINGS: [Facing; 6] = [Facing::XPositive, Facing::XNegative, Facing::YPositive, Facing::YNegative, Facing::ZPositive, Facing::ZNegative];
+
+    /// fills every cell with a pseudo random mix of all 24 possible ```Block```s
+    fn mixed_dense() -> DenseBlocks {
+        let mut dense = [[[Block::new(BlockId::AIR, Facing::XPositive); CHUNK_SIZE_HORIZONTAL]; CHUNK_HEIGHT]; CHUNK_SIZE_HORIZONTAL];
+        for (x, plane) in dense.iter_mut().enumerate() {
+            for (y, row) in plane.iter_mut().enumerate() {
+                for (z, block) in row.iter_mut().enumerate() {
+                    let seed = x * 31 + y * 17 + z * 7;
+                    *block = Block::new(TYPES[seed % 4], FACINGS[(seed / 4) % 6]);
                 }
             }
         }
+        dense
+    }
+
+    #[test]
+    fn test_dense_round_trip_uniform() {
+        let dense = [[[Block::new(STONE, Facing::XPositive); CHUNK_SIZE_HORIZONTAL]; CHUNK_HEIGHT]; CHUNK_SIZE_HORIZONTAL];
+        let chunk = Chunk::from_dense(&dense);
+        assert_eq!(0, chunk.bits_per_block());
+        assert_eq!(dense, chunk.to_dense());
+    }
+
+    #[test]
+    fn test_dense_round_trip_mixed() {
+        let dense = mixed_dense();
+        let chunk = Chunk::from_dense(&dense);
+        assert_eq!(8, chunk.bits_per_block());
+        assert_eq!(dense, chunk.to_dense());
+    }
+
+    #[test]
+    fn test_dense_round_trip_two_blocks() {
+        let mut dense = [[[Block::new(BlockId::AIR, Facing::XPositive); CHUNK_SIZE_HORIZONTAL]; CHUNK_HEIGHT]; CHUNK_SIZE_HORIZONTAL];
+        dense[3][2][15] = Block::new(DIRT, Facing::ZNegative);
+        let chunk = Chunk::from_dense(&dense);
+        assert_eq!(1, chunk.bits_per_block());
+        assert_eq!(dense, chunk.to_dense());
+    }
+
+    #[test]
+    fn test_get_set() {
+        let mut chunk = Chunk::new_simple(Block::new(STONE, Facing::XPositive));
+        let dirt = Block::new(DIRT, Facing::YPositive);
+        let previous = chunk.set(LocalPos::at(15, 4, 15), dirt);
+        assert_eq!(Block::new(STONE, Facing::XPositive), previous);
+        assert_eq!(&dirt, chunk.get(LocalPos::at(15, 4, 15)));
+        assert_eq!(STONE, chunk.get(LocalPos::at(15, 4, 14)).block_type());
+    }
+
+    #[test]
+    fn test_fill_box() {
+        let mut chunk = Chunk::new_simple(Block::new(BlockId::AIR, Facing::XPositive));
+        let dirt = Block::new(DIRT, Facing::XPositive);
+        chunk.fill_box(LocalPos::at(5, 3, 2), LocalPos::at(2, 1, 3), dirt);
+        let filled: Vec<LocalPos> = chunk.iter_non_air().map(|(local, _)| local).collect();
+        assert_eq!(4 * 3 * 2, filled.len());
+        assert!(filled.iter().all(|local| (2..=5).contains(&local.x()) && (1..=3).contains(&local.y()) && (2..=3).contains(&local.z())));
+    }
+
+    #[test]
+    fn test_iter_positions() {
+        let dense = mixed_dense();
+        let chunk = Chunk::from_dense(&dense);
+        assert_eq!(CHUNK_VOLUME, chunk.iter().count());
+        for (local, block) in chunk.iter() {
+            assert_eq!(dense[local.x() as usize][local.y() as usize][local.z() as usize], *block);
+            assert_eq!(block, chunk.get(local));
+        }
+    }
 
-        self.chunk_mesh.build()
+    #[test]
+    fn test_storage_order_matches_dense_layout() {
+        let dense = mixed_dense();
+        let chunk = Chunk::from_dense(&dense);
+        assert_eq!(dense[15][4][0], *chunk.block(15, 4, 0));
+        assert_eq!(dense[0][1][9], *chunk.block(0, 1, 9));
     }
-}
\ No newline at end of file
+}
@@ -1,3 +1,8 @@
 pub mod chunk;
+pub mod neighbourhood;
+pub mod palette;
 pub mod rendering;
 pub mod position_handling;
+pub mod raycast;
+pub mod world;
+pub mod world_save;
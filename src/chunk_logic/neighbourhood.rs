@@ -0,0 +1,216 @@
+// foreign imports
+use bevy::prelude::IVec3;
+
+// 'self' imports
+use crate::block_model::{FaceMask, ModelShape};
+use crate::block_registry::BlockRegistry;
+use crate::blocks::{Block, Facing, RenderLayer};
+use crate::chunk_logic::chunk::Chunk;
+use crate::chunk_logic::position_handling::{BlockPos, LocalPos};
+use crate::light_logic::light::{ChunkLight, LightChannel};
+
+/// # Description:
+/// Every offset in ```-1..=1``` along all three axes except for ```IVec3::ZERO```, i.e. the 26 cells around a cell
+pub fn surrounding_offsets() -> impl Iterator<Item = IVec3> {
+    (0..27)
+        .map(|index| IVec3::new(index / 9, index / 3 % 3, index % 3) - IVec3::ONE)
+        .filter(|offset| *offset != IVec3::ZERO)
+}
+
+/// # Description:
+/// A read-only view of a ```Chunk``` together with the 26 chunks around it (the six adjacent ones plus the edge and corner ones).
+/// This allows the mesh builders to look across chunk borders, e.g. for culling and ambient occlusion.
+///
+/// A neighbour of ```None``` marks a chunk that is not loaded (unknown). The ```BlockRegistry``` is carried along to look up the properties of the ```Block```s.
+///
+/// The light levels of the chunks are optional (see ```with_light()```), a view without them is meshed fully lit.
+/// # Structure:
+/// ```
+/// pub struct ChunkNeighbourhood<'a> {
+///     center: &'a Chunk,
+///     grid: [Option<&'a Chunk>; 27],
+///     registry: &'a BlockRegistry,
+///     light: Option<ViewLight<'a>>,
+/// }
+/// ```
+#[derive(Clone, Copy)]
+pub struct ChunkNeighbourhood<'a> {
+    center: &'a Chunk,
+    grid: [Option<&'a Chunk>; 27],
+    registry: &'a BlockRegistry,
+    light: Option<ViewLight<'a>>,
+}
+
+/// The light levels of every chunk of a ```ChunkNeighbourhood```, laid out like its chunks, and how bright the sky currently is
+#[derive(Clone, Copy)]
+struct ViewLight<'a> {
+    center: &'a ChunkLight,
+    grid: [Option<&'a ChunkLight>; 27],
+    daylight: f32,
+}
+
+impl<'a> ChunkNeighbourhood<'a> {
+
+    /// # Description:
+    /// Groups a ```Chunk``` with its six adjacent neighbours, which have to be ordered like ```Facing::ALL```. The edge and corner chunks are unknown.
+    pub fn new(center: &'a Chunk, neighbours: [Option<&'a Chunk>; 6], registry: &'a BlockRegistry) -> Self {
+        let mut grid = [None; 27];
+        for (facing, neighbour) in Facing::ALL.into_iter().zip(neighbours) {
+            grid[Self::grid_index(facing.offset())] = neighbour;
+        }
+        ChunkNeighbourhood { center, grid, registry, light: None }
+    }
+
+    /// # Description:
+    /// Groups a ```Chunk``` with all 26 chunks around it. ```neighbour_at``` is called with every chunk offset in ```-1..=1``` except for the center.
+    pub fn from_fn(center: &'a Chunk, registry: &'a BlockRegistry, mut neighbour_at: impl FnMut(IVec3) -> Option<&'a Chunk>) -> Self {
+        let mut grid = [None; 27];
+        for offset in surrounding_offsets() {
+            grid[Self::grid_index(offset)] = neighbour_at(offset);
+        }
+        ChunkNeighbourhood { center, grid, registry, light: None }
+    }
+
+    /// # Description:
+    /// Adds the light levels of the chunks to the view. ```light_at``` is called like the ```neighbour_at``` of ```from_fn()```,
+    /// ```daylight``` (```0.0..=1.0```) scales the skylight, e.g. to darken it at night.
+    pub fn with_light(mut self, center: &'a ChunkLight, daylight: f32, mut light_at: impl FnMut(IVec3) -> Option<&'a ChunkLight>) -> Self {
+        let mut grid = [None; 27];
+        for offset in surrounding_offsets() {
+            grid[Self::grid_index(offset)] = light_at(offset);
+        }
+        self.light = Some(ViewLight { center, grid, daylight });
+        self
+    }
+
+    fn grid_index(offset: IVec3) -> usize {
+        let shifted = offset + IVec3::ONE;
+        (shifted.x * 9 + shifted.y * 3 + shifted.z) as usize
+    }
+
+    /// # Description:
+    /// A view in which every neighbour is unknown
+    pub fn isolated(center: &'a Chunk, registry: &'a BlockRegistry) -> Self {
+        Self::new(center, [None; 6], registry)
+    }
+
+    /// # Description:
+    /// The ```Chunk``` in the middle of the view
+    pub fn center(&self) -> &'a Chunk {
+        self.center
+    }
+
+    /// # Description:
+    /// The ```BlockRegistry``` the ```Block```s of the view are looked up in
+    pub fn registry(&self) -> &'a BlockRegistry {
+        self.registry
+    }
+
+    /// # Description:
+    /// Returns the ```Block``` at a position relative to the origin of the center ```Chunk```.
+    ///
+    /// The position may leave the center ```Chunk``` by up to one chunk along every axis. Returns ```None``` if the position falls into an unknown chunk or is not covered by the view.
+    pub fn get(&self, local: IVec3) -> Option<&'a Block> {
+        let (offset, wrapped) = Self::locate(local)?;
+        let chunk = if offset == IVec3::ZERO { self.center } else { self.grid[Self::grid_index(offset)]? };
+        Some(chunk.get(wrapped))
+    }
+
+    /// # Description:
+    /// Returns the light level of the ```channel``` at a position relative to the origin of the center ```Chunk```, like ```get()``` does for the ```Block```.
+    /// Returns ```None``` as well if the view has no light levels.
+    pub fn light(&self, local: IVec3, channel: LightChannel) -> Option<u8> {
+        let light = self.light.as_ref()?;
+        let (offset, wrapped) = Self::locate(local)?;
+        let chunk_light = if offset == IVec3::ZERO { light.center } else { light.grid[Self::grid_index(offset)]? };
+        Some(chunk_light.level(channel, wrapped))
+    }
+
+    /// # Description:
+    /// How bright the skylight is (```0.0..=1.0```), or ```None``` if the view has no light levels
+    pub fn daylight(&self) -> Option<f32> {
+        self.light.as_ref().map(|light| light.daylight)
+    }
+
+    /// the chunk offset and the local position inside of that chunk, or ```None``` if the position is not covered by the view
+    fn locate(local: IVec3) -> Option<(IVec3, LocalPos)> {
+        // relative to the center chunk, so the chunk the position falls into is the offset to it
+        let (offset, wrapped) = BlockPos(local).split();
+        (offset.0.abs().max_element() <= 1).then_some((offset.0, wrapped))
+    }
+
+    /// # Description:
+    /// Returns ```true``` if the ```Block``` at ```local``` is rendered at all, i.e. it is known and not ```RenderLayer::Invisible```
+    pub fn is_block_visible(&self, local: IVec3) -> bool {
+        self.get(local).is_some_and(|block| self.registry.render_layer(block).is_visible())
+    }
+
+    /// # Description:
+    /// Returns ```true``` if the ```Block``` at ```local``` is known, fully opaque (```RenderLayer::Solid```) and fills the whole cell
+    pub fn is_solid(&self, local: IVec3) -> bool {
+        self.get(local).is_some_and(|block| self.registry.render_layer(block) == RenderLayer::Solid && self.registry.shape(block).is_full_cube())
+    }
+
+    /// # Description:
+    /// Returns ```true``` if the ```facing``` face of the ```Block``` at ```local``` has to be rendered.
+    ///
+    /// That is the case if the ```Block``` itself is visible and the ```Block``` in front of the face is unknown or does not hide it (see ```BlockRegistry::is_face_hidden_by```).
+    /// Only full blocks have such faces, blocks with another ```BlockModel``` are meshed box by box and always return ```false```.
+    pub fn is_face_visible(&self, local: IVec3, facing: Facing) -> bool {
+        let Some(block) = self.get(local).filter(|block| self.registry.render_layer(block).is_visible() && self.registry.shape(block).is_full_cube()) else { return false };
+        self.get(local + facing.offset()).is_none_or(|neighbour| !self.registry.is_face_hidden_by(block, neighbour, facing))
+    }
+
+    /// # Description:
+    /// The ```ModelShape``` of the ```Block``` at ```local```, connected to the known blocks next to it (see ```BlockRegistry::connected_shape```)
+    pub fn shape(&self, local: IVec3) -> Option<&'a ModelShape> {
+        let block = self.get(local)?;
+        Some(self.registry.connected_shape(block, |facing| self.get(local + facing.offset())))
+    }
+
+    /// # Description:
+    /// The part of the ```facing``` side of the ```Block``` at ```local``` that is covered by the connected shape of the ```Block``` in front of it
+    /// (see ```BlockRegistry::covers```). Nothing is covered if either ```Block``` is unknown.
+    pub fn face_cover(&self, local: IVec3, facing: Facing) -> FaceMask {
+        let neighbour_pos = local + facing.offset();
+        match (self.get(local), self.get(neighbour_pos), self.shape(neighbour_pos)) {
+            (Some(block), Some(neighbour), Some(shape)) if self.registry.covers(block, neighbour) => shape.side(facing.opposite()),
+            _ => FaceMask::EMPTY,
+        }
+    }
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_registry::{test_block, test_registry};
+
+    #[test]
+    fn test_get_across_borders() {
+        let [stone, dirt] = [test_block("stone"), test_block("dirt")];
+        let center = Chunk::new_simple(stone);
+        let dirt_chunk = Chunk::new_simple(dirt);
+        let view = ChunkNeighbourhood::new(&center, [None, Some(&dirt_chunk), None, None, Some(&dirt_chunk), None], test_registry());
+        assert_eq!(stone.block_type(), view.get(IVec3::new(15, 4, 15)).unwrap().block_type());
+        assert_eq!(dirt.block_type(), view.get(IVec3::new(-1, 0, 0)).unwrap().block_type());
+        assert_eq!(dirt.block_type(), view.get(IVec3::new(3, 2, 16)).unwrap().block_type());
+        assert_eq!(None, view.get(IVec3::new(16, 0, 0)));
+        assert_eq!(None, view.get(IVec3::new(-1, 0, 16)));
+        assert_eq!(None, view.get(IVec3::new(-17, 0, 0)));
+    }
+
+    #[test]
+    fn test_get_edges_and_corners() {
+        let [stone, dirt] = [test_block("stone"), test_block("dirt")];
+        let center = Chunk::new_simple(stone);
+        let dirt_chunk = Chunk::new_simple(dirt);
+        let view = ChunkNeighbourhood::from_fn(&center, test_registry(), |offset| (offset == IVec3::new(-1, 1, 1) || offset == IVec3::new(1, 0, -1)).then_some(&dirt_chunk));
+        assert_eq!(dirt.block_type(), view.get(IVec3::new(-1, 5, 16)).unwrap().block_type());
+        assert_eq!(dirt.block_type(), view.get(IVec3::new(16, 3, -1)).unwrap().block_type());
+        assert_eq!(stone.block_type(), view.get(IVec3::new(15, 3, 0)).unwrap().block_type());
+        assert_eq!(None, view.get(IVec3::new(16, 3, 16)));
+    }
+}
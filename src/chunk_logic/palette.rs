@@ -0,0 +1,239 @@
+/// The possible sizes of a single packed index. Every size divides 64 so an index never straddles two words.
+///
+/// The last step (16 bits) is only reached once more than 256 distinct values are stored and acts as the dense fallback.
+const BIT_STEPS: [u8; 6] = [0, 1, 2, 4, 8, 16];
+
+/// # Description:
+/// A palette-compressed container with a fixed number of cells. Every distinct value is stored once in the ```palette```,
+/// the cells themselves only store bit-packed indices into said ```palette```.
+///
+/// The indices grow from 0/1/2/4/8 bits as the ```palette``` grows. A container holding a single value needs no index data at all.
+/// # Structure:
+/// ```
+/// pub struct PalettedStorage<T> {
+///     palette: Vec<T>,
+///     ref_counts: Vec<u32>,
+///     bits_per_index: u8,
+///     data: Vec<u64>,
+///     len: usize,
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct PalettedStorage<T> {
+    palette: Vec<T>,
+    ref_counts: Vec<u32>,
+    bits_per_index: u8,
+    data: Vec<u64>,
+    len: usize,
+}
+
+impl<T: Copy + PartialEq> PalettedStorage<T> {
+
+    /// # Description:
+    /// Creates a container with ```len``` cells that all hold ```value```
+    /// # Warning:
+    /// panics if ```len``` exceeds what a 16 bit index can address
+    pub fn new(len: usize, value: T) -> Self {
+        assert!(len <= 1 << 16, "a PalettedStorage can hold at most 65536 cells");
+        PalettedStorage {
+            palette: vec![value],
+            ref_counts: vec![len as u32],
+            bits_per_index: 0,
+            data: Vec::new(),
+            len,
+        }
+    }
+
+    /// # Description:
+    /// Packs a slice of values into a new container
+    /// # Warning:
+    /// panics if ```values``` is empty
+    pub fn from_slice(values: &[T]) -> Self {
+        let mut storage = Self::new(values.len(), values[0]);
+        for (index, value) in values.iter().enumerate() {
+            storage.set(index, *value);
+        }
+        storage
+    }
+
+    /// # Description:
+    /// Unpacks the container into a plain ```Vec``` with one value per cell
+    pub fn to_vec(&self) -> Vec<T> {
+        self.iter().copied().collect()
+    }
+
+    /// # Description:
+    /// The amount of cells in the container
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// # Description:
+    /// Returns ```true``` if the container has no cells
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// # Description:
+    /// The amount of bits every cell currently uses
+    pub fn bits_per_index(&self) -> u8 {
+        self.bits_per_index
+    }
+
+    /// # Description:
+    /// The distinct values that are currently in use
+    pub fn palette(&self) -> impl Iterator<Item = &T> {
+        self.palette.iter().zip(&self.ref_counts).filter(|(_, count)| **count > 0).map(|(value, _)| value)
+    }
+
+    /// # Description:
+    /// Returns the value of the cell at ```index```
+    /// # Warning:
+    /// panics if ```index``` is out of range
+    pub fn get(&self, index: usize) -> &T {
+        assert!(index < self.len, "index {index} out of range for a PalettedStorage of length {}", self.len);
+        &self.palette[self.read_index(index)]
+    }
+
+    /// # Description:
+    /// Overwrites the cell at ```index``` and returns the previous value. Grows the index size if the ```palette``` runs out of space.
+    /// # Warning:
+    /// panics if ```index``` is out of range
+    pub fn set(&mut self, index: usize, value: T) -> T {
+        assert!(index < self.len, "index {index} out of range for a PalettedStorage of length {}", self.len);
+        let old_palette_index = self.read_index(index);
+        let old_value = self.palette[old_palette_index];
+        if old_value == value {
+            return old_value;
+        }
+
+        self.ref_counts[old_palette_index] -= 1;
+        let new_palette_index = self.palette_index_for(value);
+        self.ref_counts[new_palette_index] += 1;
+        self.write_index(index, new_palette_index);
+        old_value
+    }
+
+    /// # Description:
+    /// Iterates over all cells in index order
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        (0..self.len).map(|index| &self.palette[self.read_index(index)])
+    }
+
+    /// # Description:
+    /// Finds the ```palette``` slot of ```value```, reusing unused slots before growing the ```palette```
+    fn palette_index_for(&mut self, value: T) -> usize {
+        if let Some(position) = self.palette.iter().position(|entry| *entry == value) {
+            return position;
+        }
+        if let Some(position) = self.ref_counts.iter().position(|count| *count == 0) {
+            self.palette[position] = value;
+            return position;
+        }
+
+        self.palette.push(value);
+        self.ref_counts.push(0);
+        if self.palette.len() > 1 << self.bits_per_index {
+            self.grow();
+        }
+        self.palette.len() - 1
+    }
+
+    /// # Description:
+    /// Repacks all indices with the next bigger index size
+    fn grow(&mut self) {
+        let new_bits = *BIT_STEPS.iter()
+            .find(|bits| 1usize << **bits >= self.palette.len())
+            .expect("palette can never outgrow 16 bit indices");
+
+        let indices: Vec<usize> = (0..self.len).map(|index| self.read_index(index)).collect();
+        self.bits_per_index = new_bits;
+        let per_word = 64 / new_bits as usize;
+        self.data = vec![0; self.len.div_ceil(per_word)];
+        for (index, palette_index) in indices.into_iter().enumerate() {
+            self.write_index(index, palette_index);
+        }
+    }
+
+    fn read_index(&self, index: usize) -> usize {
+        if self.bits_per_index == 0 {
+            return 0;
+        }
+        let bits = self.bits_per_index as usize;
+        let per_word = 64 / bits;
+        let shift = (index % per_word) * bits;
+        ((self.data[index / per_word] >> shift) & ((1u64 << bits) - 1)) as usize
+    }
+
+    fn write_index(&mut self, index: usize, palette_index: usize) {
+        if self.bits_per_index == 0 {
+            return;
+        }
+        let bits = self.bits_per_index as usize;
+        let per_word = 64 / bits;
+        let shift = (index % per_word) * bits;
+        let mask = ((1u64 << bits) - 1) << shift;
+        let word = &mut self.data[index / per_word];
+        *word = (*word & !mask) | ((palette_index as u64) << shift);
+    }
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uniform_needs_no_index_data() {
+        let storage = PalettedStorage::new(1280, 7u8);
+        assert_eq!(0, storage.bits_per_index());
+        assert!(storage.data.is_empty());
+        assert!(storage.iter().all(|value| *value == 7));
+    }
+
+    #[test]
+    fn test_index_size_grows_with_palette() {
+        let mut storage = PalettedStorage::new(1280, 0u16);
+        let mut expected_bits = Vec::new();
+        for value in 1..300u16 {
+            storage.set(value as usize, value);
+            expected_bits.push(storage.bits_per_index());
+        }
+        assert_eq!(1, expected_bits[0]);
+        assert_eq!(2, expected_bits[1]);
+        assert_eq!(2, expected_bits[2]);
+        assert_eq!(4, expected_bits[3]);
+        assert_eq!(8, expected_bits[15]);
+        assert_eq!(8, expected_bits[254]);
+        assert_eq!(16, expected_bits[255]);
+        assert!((1..300).all(|value| *storage.get(value) == value as u16));
+    }
+
+    #[test]
+    fn test_set_returns_previous_value() {
+        let mut storage = PalettedStorage::new(16, 'a');
+        assert_eq!('a', storage.set(3, 'b'));
+        assert_eq!('b', storage.set(3, 'c'));
+        assert_eq!('c', *storage.get(3));
+        assert_eq!('a', *storage.get(2));
+    }
+
+    #[test]
+    fn test_unused_palette_slots_are_reused() {
+        let mut storage = PalettedStorage::new(64, 0u8);
+        storage.set(0, 1);
+        storage.set(0, 2);
+        storage.set(0, 3);
+        assert_eq!(2, storage.palette().count());
+        assert_eq!(1, storage.bits_per_index());
+    }
+
+    #[test]
+    fn test_slice_round_trip() {
+        let values: Vec<u32> = (0..1000).map(|i| (i * 7919) % 37).collect();
+        let storage = PalettedStorage::from_slice(&values);
+        assert_eq!(values, storage.to_vec());
+    }
+}
@@ -1,35 +1,321 @@
-// due to the way f32 works there are coordinates such as (-0,.,.) which ruins any simple calculations regarding positioning
-// this file aims to fix this
+// the three integer coordinate spaces of the world and the exact conversions between them and world (float) positions
+// block and chunk positions are never rounded from floats directly, so negative coordinates and -0.0 need no special cases
 
-use bevy::math::f32::Vec3;
+// foreign imports
+use std::fmt;
+use std::ops::Add;
+use bevy::math::{IVec3, UVec3, Vec3};
+use serde::{Deserialize, Serialize};
 
+// 'self' imports
+use crate::blocks::Facing;
 use crate::chunk_logic::chunk;
 
-pub fn chunk_translation_fix(mut translation: Vec3) -> Vec3 {
-    if translation.x < 0.0 {
-        translation.x = translation.x - 1.0
+/// The size of a chunk along every axis as a vector
+pub const CHUNK_DIMENSIONS: IVec3 = IVec3::new(chunk::CHUNK_SIZE_HORIZONTAL as i32, chunk::CHUNK_HEIGHT as i32, chunk::CHUNK_SIZE_HORIZONTAL as i32);
+
+/// # Description:
+/// The integer position of a block in the world. The block at ```BlockPos(IVec3::ZERO)``` fills the unit cube from world position ```(0, 0, 0)``` to ```(1, 1, 1)```.
+/// # Format:
+/// ```
+/// pub struct BlockPos(pub IVec3);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+pub struct BlockPos(pub IVec3);
+
+/// # Description:
+/// The integer position of a chunk, counted in chunks. The chunk at ```ChunkPos(IVec3::ZERO)``` contains the blocks from ```(0, 0, 0)``` to ```CHUNK_DIMENSIONS - 1```.
+/// # Format:
+/// ```
+/// pub struct ChunkPos(pub IVec3);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+pub struct ChunkPos(pub IVec3);
+
+/// # Description:
+/// The position of a block relative to the origin of its chunk, always inside of ```CHUNK_DIMENSIONS```
+/// # Format:
+/// ```
+/// pub struct LocalPos(UVec3);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct LocalPos(UVec3);
+
+impl BlockPos {
+
+    /// # Description:
+    /// The block at the origin of the world
+    pub const ZERO: BlockPos = BlockPos(IVec3::ZERO);
+
+    /// # Description:
+    /// The block at the given block coordinates
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        BlockPos(IVec3::new(x, y, z))
+    }
+
+    /// # Description:
+    /// The block next to this one on its ```facing``` side
+    pub fn neighbour(self, facing: Facing) -> BlockPos {
+        self + facing.offset()
+    }
+
+    /// # Description:
+    /// The block that contains the world position, points on a face between two blocks belong to the block on the positive side
+    pub fn from_world(translation: Vec3) -> Self {
+        BlockPos(translation.floor().as_ivec3())
+    }
+
+    /// # Description:
+    /// The chunk that contains the block
+    pub fn chunk(self) -> ChunkPos {
+        ChunkPos(IVec3::new(
+            self.0.x.div_euclid(CHUNK_DIMENSIONS.x),
+            self.0.y.div_euclid(CHUNK_DIMENSIONS.y),
+            self.0.z.div_euclid(CHUNK_DIMENSIONS.z)
+        ))
+    }
+
+    /// # Description:
+    /// The position of the block inside of its chunk
+    pub fn local(self) -> LocalPos {
+        LocalPos(UVec3::new(
+            self.0.x.rem_euclid(CHUNK_DIMENSIONS.x) as u32,
+            self.0.y.rem_euclid(CHUNK_DIMENSIONS.y) as u32,
+            self.0.z.rem_euclid(CHUNK_DIMENSIONS.z) as u32
+        ))
+    }
+
+    /// # Description:
+    /// Splits the block position into its chunk and the position inside of it, see ```ChunkPos::block()``` for the inverse
+    pub fn split(self) -> (ChunkPos, LocalPos) {
+        (self.chunk(), self.local())
+    }
+
+    /// # Description:
+    /// The world position of the lowest corner of the block
+    pub fn to_world(self) -> Vec3 {
+        self.0.as_vec3()
+    }
+
+    /// # Description:
+    /// The world position of the center of the block
+    pub fn center(self) -> Vec3 {
+        self.to_world() + Vec3::splat(0.5)
+    }
+}
+
+impl ChunkPos {
+
+    /// # Description:
+    /// The chunk at the origin of the world
+    pub const ZERO: ChunkPos = ChunkPos(IVec3::ZERO);
+
+    /// # Description:
+    /// The chunk at the given chunk coordinates
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        ChunkPos(IVec3::new(x, y, z))
+    }
+
+    /// # Description:
+    /// The chunk that contains the world position
+    pub fn from_world(translation: Vec3) -> Self {
+        BlockPos::from_world(translation).chunk()
+    }
+
+    /// # Description:
+    /// The block at the origin (lowest corner) of the chunk
+    pub fn origin(self) -> BlockPos {
+        BlockPos(self.0 * CHUNK_DIMENSIONS)
+    }
+
+    /// # Description:
+    /// The block at the position ```local``` inside of the chunk
+    pub fn block(self, local: LocalPos) -> BlockPos {
+        BlockPos(self.origin().0 + local.0.as_ivec3())
+    }
+
+    /// # Description:
+    /// The world position of the lowest corner of the chunk, this is where the chunk meshes are placed
+    pub fn to_world(self) -> Vec3 {
+        self.origin().to_world()
+    }
+}
+
+impl LocalPos {
+
+    /// # Description:
+    /// Returns ```None``` if ```local``` lies outside of ```CHUNK_DIMENSIONS```
+    pub fn new(local: UVec3) -> Option<Self> {
+        local.cmplt(CHUNK_DIMENSIONS.as_uvec3()).all().then_some(LocalPos(local))
+    }
+
+    /// # Description:
+    /// The position ```(x, y, z)``` inside of the chunk, for coordinates that are known to be inside of it
+    /// # Panics:
+    /// if the position lies outside of ```CHUNK_DIMENSIONS```, see ```new()``` for positions that may not be
+    pub const fn at(x: u32, y: u32, z: u32) -> Self {
+        assert!(x < CHUNK_DIMENSIONS.x as u32 && y < CHUNK_DIMENSIONS.y as u32 && z < CHUNK_DIMENSIONS.z as u32, "local position outside of the chunk");
+        LocalPos(UVec3::new(x, y, z))
+    }
+
+    /// # Description:
+    /// The x coordinate inside of the chunk
+    pub fn x(self) -> u32 {
+        self.0.x
     }
-    if translation.y < 0.0 {
-        translation.y = translation.y - 1.0
+
+    /// # Description:
+    /// The y coordinate inside of the chunk
+    pub fn y(self) -> u32 {
+        self.0.y
     }
-    if translation.z < 0.0 {
-        translation.z = translation.z - 1.0
+
+    /// # Description:
+    /// The z coordinate inside of the chunk
+    pub fn z(self) -> u32 {
+        self.0.z
     }
-    translation
 }
 
-pub fn to_chunk_coordinates(translation: Vec3) -> Vec3 {
-    let offset_chunk_grid = chunk::CHUNK_SIZE_HORIZONTAL as f32;
-    let mut return_vec = Vec3::new(translation.x.abs().div_euclid(offset_chunk_grid), translation.y.abs().div_euclid(offset_chunk_grid), translation.y.abs().div_euclid(offset_chunk_grid));
+/// moves the block by an offset counted in blocks
+impl Add<IVec3> for BlockPos {
+    type Output = BlockPos;
+    fn add(self, offset: IVec3) -> BlockPos { BlockPos(self.0 + offset) }
+}
+
+/// moves the chunk by an offset counted in chunks
+impl Add<IVec3> for ChunkPos {
+    type Output = ChunkPos;
+    fn add(self, offset: IVec3) -> ChunkPos { ChunkPos(self.0 + offset) }
+}
+
+impl From<IVec3> for BlockPos {
+    fn from(block_pos: IVec3) -> Self { BlockPos(block_pos) }
+}
+
+impl From<BlockPos> for IVec3 {
+    fn from(block_pos: BlockPos) -> Self { block_pos.0 }
+}
+
+impl From<IVec3> for ChunkPos {
+    fn from(chunk_pos: IVec3) -> Self { ChunkPos(chunk_pos) }
+}
 
-    if translation.x < 0.0 {
-        return_vec.x = -return_vec.x - 1.0
+impl From<ChunkPos> for IVec3 {
+    fn from(chunk_pos: ChunkPos) -> Self { chunk_pos.0 }
+}
+
+impl From<LocalPos> for UVec3 {
+    fn from(local: LocalPos) -> Self { local.0 }
+}
+
+impl fmt::Display for BlockPos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
     }
-    if translation.y < 0.0 {
-        return_vec.y = -return_vec.y - 1.0
+}
+
+impl fmt::Display for ChunkPos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
     }
-    if translation.z < 0.0 {
-        return_vec.z = -return_vec.z - 1.0
+}
+
+impl fmt::Display for LocalPos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
     }
-    return_vec
-}
\ No newline at end of file
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use super::*;
+
+    /// block coordinates far enough out to cross many chunks, but small enough to stay exact as ```f32```
+    const RANGE: std::ops::Range<i32> = -1_000_000..1_000_000;
+
+    fn block_pos() -> impl Strategy<Value = BlockPos> {
+        (RANGE, RANGE, RANGE).prop_map(|(x, y, z)| BlockPos::new(x, y, z))
+    }
+
+    #[test]
+    fn test_negative_coordinates() {
+        // -0.0 and small negative values used to end up in the wrong block
+        assert_eq!(BlockPos(IVec3::ZERO), BlockPos::from_world(Vec3::new(-0.0, 0.0, -0.0)));
+        assert_eq!(BlockPos::new(-1, 0, -1), BlockPos::from_world(Vec3::new(-0.01, 0.5, -0.99)));
+        assert_eq!(BlockPos::new(-2, -1, 3), BlockPos::from_world(Vec3::new(-1.5, -1.0, 3.0)));
+
+        assert_eq!(ChunkPos(IVec3::new(-1, -1, -1)), BlockPos::new(-1, -1, -1).chunk());
+        assert_eq!(UVec3::new(15, 4, 15), BlockPos::new(-1, -1, -1).local().0);
+        assert_eq!(ChunkPos(IVec3::new(-2, 0, 1)), BlockPos::new(-17, 4, 16).chunk());
+        assert_eq!(UVec3::new(15, 4, 0), BlockPos::new(-17, 4, 16).local().0);
+    }
+
+    #[test]
+    fn test_each_axis_uses_its_own_coordinate() {
+        // the z coordinate used to be computed from y
+        assert_eq!(ChunkPos(IVec3::new(0, 0, -2)), ChunkPos::from_world(Vec3::new(0.0, 0.0, -20.0)));
+        assert_eq!(ChunkPos(IVec3::new(1, 2, 3)), ChunkPos::from_world(Vec3::new(16.0, 10.0, 48.5)));
+        assert_eq!(Vec3::new(16.0, 10.0, 48.0), ChunkPos(IVec3::new(1, 2, 3)).to_world());
+    }
+
+    #[test]
+    fn test_local_bounds() {
+        assert!(LocalPos::new(UVec3::new(15, 4, 15)).is_some());
+        assert!(LocalPos::new(UVec3::new(16, 0, 0)).is_none());
+        assert!(LocalPos::new(UVec3::new(0, 5, 0)).is_none());
+        assert!(LocalPos::new(UVec3::new(0, 0, u32::MAX)).is_none());
+        assert_eq!(Some(LocalPos::at(15, 4, 15)), LocalPos::new(UVec3::new(15, 4, 15)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_local_at_outside_panics() {
+        LocalPos::at(0, 5, 0);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_split_round_trips(block_pos in block_pos()) {
+            let (chunk_pos, local) = block_pos.split();
+            prop_assert_eq!(block_pos, chunk_pos.block(local));
+            prop_assert_eq!(Some(local), LocalPos::new(local.0));
+        }
+
+        #[test]
+        fn prop_chunk_borders(block_pos in block_pos()) {
+            // the neighbour along an axis is in the next chunk exactly when the block is on the border of its chunk
+            for axis in 0..3 {
+                let mut step = IVec3::ZERO;
+                step[axis] = 1;
+                let next = BlockPos(block_pos.0 + step);
+                let on_border = block_pos.local().0[axis] as i32 == CHUNK_DIMENSIONS[axis] - 1;
+                prop_assert_eq!(on_border, next.chunk() != block_pos.chunk());
+                prop_assert_eq!(on_border, next.local().0[axis] == 0);
+                prop_assert_eq!(block_pos.chunk().0[axis] + on_border as i32, next.chunk().0[axis]);
+            }
+        }
+
+        #[test]
+        fn prop_world_positions_inside_a_block(block_pos in block_pos(), offset in (0.0f32..1.0, 0.0f32..1.0, 0.0f32..1.0)) {
+            // large coordinates have fewer fraction bits, the point must not round onto the next block
+            let point = block_pos.to_world() + Vec3::new(offset.0, offset.1, offset.2);
+            prop_assume!(point.cmplt(block_pos.to_world() + Vec3::ONE).all());
+            prop_assert_eq!(block_pos, BlockPos::from_world(point));
+            prop_assert_eq!(block_pos.chunk(), ChunkPos::from_world(point));
+            prop_assert_eq!(block_pos, BlockPos::from_world(block_pos.center()));
+        }
+
+        #[test]
+        fn prop_chunk_origin(block_pos in block_pos()) {
+            let origin = block_pos.chunk().origin();
+            prop_assert_eq!(block_pos.chunk(), origin.chunk());
+            prop_assert_eq!(UVec3::ZERO, origin.local().0);
+            prop_assert_eq!(block_pos.0 - origin.0, block_pos.local().0.as_ivec3());
+        }
+    }
+}
@@ -0,0 +1,189 @@
+// foreign imports
+use bevy::prelude::{IVec3, Vec3};
+
+// 'self' imports
+use crate::blocks::Facing;
+use crate::chunk_logic::position_handling::BlockPos;
+use crate::chunk_logic::world::VoxelWorld;
+
+/// # Description:
+/// The block a ray ran into
+/// # Structure:
+/// ```
+/// pub struct RaycastHit {
+///     pub block_pos: BlockPos,
+///     pub face: Facing,
+///     pub point: Vec3,
+///     pub distance: f32,
+/// }
+/// ```
+/// ```face``` is the face of the block the ray entered through, ```point``` lies on that face, ```distance``` from the origin of the ray.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RaycastHit {
+    pub block_pos: BlockPos,
+    pub face: Facing,
+    pub point: Vec3,
+    pub distance: f32,
+}
+
+impl RaycastHit {
+
+    /// # Description:
+    /// The block in front of the hit face, this is where a block placed against the hit goes
+    pub fn adjacent(&self) -> BlockPos {
+        self.block_pos.neighbour(self.face)
+    }
+}
+
+/// # Description:
+/// Follows a ray from ```origin``` along ```direction``` through the block grid and returns the first block that is not air within ```max_distance```.
+///
+/// This is the voxel traversal of Amanatides and Woo: the ray steps from one block to the next across whichever block boundary it reaches first,
+/// so it visits the blocks along the ray in order and each of them once.
+///
+/// The block that contains the origin is not tested, a camera inside of a block can still look out of it.
+/// Unloaded chunks are treated as empty, the ray passes through them, e.g. above the highest chunk.
+/// Returns ```None``` for a zero (or not finite) direction, and for an origin or ```max_distance``` that is not finite:
+/// the ray only stops once it is longer than ```max_distance```, through unloaded chunks it would never end.
+pub fn raycast(world: &VoxelWorld, origin: Vec3, direction: Vec3, max_distance: f32) -> Option<RaycastHit> {
+    if !origin.is_finite() || !max_distance.is_finite() {
+        return None;
+    }
+    let direction = direction.try_normalize()?;
+    let mut block_pos = BlockPos::from_world(origin).0;
+
+    let step = IVec3::new(axis_step(direction.x), axis_step(direction.y), axis_step(direction.z));
+    // the distance along the ray to the first boundary on each axis, and between two boundaries of the same axis
+    let mut next_boundary = Vec3::ZERO;
+    let mut boundary_spacing = Vec3::ZERO;
+    for axis in 0..3 {
+        if step[axis] == 0 {
+            next_boundary[axis] = f32::INFINITY;
+            boundary_spacing[axis] = f32::INFINITY;
+        } else {
+            let boundary = (block_pos[axis] + step[axis].max(0)) as f32;
+            next_boundary[axis] = (boundary - origin[axis]) / direction[axis];
+            boundary_spacing[axis] = 1.0 / direction[axis].abs();
+        }
+    }
+
+    loop {
+        let axis = if next_boundary.x <= next_boundary.y && next_boundary.x <= next_boundary.z { 0 } else if next_boundary.y <= next_boundary.z { 1 } else { 2 };
+        let distance = next_boundary[axis];
+        if distance > max_distance {
+            return None;
+        }
+        block_pos[axis] += step[axis];
+        next_boundary[axis] += boundary_spacing[axis];
+
+        if world.get_block(BlockPos(block_pos)).is_ok_and(|block| !block.is_air()) {
+            let mut entered = IVec3::ZERO;
+            entered[axis] = -step[axis];
+            let mut point = origin + direction * distance;
+            // the point is on the boundary exactly, rounding must not move it off the face
+            point[axis] = (block_pos[axis] + entered[axis].max(0)) as f32;
+            return Some(RaycastHit {
+                block_pos: BlockPos(block_pos),
+                face: Facing::from_offset(entered).expect("the ray enters through a single axis"),
+                point,
+                distance,
+            });
+        }
+    }
+}
+
+/// the direction a ray moves through the grid along one axis
+fn axis_step(direction: f32) -> i32 {
+    if direction > 0.0 { 1 } else if direction < 0.0 { -1 } else { 0 }
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use crate::block_registry::test_block;
+    use crate::chunk_logic::world::test_floor_world;
+    use super::*;
+
+    #[test]
+    fn test_straight_down() {
+        let world = test_floor_world([0]);
+        // from above the loaded chunk, through unloaded space
+        let hit = raycast(&world, Vec3::new(3.5, 12.0, 4.25), Vec3::NEG_Y, 20.0).unwrap();
+        assert_eq!(BlockPos::new(3, 1, 4), hit.block_pos);
+        assert_eq!(Facing::YPositive, hit.face);
+        assert_eq!(Vec3::new(3.5, 2.0, 4.25), hit.point);
+        assert_eq!(10.0, hit.distance);
+        assert_eq!(BlockPos::new(3, 2, 4), hit.adjacent());
+
+        // too short to reach the floor
+        assert_eq!(None, raycast(&world, Vec3::new(3.5, 12.0, 4.25), Vec3::NEG_Y, 9.9));
+        assert_eq!(None, raycast(&world, Vec3::new(3.5, 12.0, 4.25), Vec3::ZERO, 20.0));
+    }
+
+    #[test]
+    fn test_not_finite_rays_end() {
+        let world = test_floor_world([0]);
+        let origin = Vec3::new(3.5, 12.0, 4.25);
+        // each of these used to step through unloaded chunks forever
+        assert_eq!(None, raycast(&world, origin, Vec3::Y, f32::INFINITY));
+        assert_eq!(None, raycast(&world, origin, Vec3::NEG_Y, f32::NAN));
+        assert_eq!(None, raycast(&world, Vec3::new(f32::NAN, 12.0, 4.25), Vec3::X, 20.0));
+        assert_eq!(None, raycast(&world, Vec3::new(3.5, f32::INFINITY, 4.25), Vec3::NEG_Y, 20.0));
+        assert_eq!(None, raycast(&world, origin, Vec3::new(f32::NAN, -1.0, 0.0), 20.0));
+    }
+
+    #[test]
+    fn test_across_chunk_borders() {
+        let mut world = test_floor_world([-1, 0, 2]);
+        world.set_block(BlockPos::new(40, 2, 5), test_block("planks")).unwrap();
+        world.set_block(BlockPos::new(-10, 2, 5), test_block("glass")).unwrap();
+
+        // along +x over the floor of chunk 0, through the unloaded chunk 1 into chunk 2
+        let hit = raycast(&world, Vec3::new(-3.0, 2.5, 5.5), Vec3::X, 100.0).unwrap();
+        assert_eq!(BlockPos::new(40, 2, 5), hit.block_pos);
+        assert_eq!(Facing::XNegative, hit.face);
+        assert_eq!(Vec3::new(40.0, 2.5, 5.5), hit.point);
+        assert_eq!(43.0, hit.distance);
+
+        // along -x into the negative chunk
+        let hit = raycast(&world, Vec3::new(5.0, 2.5, 5.5), Vec3::NEG_X, 100.0).unwrap();
+        assert_eq!(BlockPos::new(-10, 2, 5), hit.block_pos);
+        assert_eq!(Facing::XPositive, hit.face);
+        assert_eq!(Vec3::new(-9.0, 2.5, 5.5), hit.point);
+        assert_eq!(14.0, hit.distance);
+    }
+
+    #[test]
+    fn test_diagonal_rays() {
+        let mut world = test_floor_world([0]);
+        world.set_block(BlockPos::new(6, 2, 3), test_block("stone")).unwrap();
+
+        // a steep ray reaches the floor before the side of the raised block
+        let origin = Vec3::new(2.5, 4.5, 3.5);
+        let hit = raycast(&world, origin, Vec3::new(1.0, -1.0, 0.0), 20.0).unwrap();
+        assert_eq!(BlockPos::new(5, 1, 3), hit.block_pos);
+        assert_eq!(Facing::YPositive, hit.face);
+        assert!((hit.point - Vec3::new(5.0, 2.0, 3.5)).length() < 1e-5);
+        assert!((hit.distance - 2.5 * 2f32.sqrt()).abs() < 1e-5);
+
+        // a flatter one runs into its side
+        let hit = raycast(&world, origin, Vec3::new(2.0, -1.0, 0.0), 20.0).unwrap();
+        assert_eq!(BlockPos::new(6, 2, 3), hit.block_pos);
+        assert_eq!(Facing::XNegative, hit.face);
+        assert_eq!(6.0, hit.point.x);
+        assert!((hit.distance - origin.distance(hit.point)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_origin_inside_a_block() {
+        let world = test_floor_world([0]);
+        // the stone around the origin is ignored, the next one is hit
+        let hit = raycast(&world, Vec3::new(3.5, 1.5, 3.5), Vec3::NEG_Y, 5.0).unwrap();
+        assert_eq!(BlockPos::new(3, 0, 3), hit.block_pos);
+        assert_eq!(0.5, hit.distance);
+        // and looking up out of the floor hits nothing
+        assert_eq!(None, raycast(&world, Vec3::new(3.5, 1.5, 3.5), Vec3::Y, 50.0));
+    }
+}
@@ -0,0 +1,141 @@
+// foreign imports
+use bevy::prelude::IVec3;
+
+// 'self' imports
+use crate::blocks::Facing;
+use crate::chunk_logic::neighbourhood::ChunkNeighbourhood;
+use crate::chunk_logic::rendering::rendering_const;
+
+/// The darkest ambient occlusion level, used when both side voxels of a corner are solid
+pub const AO_MIN: u8 = 0;
+/// The ambient occlusion level of a corner without any solid voxels around it
+pub const AO_MAX: u8 = 3;
+
+/// The brightness every ambient occlusion level (```AO_MIN..=AO_MAX```) is rendered with
+pub const AO_BRIGHTNESS: [f32; 4] = [0.35, 0.55, 0.75, 1.0];
+
+/// # Description:
+/// Classic voxel ambient occlusion of a single face vertex, based on the two voxels along the edges of the corner (```side_a``` and ```side_b```)
+/// and the voxel diagonally across the ```corner```. All three lie in the layer in front of the face.
+///
+/// Returns a level between ```AO_MIN``` (fully occluded) and ```AO_MAX``` (not occluded).
+pub fn vertex_ao(side_a: bool, side_b: bool, corner: bool) -> u8 {
+    if side_a && side_b {
+        // the corner voxel is hidden behind both sides either way
+        return AO_MIN;
+    }
+    AO_MAX - side_a as u8 - side_b as u8 - corner as u8
+}
+
+/// # Description:
+/// Calculates the ambient occlusion of the four vertices of a face, in the same order as ```rendering_const::VERTICES```.
+///
+/// Only solid voxels occlude. Voxels in unknown chunks count as open, so nothing is darkened before a neighbour is loaded.
+pub fn face_ao(view: &ChunkNeighbourhood, local: IVec3, facing: Facing) -> [u8; 4] {
+    let [u_axis, v_axis] = rendering_const::FACE_AXES[facing.index()];
+    let front = local + facing.offset();
+    let solid = |pos: IVec3| view.is_solid(pos);
+
+    rendering_const::VERTICES[facing.index()].map(|vertex| {
+        // the corner of the face the vertex lies on, as a step of -1 or +1 along the u and v axis
+        let mut step_u = IVec3::ZERO;
+        let mut step_v = IVec3::ZERO;
+        step_u[u_axis] = if vertex[u_axis] == 0.0 { -1 } else { 1 };
+        step_v[v_axis] = if vertex[v_axis] == 0.0 { -1 } else { 1 };
+        vertex_ao(solid(front + step_u), solid(front + step_v), solid(front + step_u + step_v))
+    })
+}
+
+/// # Description:
+/// Returns ```true``` if the quad should be split along the diagonal between vertex 0 and 3 instead of 1 and 2.
+///
+/// Splitting along the brighter diagonal keeps a single dark corner inside one triangle, which avoids the anisotropy of interpolated ambient occlusion.
+pub fn should_flip(ao: [u8; 4]) -> bool {
+    ao[0] + ao[3] > ao[1] + ao[2]
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use crate::block_registry::{test_block, test_registry};
+    use crate::chunk_logic::chunk::Chunk;
+    use crate::chunk_logic::position_handling::LocalPos;
+    use super::*;
+
+    const TOP: Facing = Facing::YPositive;
+
+    fn air_chunk_with(blocks: &[[u32; 3]]) -> Chunk {
+        let mut chunk = Chunk::new_simple(test_block("air"));
+        for pos in blocks {
+            chunk.set(LocalPos::at(pos[0], pos[1], pos[2]), test_block("stone"));
+        }
+        chunk
+    }
+
+    #[test]
+    fn test_vertex_ao_levels() {
+        assert_eq!(3, vertex_ao(false, false, false));
+        assert_eq!(2, vertex_ao(true, false, false));
+        assert_eq!(2, vertex_ao(false, false, true));
+        assert_eq!(1, vertex_ao(false, true, true));
+        assert_eq!(0, vertex_ao(true, true, false));
+        assert_eq!(0, vertex_ao(true, true, true));
+    }
+
+    #[test]
+    fn test_open_floor_is_unoccluded() {
+        let chunk = air_chunk_with(&[[5, 1, 5], [4, 1, 5], [6, 1, 5]]);
+        let view = ChunkNeighbourhood::isolated(&chunk, test_registry());
+        assert_eq!([3, 3, 3, 3], face_ao(&view, IVec3::new(5, 1, 5), TOP));
+    }
+
+    #[test]
+    fn test_wall_darkens_one_edge() {
+        // a wall block on top of the neighbour at -x
+        let chunk = air_chunk_with(&[[5, 1, 5], [4, 2, 5]]);
+        let view = ChunkNeighbourhood::isolated(&chunk, test_registry());
+        // top face vertices: (0,_,0), (0,_,1), (1,_,0), (1,_,1)
+        assert_eq!([2, 2, 3, 3], face_ao(&view, IVec3::new(5, 1, 5), TOP));
+    }
+
+    #[test]
+    fn test_single_corner_block() {
+        // only the diagonal voxel at (-x, +z) is solid
+        let chunk = air_chunk_with(&[[5, 1, 5], [4, 2, 6]]);
+        let view = ChunkNeighbourhood::isolated(&chunk, test_registry());
+        assert_eq!([3, 2, 3, 3], face_ao(&view, IVec3::new(5, 1, 5), TOP));
+    }
+
+    #[test]
+    fn test_inner_corner_is_fully_occluded() {
+        // walls at -x and +z meet above the (0,_,1) vertex
+        let chunk = air_chunk_with(&[[5, 1, 5], [4, 2, 5], [5, 2, 6]]);
+        let view = ChunkNeighbourhood::isolated(&chunk, test_registry());
+        assert_eq!([2, 0, 3, 2], face_ao(&view, IVec3::new(5, 1, 5), TOP));
+    }
+
+    #[test]
+    fn test_across_chunk_border() {
+        let chunk = air_chunk_with(&[[15, 1, 15]]);
+        let corner_chunk = air_chunk_with(&[[0, 2, 0]]);
+        // the occluding voxel sits diagonally in the chunk at (+x, 0, +z)
+        let view = ChunkNeighbourhood::from_fn(&chunk, test_registry(), |offset| (offset == IVec3::new(1, 0, 1)).then_some(&corner_chunk));
+        assert_eq!([3, 3, 3, 2], face_ao(&view, IVec3::new(15, 1, 15), TOP));
+
+        // without the neighbour the corner counts as open
+        let view = ChunkNeighbourhood::isolated(&chunk, test_registry());
+        assert_eq!([3, 3, 3, 3], face_ao(&view, IVec3::new(15, 1, 15), TOP));
+    }
+
+    #[test]
+    fn test_flip() {
+        assert!(!should_flip([3, 3, 3, 3]));
+        // a dark corner on vertex 0 stays inside the triangle (0, 1, 2)
+        assert!(!should_flip([0, 3, 3, 3]));
+        // a dark corner on vertex 1 needs the other diagonal
+        assert!(should_flip([3, 0, 3, 3]));
+        assert!(should_flip([3, 2, 2, 3]));
+    }
+}
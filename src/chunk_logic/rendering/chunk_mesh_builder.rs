@@ -1,12 +1,16 @@
 // foreign imports
-use bevy::prelude::Mesh;
+use bevy::prelude::{Mesh, Vec3};
 use bevy::render::mesh::PrimitiveTopology;
 use bevy::render::mesh::Indices;
 
 // 'self' imports
+use crate::block_model::{ModelBox, MODEL_RESOLUTION};
+use crate::blocks::{Facing, RenderLayer};
 // #[path ="./rendering_const.rs"]
 // mod rendering_const;
 use crate::chunk_logic::rendering::rendering_const;
+use crate::chunk_logic::rendering::ambient_occlusion::{self, AO_BRIGHTNESS, AO_MAX};
+use crate::chunk_logic::rendering::smooth_lighting::FULL_LIGHT;
 
 /// # Description:
 /// The struct that contains all the necessary info to render the Mesh to the screen
@@ -17,15 +21,17 @@ use crate::chunk_logic::rendering::rendering_const;
 ///     triangles: Vec<u32>,
 ///     normals: Vec<[f32; 3]>,
 ///     uvs: Vec<[f32; 2]>,
+///     colors: Vec<[f32; 4]>,
 ///     face_count:u32
 /// }
 /// ```
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct ChunkMeshBuilder {
     vertices: Vec<[f32; 3]>,
     triangles: Vec<u32>,
     normals: Vec<[f32; 3]>,
     uvs: Vec<[f32; 2]>,
+    colors: Vec<[f32; 4]>,
     face_count:u32
 }
 
@@ -38,22 +44,84 @@ impl ChunkMeshBuilder {
     }
 
     /// # Description:
-    /// Makes the additon of the two arrays far easier to work with
-    fn add_vec3(mut base: [f32; 3], addition: [u32; 3]) -> [f32; 3] {
-        for i in 0..3 {
-            base[i] += addition[i] as f32;
+    /// Adds the face pointing to ```facing``` of the block at ```coord``` to the ```Mesh```, fully lit and without any ambient occlusion
+    pub fn add_face(&mut self, coord: [u32; 3], facing: Facing, uvs: [[f32; 2]; 4]) {
+        self.add_quad(coord, facing, [1, 1], [AO_MAX; 4], [FULL_LIGHT; 4], uvs);
+    }
+
+    /// # Description:
+    /// Adds a face that spans ```size``` blocks along the u and v axes of the face (see ```rendering_const::FACE_AXES```).
+    /// ```coord``` is the block in the lowest corner of the face.
+    ///
+    /// ```uvs``` are the texture coordinates of the four vertices, usually from ```BlockAtlas::face_uvs```.
+    /// ```ao``` holds the ambient occlusion level and ```light``` the vertex light (see ```smooth_lighting::face_light```) of every vertex,
+    /// both are multiplied into the vertex color.
+    pub fn add_quad(&mut self, coord: [u32; 3], facing: Facing, size: [u32; 2], ao: [u8; 4], light: [u8; 4], uvs: [[f32; 2]; 4]) {
+        let [u_axis, v_axis] = rendering_const::FACE_AXES[facing.index()];
+        let mut scale = [1.0; 3];
+        scale[u_axis] = size[0] as f32;
+        scale[v_axis] = size[1] as f32;
+
+        let positions = rendering_const::VERTICES[facing.index()].map(|vertex| [
+            vertex[0] * scale[0] + coord[0] as f32,
+            vertex[1] * scale[1] + coord[1] as f32,
+            vertex[2] * scale[2] + coord[2] as f32
+        ]);
+        self.push_quad(positions, rendering_const::NORMALS[facing.index()], ao, light, uvs);
+    }
+
+    /// # Description:
+    /// Adds the ```facing``` face of a box of a block model (see ```ModelShape```) of the block at ```coord```, without any ambient occlusion
+    /// and with the same vertex ```light``` on all four vertices.
+    ///
+    /// ```uvs``` are the texture coordinates of the whole block face, the box face only shows the part of the texture it covers.
+    pub fn add_box_face(&mut self, coord: [u32; 3], facing: Facing, model_box: ModelBox, light: u8, uvs: [[f32; 2]; 4]) {
+        let resolution = MODEL_RESOLUTION as f32;
+        let [u_axis, v_axis] = rendering_const::FACE_AXES[facing.index()];
+        let template = rendering_const::VERTICES[facing.index()];
+
+        let mut positions = [[0.0; 3]; 4];
+        let mut box_uvs = [[0.0; 2]; 4];
+        for (index, vertex) in template.iter().enumerate() {
+            let mut fraction = [0.0; 3];
+            for axis in 0..3 {
+                let corner = if vertex[axis] == 0.0 { model_box.from[axis] } else { model_box.to[axis] };
+                fraction[axis] = corner as f32 / resolution;
+                positions[index][axis] = coord[axis] as f32 + fraction[axis];
+            }
+            // the texture runs along the face axes in the same direction as the corners of the template face
+            let along = |axis: usize, uv: usize| if (rendering_const::UVS[index][uv] == 1.0) == (vertex[axis] == 1.0) { fraction[axis] } else { 1.0 - fraction[axis] };
+            let (fu, fv) = (along(u_axis, 0), along(v_axis, 1));
+            box_uvs[index] = [
+                uvs[0][0] + (uvs[2][0] - uvs[0][0]) * fu + (uvs[1][0] - uvs[0][0]) * fv,
+                uvs[0][1] + (uvs[2][1] - uvs[0][1]) * fu + (uvs[1][1] - uvs[0][1]) * fv,
+            ];
         }
-        base
+        self.push_quad(positions, rendering_const::NORMALS[facing.index()], [AO_MAX; 4], [light; 4], box_uvs);
     }
 
     /// # Description:
-    /// Adds a new face of the entity to the ```Mesh```
-    pub fn add_face(&mut self, coord: [u32; 3], face_index: u8) {
-        for i in &rendering_const::VERTICES[face_index as usize] {
-            self.vertices.push(Self::add_vec3(*i, coord));
+    /// Adds the two crossed, double sided quads of a ```BlockModel::Cross``` (plants) in the block at ```coord```, all lit with the vertex ```light```
+    pub fn add_cross(&mut self, coord: [u32; 3], light: u8, uvs: [[f32; 2]; 4]) {
+        let origin = coord.map(|c| c as f32);
+        let at = |x: f32, y: f32, z: f32| [origin[0] + x, origin[1] + y, origin[2] + z];
+        let diagonals = [
+            [at(0.0, 0.0, 0.0), at(0.0, 1.0, 0.0), at(1.0, 0.0, 1.0), at(1.0, 1.0, 1.0)],
+            [at(0.0, 0.0, 1.0), at(0.0, 1.0, 1.0), at(1.0, 0.0, 0.0), at(1.0, 1.0, 0.0)],
+        ];
+        for [a, b, c, d] in diagonals {
+            let normal = (Vec3::from_array(b) - Vec3::from_array(a)).cross(Vec3::from_array(c) - Vec3::from_array(a)).normalize();
+            self.push_quad([a, b, c, d], normal.to_array(), [AO_MAX; 4], [light; 4], uvs);
+            // the back side swaps the two edges, which reverses the winding
+            self.push_quad([c, d, a, b], (-normal).to_array(), [AO_MAX; 4], [light; 4], [uvs[2], uvs[3], uvs[0], uvs[1]]);
         }
+    }
 
-        let mut arr=rendering_const::TRIANGLES.clone();
+    /// Adds one quad with the vertices in the order of ```rendering_const::VERTICES```
+    fn push_quad(&mut self, positions: [[f32; 3]; 4], normal: [f32; 3], ao: [u8; 4], light: [u8; 4], uvs: [[f32; 2]; 4]) {
+        self.vertices.extend_from_slice(&positions);
+
+        let mut arr=if ambient_occlusion::should_flip(ao) { rendering_const::FLIPPED_TRIANGLES } else { rendering_const::TRIANGLES };
         self.triangles.extend_from_slice({
             for i in &mut arr {
                 *i+=4*self.face_count;
@@ -62,13 +130,54 @@ impl ChunkMeshBuilder {
         });
 
         for _ in 0..4 {
-            self.normals.push(rendering_const::NORMALS[face_index as usize]);
+            self.normals.push(normal);
         }
 
-        self.uvs.extend_from_slice(&rendering_const::UVS);
+        self.uvs.extend_from_slice(&uvs);
+
+        for (level, light) in ao.into_iter().zip(light) {
+            let brightness = AO_BRIGHTNESS[level as usize] * light as f32 / FULL_LIGHT as f32;
+            self.colors.push([brightness, brightness, brightness, 1.0]);
+        }
         self.face_count+=1;
     }
 
+    /// # Description:
+    /// The amount of faces that were added so far
+    pub fn face_count(&self) -> u32 {
+        self.face_count
+    }
+
+    /// # Description:
+    /// The vertex positions added so far, four per face
+    pub fn vertices(&self) -> &[[f32; 3]] {
+        &self.vertices
+    }
+
+    /// # Description:
+    /// The vertex normals added so far, four per face
+    pub fn normals(&self) -> &[[f32; 3]] {
+        &self.normals
+    }
+
+    /// # Description:
+    /// The vertex UVs added so far, four per face
+    pub fn uvs(&self) -> &[[f32; 2]] {
+        &self.uvs
+    }
+
+    /// # Description:
+    /// The vertex colors added so far, four per face
+    pub fn colors(&self) -> &[[f32; 4]] {
+        &self.colors
+    }
+
+    /// # Description:
+    /// The triangle indices added so far, six per face
+    pub fn triangles(&self) -> &[u32] {
+        &self.triangles
+    }
+
     /// # Description:
     /// Fills the rest of the ```Mesh``` based on available data. This makes the ```Mesh``` usable by bevy
     pub fn build(self) -> Mesh {
@@ -76,8 +185,46 @@ impl ChunkMeshBuilder {
         msh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.vertices);
         msh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals);
         msh.insert_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs);
+        msh.insert_attribute(Mesh::ATTRIBUTE_COLOR, self.colors);
 
         msh.set_indices(Some(Indices::U32(self.triangles)));
         msh
     }
-}
\ No newline at end of file
+}
+
+/// # Description:
+/// The mesh data of a chunk, split by how it is drawn. ```Solid``` and ```Cutout``` faces share the alpha tested ```opaque``` mesh,
+/// ```Translucent``` faces go into the alpha blended ```translucent``` mesh.
+/// # Structure:
+/// ```
+/// pub struct ChunkMeshLayers {
+///     pub opaque: ChunkMeshBuilder,
+///     pub translucent: ChunkMeshBuilder,
+/// }
+/// ```
+#[derive(Default, Clone)]
+pub struct ChunkMeshLayers {
+    pub opaque: ChunkMeshBuilder,
+    pub translucent: ChunkMeshBuilder,
+}
+
+impl ChunkMeshLayers {
+
+    /// # Description:
+    /// Creates empty layers based on the ```Self::default()``` function
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Description:
+    /// The mesh that faces of blocks on the ```render_layer``` are added to
+    pub fn layer_mut(&mut self, render_layer: RenderLayer) -> &mut ChunkMeshBuilder {
+        if render_layer.is_translucent() { &mut self.translucent } else { &mut self.opaque }
+    }
+
+    /// # Description:
+    /// The amount of faces in both meshes
+    pub fn face_count(&self) -> u32 {
+        self.opaque.face_count() + self.translucent.face_count()
+    }
+}
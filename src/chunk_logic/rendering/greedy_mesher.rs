@@ -0,0 +1,214 @@
+// foreign imports
+use bevy::prelude::IVec3;
+
+// 'self' imports
+use crate::blocks::{Facing, RenderLayer};
+use crate::chunk_logic::chunk::{CHUNK_HEIGHT, CHUNK_SIZE_HORIZONTAL};
+use crate::chunk_logic::neighbourhood::ChunkNeighbourhood;
+use crate::chunk_logic::rendering::chunk_mesh_builder::ChunkMeshLayers;
+use crate::chunk_logic::rendering::{ambient_occlusion, rendering_const, smooth_lighting};
+use crate::chunk_logic::rendering::texture_atlas::BlockAtlas;
+
+/// the render layer, atlas tile, ambient occlusion and light of a face, only faces with the same key are merged
+type FaceKey = (RenderLayer, u32, [u8; 4], [u8; 4]);
+
+/// # Description:
+/// Adds the visible faces of the center ```Chunk``` of the ```view``` to the mesh of their render layer, merging coplanar neighbouring faces that show the same atlas tile into rectangles.
+/// Faces are only merged if their ambient occlusion and light match as well, otherwise the shading would be smeared across the whole rectangle.
+///
+/// Every face direction is handled slice by slice: the visible faces of a slice are collected in a 2D mask, which is then
+/// split into rectangles by growing each one first along the u axis and then along the v axis as far as possible.
+pub fn add_greedy_faces(view: &ChunkNeighbourhood, atlas: &BlockAtlas, layers: &mut ChunkMeshLayers) {
+    let dimensions = [CHUNK_SIZE_HORIZONTAL, CHUNK_HEIGHT, CHUNK_SIZE_HORIZONTAL];
+
+    for facing in Facing::ALL {
+        let [u_axis, v_axis] = rendering_const::FACE_AXES[facing.index()];
+        let normal_axis = 3 - u_axis - v_axis;
+        let (width, height) = (dimensions[u_axis], dimensions[v_axis]);
+
+        for slice in 0..dimensions[normal_axis] {
+            let coord_of = |u: usize, v: usize| {
+                let mut coord = [0u32; 3];
+                coord[normal_axis] = slice as u32;
+                coord[u_axis] = u as u32;
+                coord[v_axis] = v as u32;
+                coord
+            };
+
+            // the key of every visible face in this slice, None where nothing has to be drawn
+            let mut mask: Vec<Option<FaceKey>> = vec![None; width * height];
+            for v in 0..height {
+                for u in 0..width {
+                    let pos = IVec3::from_array(coord_of(u, v).map(|c| c as i32));
+                    if view.is_face_visible(pos, facing) {
+                        let ao = ambient_occlusion::face_ao(view, pos, facing);
+                        let light = smooth_lighting::face_light(view, pos, facing);
+                        mask[v * width + u] = view.get(pos).map(|block| (view.registry().render_layer(block), atlas.face_tile(view.registry(), block, facing), ao, light));
+                    }
+                }
+            }
+
+            for v in 0..height {
+                let mut u = 0;
+                while u < width {
+                    let Some(key) = mask[v * width + u] else {
+                        u += 1;
+                        continue;
+                    };
+
+                    let mut quad_width = 1;
+                    while u + quad_width < width && mask[v * width + u + quad_width] == Some(key) {
+                        quad_width += 1;
+                    }
+
+                    let mut quad_height = 1;
+                    while v + quad_height < height
+                        && mask[(v + quad_height) * width + u..(v + quad_height) * width + u + quad_width].iter().all(|cell| *cell == Some(key)) {
+                        quad_height += 1;
+                    }
+
+                    for row in v..v + quad_height {
+                        mask[row * width + u..row * width + u + quad_width].fill(None);
+                    }
+
+                    let size = [quad_width as u32, quad_height as u32];
+                    let (render_layer, tile, ao, light) = key;
+                    layers.layer_mut(render_layer).add_quad(coord_of(u, v), facing, size, ao, light, atlas.tile_uvs(tile, size));
+                    u += quad_width;
+                }
+            }
+        }
+    }
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use bevy::prelude::Mesh;
+    use bevy::render::mesh::VertexAttributeValues;
+    use crate::block_registry::{test_block, test_registry};
+    use crate::chunk_logic::chunk::{Chunk, ChunkComp};
+    use crate::chunk_logic::position_handling::LocalPos;
+    use crate::chunk_logic::rendering::mesher::{CulledMesher, GreedyMesher, Mesher};
+    use super::*;
+
+    fn atlas() -> BlockAtlas {
+        BlockAtlas::new(["stone", "dirt"])
+    }
+
+    fn build_mesh(mesher: &dyn Mesher, chunk: &ChunkComp, neighbours: [Option<&Chunk>; 6]) -> Mesh {
+        mesher.mesh(&ChunkNeighbourhood::new(chunk.chunk(), neighbours, test_registry()), &atlas()).opaque.build()
+    }
+
+    /// Splits every quad of the ```mesh``` into unit faces, identified by the normal and the lowest corner of the face.
+    /// Panics if two quads overlap.
+    fn unit_faces(mesh: &Mesh) -> HashSet<([i32; 3], [i32; 3])> {
+        let Some(VertexAttributeValues::Float32x3(positions)) = mesh.attribute(Mesh::ATTRIBUTE_POSITION) else { panic!("missing positions") };
+        let Some(VertexAttributeValues::Float32x3(normals)) = mesh.attribute(Mesh::ATTRIBUTE_NORMAL) else { panic!("missing normals") };
+
+        let mut faces = HashSet::new();
+        for (quad, normal) in positions.chunks(4).zip(normals.chunks(4)) {
+            let normal = normal[0].map(|n| n as i32);
+            let min = quad.iter().fold([f32::MAX; 3], |acc, p| [acc[0].min(p[0]), acc[1].min(p[1]), acc[2].min(p[2])]).map(|c| c as i32);
+            let max = quad.iter().fold([f32::MIN; 3], |acc, p| [acc[0].max(p[0]), acc[1].max(p[1]), acc[2].max(p[2])]).map(|c| c as i32);
+            for x in min[0]..max[0].max(min[0] + 1) {
+                for y in min[1]..max[1].max(min[1] + 1) {
+                    for z in min[2]..max[2].max(min[2] + 1) {
+                        assert!(faces.insert((normal, [x, y, z])), "overlapping faces at {:?}", [x, y, z]);
+                    }
+                }
+            }
+        }
+        faces
+    }
+
+    /// a chunk with a stone floor, some dirt and holes, so that both meshers have to deal with different block types
+    fn mixed_chunk() -> ChunkComp {
+        let mut chunk = ChunkComp::new_simple(test_block("stone"));
+        let air = test_block("air");
+        let dirt = test_block("dirt");
+        chunk.fill_box(LocalPos::at(0, 3, 0), LocalPos::at(15, 4, 15), air);
+        chunk.fill_box(LocalPos::at(2, 2, 2), LocalPos::at(9, 3, 6), dirt);
+        chunk.fill_box(LocalPos::at(12, 0, 0), LocalPos::at(13, 2, 15), air);
+        chunk.set(LocalPos::at(5, 4, 5), dirt);
+        chunk.set(LocalPos::at(0, 0, 0), air);
+        chunk
+    }
+
+    #[test]
+    fn test_flat_top_is_one_quad() {
+        let chunk = ChunkComp::new_simple(test_block("stone"));
+        let mesh = build_mesh(&GreedyMesher, &chunk, [None; 6]);
+        // one quad per side of the box
+        assert_eq!(6 * 4, mesh.count_vertices());
+    }
+
+    #[test]
+    fn test_same_surface_as_culling_isolated() {
+        let culled = unit_faces(&build_mesh(&CulledMesher, &mixed_chunk(), [None; 6]));
+        let greedy = unit_faces(&build_mesh(&GreedyMesher, &mixed_chunk(), [None; 6]));
+        assert_eq!(culled, greedy);
+    }
+
+    #[test]
+    fn test_same_surface_as_culling_with_neighbours() {
+        let stone = Chunk::new_simple(test_block("stone"));
+        let neighbours = [Some(&stone), None, None, Some(&stone), Some(&stone), None];
+        let culled = unit_faces(&build_mesh(&CulledMesher, &mixed_chunk(), neighbours));
+        let greedy = unit_faces(&build_mesh(&GreedyMesher, &mixed_chunk(), neighbours));
+        assert_eq!(culled, greedy);
+        assert!(build_mesh(&GreedyMesher, &mixed_chunk(), neighbours).count_vertices() < build_mesh(&CulledMesher, &mixed_chunk(), neighbours).count_vertices());
+    }
+
+    #[test]
+    fn test_different_block_types_are_not_merged() {
+        let mut chunk = ChunkComp::new_simple(test_block("stone"));
+        chunk.set(LocalPos::at(7, 4, 7), test_block("dirt"));
+        let mesh = build_mesh(&GreedyMesher, &chunk, [None; 6]);
+        let Some(VertexAttributeValues::Float32x3(positions)) = mesh.attribute(Mesh::ATTRIBUTE_POSITION) else { panic!("missing positions") };
+        let Some(VertexAttributeValues::Float32x3(normals)) = mesh.attribute(Mesh::ATTRIBUTE_NORMAL) else { panic!("missing normals") };
+        let top_quads = normals.chunks(4).filter(|normal| normal[0] == [0.0, 1.0, 0.0]).count();
+        // the dirt block splits the stone top into 4 rectangles around it
+        assert_eq!(5, top_quads);
+        // plus one quad for each of the 5 remaining sides
+        assert_eq!(5 + 5, positions.len() / 4);
+    }
+
+    #[test]
+    fn test_different_ambient_occlusion_is_not_merged() {
+        let mut chunk = ChunkComp::new_simple(test_block("stone"));
+        let air = test_block("air");
+        chunk.fill_box(LocalPos::at(0, 3, 0), LocalPos::at(15, 4, 15), air);
+        chunk.set(LocalPos::at(7, 3, 7), test_block("stone"));
+        let mesh = build_mesh(&GreedyMesher, &chunk, [None; 6]);
+        let Some(VertexAttributeValues::Float32x3(normals)) = mesh.attribute(Mesh::ATTRIBUTE_NORMAL) else { panic!("missing normals") };
+        let Some(VertexAttributeValues::Float32x4(colors)) = mesh.attribute(Mesh::ATTRIBUTE_COLOR) else { panic!("missing colors") };
+        let floor_quads: Vec<usize> = normals.chunks(4).enumerate()
+            .filter(|(_, normal)| normal[0] == [0.0, 1.0, 0.0])
+            .map(|(quad, _)| quad)
+            .collect();
+        // the floor around the block is split into the 8 occluded faces next to it and 4 open rectangles, plus the top of the block itself
+        assert_eq!(8 + 4 + 1, floor_quads.len());
+        assert!(floor_quads.iter().any(|quad| colors[quad * 4..quad * 4 + 4].iter().any(|color| color[0] < 1.0)));
+    }
+
+    #[test]
+    fn test_uvs_tile_per_block() {
+        let mut chunk = ChunkComp::new_simple(test_block("stone"));
+        chunk.fill_box(LocalPos::at(0, 4, 0), LocalPos::at(15, 4, 7), test_block("dirt"));
+        let mesh = build_mesh(&GreedyMesher, &chunk, [None; 6]);
+        let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute(Mesh::ATTRIBUTE_UV_0) else { panic!("missing uvs") };
+        let Some(VertexAttributeValues::Float32x3(normals)) = mesh.attribute(Mesh::ATTRIBUTE_NORMAL) else { panic!("missing normals") };
+        let top_uvs: Vec<&[[f32; 2]]> = normals.chunks(4).zip(uvs.chunks(4))
+            .filter(|(normal, _)| normal[0] == [0.0, 1.0, 0.0])
+            .map(|(_, uvs)| uvs)
+            .collect();
+        // one 16x8 quad per half, each repeating its own tile once per block
+        assert_eq!(2, top_uvs.len());
+        assert!(top_uvs.contains(&atlas().tile_uvs(atlas().tile("dirt"), [16, 8]).as_slice()));
+        assert!(top_uvs.contains(&atlas().tile_uvs(atlas().tile("stone"), [16, 8]).as_slice()));
+    }
+}
@@ -0,0 +1,470 @@
+// foreign imports
+use bevy::prelude::{IVec3, Resource};
+
+// 'self' imports
+use crate::block_model::FaceMask;
+use crate::blocks::Facing;
+use crate::chunk_logic::chunk::{CHUNK_HEIGHT, CHUNK_SIZE_HORIZONTAL};
+use crate::chunk_logic::neighbourhood::ChunkNeighbourhood;
+use crate::chunk_logic::rendering::chunk_mesh_builder::ChunkMeshLayers;
+use crate::chunk_logic::rendering::{ambient_occlusion, greedy_mesher, smooth_lighting};
+use crate::chunk_logic::rendering::texture_atlas::BlockAtlas;
+
+/// # Description:
+/// Turns a read-only ```ChunkNeighbourhood``` into mesh data. The chunk itself is never consumed, so it can be remeshed whenever it changes.
+///
+/// Implementations are swapped at runtime through the ```MesherSelection``` resource.
+pub trait Mesher: Send + Sync {
+
+    /// # Description:
+    /// A short name to show in the UI
+    fn name(&self) -> &'static str;
+
+    /// # Description:
+    /// Builds the mesh data of the center ```Chunk``` of the ```view```, with the UVs of the block textures in the ```atlas```
+    fn mesh(&self, view: &ChunkNeighbourhood, atlas: &BlockAtlas) -> ChunkMeshLayers;
+}
+
+/// # Description:
+/// Emits all six faces of every visible block, without any culling. Mostly useful as a reference.
+pub struct NaiveMesher;
+
+/// # Description:
+/// Emits every block face that is not hidden by the block in front of it, across chunk borders as well.
+/// Every face is shaded with per-vertex ambient occlusion and smooth lighting.
+pub struct CulledMesher;
+
+/// # Description:
+/// Same culling and shading as the ```CulledMesher```, but merges coplanar faces with the same texture, ambient occlusion and light into larger rectangles
+pub struct GreedyMesher;
+
+impl Mesher for NaiveMesher {
+    fn name(&self) -> &'static str {
+        "Naive"
+    }
+
+    fn mesh(&self, view: &ChunkNeighbourhood, atlas: &BlockAtlas) -> ChunkMeshLayers {
+        let mut chunk_mesh = ChunkMeshLayers::new();
+        for_each_position(|pos, coord| {
+            if let Some(block) = view.get(pos).filter(|_| view.is_block_visible(pos)) {
+                if !view.registry().shape(block).is_full_cube() {
+                    add_block_model(view, atlas, pos, coord, false, &mut chunk_mesh);
+                    return;
+                }
+                for facing in Facing::ALL {
+                    chunk_mesh.layer_mut(view.registry().render_layer(block)).add_face(coord, facing, atlas.face_uvs(view.registry(), block, facing, [1, 1]));
+                }
+            }
+        });
+        chunk_mesh
+    }
+}
+
+impl Mesher for CulledMesher {
+    fn name(&self) -> &'static str {
+        "Culled"
+    }
+
+    fn mesh(&self, view: &ChunkNeighbourhood, atlas: &BlockAtlas) -> ChunkMeshLayers {
+        let mut chunk_mesh = ChunkMeshLayers::new();
+        for_each_position(|pos, coord| {
+            add_block_model(view, atlas, pos, coord, true, &mut chunk_mesh);
+            for facing in Facing::ALL {
+                if view.is_face_visible(pos, facing) {
+                    let Some(block) = view.get(pos) else { continue };
+                    let ao = ambient_occlusion::face_ao(view, pos, facing);
+                    let light = smooth_lighting::face_light(view, pos, facing);
+                    chunk_mesh.layer_mut(view.registry().render_layer(block)).add_quad(coord, facing, [1, 1], ao, light, atlas.face_uvs(view.registry(), block, facing, [1, 1]));
+                }
+            }
+        });
+        chunk_mesh
+    }
+}
+
+impl Mesher for GreedyMesher {
+    fn name(&self) -> &'static str {
+        "Greedy"
+    }
+
+    fn mesh(&self, view: &ChunkNeighbourhood, atlas: &BlockAtlas) -> ChunkMeshLayers {
+        let mut chunk_mesh = ChunkMeshLayers::new();
+        greedy_mesher::add_greedy_faces(view, atlas, &mut chunk_mesh);
+        for_each_position(|pos, coord| add_block_model(view, atlas, pos, coord, true, &mut chunk_mesh));
+        chunk_mesh
+    }
+}
+
+/// # Description:
+/// Holds every available ```Mesher``` and which one is used to build the chunk meshes
+/// # Structure:
+/// ```
+/// pub struct MesherSelection {
+///     meshers: Vec<Box<dyn Mesher>>,
+///     active: usize,
+/// }
+/// ```
+#[derive(Resource)]
+pub struct MesherSelection {
+    meshers: Vec<Box<dyn Mesher>>,
+    active: usize,
+}
+
+impl Default for MesherSelection {
+    /// # Description:
+    /// All built-in meshers, with the ```CulledMesher``` active
+    fn default() -> Self {
+        MesherSelection {
+            meshers: vec![Box::new(NaiveMesher), Box::new(CulledMesher), Box::new(GreedyMesher)],
+            active: 1,
+        }
+    }
+}
+
+impl MesherSelection {
+
+    /// # Description:
+    /// The ```Mesher``` that is currently used
+    pub fn active(&self) -> &dyn Mesher {
+        self.meshers[self.active].as_ref()
+    }
+
+    /// # Description:
+    /// Switches to the next available ```Mesher```, wrapping around at the end
+    pub fn select_next(&mut self) {
+        self.active = (self.active + 1) % self.meshers.len();
+    }
+
+    /// # Description:
+    /// Adds a ```Mesher``` and makes it the active one
+    pub fn add_and_select(&mut self, mesher: Box<dyn Mesher>) {
+        self.meshers.push(mesher);
+        self.active = self.meshers.len() - 1;
+    }
+
+    /// # Description:
+    /// Every available ```Mesher```
+    pub fn all(&self) -> impl Iterator<Item = &dyn Mesher> {
+        self.meshers.iter().map(|mesher| mesher.as_ref())
+    }
+}
+
+/// # Description:
+/// Adds the geometry of the block at ```pos``` if it is visible and not a full block (see ```BlockModel```). Full blocks are left to the ```Mesher```.
+///
+/// Fences and panes reach out to the blocks next to them, see ```ChunkNeighbourhood::shape```.
+///
+/// With ```cull``` a box face is only skipped if it is fully covered, by the other boxes of the model or, on the border of the block, by the neighbour.
+/// The part of a face that the other boxes of the model cover is cut away, so the model stays a closed surface.
+/// Faces that the neighbour covers only partly are drawn completely, the covered part ends up inside of the neighbour.
+///
+/// Faces on the side of the block are lit by the voxel in front of them, everything else by the voxel of the block itself.
+fn add_block_model(view: &ChunkNeighbourhood, atlas: &BlockAtlas, pos: IVec3, coord: [u32; 3], cull: bool, chunk_mesh: &mut ChunkMeshLayers) {
+    let registry = view.registry();
+    let Some(block) = view.get(pos).filter(|block| registry.render_layer(block).is_visible() && !registry.shape(block).is_full_cube()) else { return };
+    let Some(shape) = view.shape(pos) else { return };
+    let layer = chunk_mesh.layer_mut(registry.render_layer(block));
+
+    if shape.is_cross() {
+        layer.add_cross(coord, smooth_lighting::voxel_light(view, pos), atlas.face_uvs(registry, block, Facing::XPositive, [1, 1]));
+        return;
+    }
+    for (index, model_box) in shape.boxes().iter().enumerate() {
+        for facing in Facing::ALL {
+            let face = model_box.face(facing).1;
+            let mut parts = vec![*model_box];
+            if cull {
+                let inner = shape.inner_cover(index, facing);
+                let outer = if model_box.touches_side(facing) { view.face_cover(pos, facing) } else { FaceMask::EMPTY };
+                if inner.union(outer).covers(face) {
+                    continue;
+                }
+                if inner != FaceMask::EMPTY {
+                    let mut visible = FaceMask::EMPTY;
+                    visible.add(face);
+                    parts = visible.without(inner).rects().into_iter().map(|rect| model_box.with_face_rect(facing, rect)).collect();
+                }
+            }
+            let lit_from = if model_box.touches_side(facing) { pos + facing.offset() } else { pos };
+            for part in parts {
+                layer.add_box_face(coord, facing, part, smooth_lighting::voxel_light(view, lit_from), atlas.face_uvs(registry, block, facing, [1, 1]));
+            }
+        }
+    }
+}
+
+/// # Description:
+/// Calls ```f``` with every local position of a chunk, both as a signed position and as a mesh coordinate
+fn for_each_position(mut f: impl FnMut(IVec3, [u32; 3])) {
+    for x in 0..CHUNK_SIZE_HORIZONTAL {
+        for y in 0..CHUNK_HEIGHT {
+            for z in 0..CHUNK_SIZE_HORIZONTAL {
+                f(IVec3::new(x as i32, y as i32, z as i32), [x as u32, y as u32, z as u32]);
+            }
+        }
+    }
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use bevy::math::DVec3;
+    use bevy::prelude::Vec3;
+    use crate::block_model::MODEL_RESOLUTION;
+    use crate::block_registry::{test_block, test_registry};
+    use crate::blocks::Block;
+    use crate::chunk_logic::chunk::Chunk;
+    use crate::chunk_logic::position_handling::LocalPos;
+    use crate::chunk_logic::rendering::chunk_mesh_builder::ChunkMeshBuilder;
+    use super::*;
+
+    /// scenes without loaded neighbours, so every mesher has to produce closed surfaces.
+    /// Models only touch air and blocks whose side they cover exactly, the meshers draw partly covered sides of neighbours completely.
+    fn scenes() -> Vec<(&'static str, Chunk)> {
+        let [air, stone, dirt, glass, leaves] = ["air", "stone", "dirt", "glass", "leaves"].map(test_block);
+        let [slab, fence, pane, flower] = ["stone_slab", "fence", "glass_pane", "flower"].map(test_block);
+        let stairs = |facing: Facing| Block::new(test_block("planks_stairs").block_type(), facing);
+
+        let mut single = Chunk::new_simple(air);
+        single.set(LocalPos::at(4, 2, 9), dirt);
+
+        let mut mixed = Chunk::new_simple(stone);
+        mixed.fill_box(LocalPos::at(0, 3, 0), LocalPos::at(15, 4, 15), air);
+        mixed.fill_box(LocalPos::at(2, 2, 2), LocalPos::at(9, 3, 6), dirt);
+        mixed.fill_box(LocalPos::at(12, 0, 0), LocalPos::at(13, 2, 15), air);
+        mixed.set(LocalPos::at(0, 0, 0), air);
+
+        let mut checkerboard = Chunk::new_simple(air);
+        for x in 0..CHUNK_SIZE_HORIZONTAL as u32 {
+            for y in 0..CHUNK_HEIGHT as u32 {
+                for z in 0..CHUNK_SIZE_HORIZONTAL as u32 {
+                    if (x + y + z) % 2 == 0 {
+                        checkerboard.set(LocalPos::at(x, y, z), dirt);
+                    }
+                }
+            }
+        }
+
+        // glass and leaves that do not touch any other block type
+        let mut see_through = Chunk::new_simple(air);
+        see_through.fill_box(LocalPos::at(1, 0, 1), LocalPos::at(4, 3, 5), glass);
+        see_through.fill_box(LocalPos::at(8, 1, 8), LocalPos::at(12, 4, 10), leaves);
+
+        // every model on its own, and two slabs whose sides cover each other
+        let mut models = Chunk::new_simple(air);
+        for (x, block) in [slab, stairs(Facing::XPositive), stairs(Facing::XNegative), stairs(Facing::ZPositive), stairs(Facing::ZNegative), fence, pane, flower].into_iter().enumerate() {
+            models.set(LocalPos::at(2 * x as u32, 1, 3), block);
+        }
+        models.fill_box(LocalPos::at(2, 3, 8), LocalPos::at(3, 3, 8), slab);
+
+        // a fence with a corner and a cross of panes, their arms meet at the sides of the blocks
+        let mut connected = Chunk::new_simple(air);
+        connected.fill_box(LocalPos::at(1, 0, 2), LocalPos::at(5, 0, 2), fence);
+        connected.fill_box(LocalPos::at(5, 0, 3), LocalPos::at(5, 0, 5), fence);
+        connected.fill_box(LocalPos::at(8, 2, 10), LocalPos::at(12, 3, 10), pane);
+        connected.fill_box(LocalPos::at(10, 2, 8), LocalPos::at(10, 3, 12), pane);
+
+        vec![
+            ("stone", Chunk::new_simple(stone)),
+            ("models", models),
+            ("connected", connected),
+            ("see_through", see_through),
+            ("air", Chunk::new_simple(air)),
+            ("single", single),
+            ("mixed", mixed),
+            ("checkerboard", checkerboard),
+        ]
+    }
+
+    fn triangles(chunk_mesh: &ChunkMeshBuilder) -> impl Iterator<Item = [usize; 3]> + '_ {
+        chunk_mesh.triangles().chunks(3).map(|triangle| [triangle[0] as usize, triangle[1] as usize, triangle[2] as usize])
+    }
+
+    /// the volume of the model boxes of every block, in blocks (crossed quads have none)
+    fn model_volume(view: &ChunkNeighbourhood) -> f64 {
+        let mut volume = 0.0;
+        for_each_position(|pos, _| {
+            let Some(shape) = view.shape(pos).filter(|_| view.is_block_visible(pos)) else { return };
+            for model_box in shape.boxes() {
+                volume += (0..3).map(|axis| (model_box.to[axis] - model_box.from[axis]) as f64 / MODEL_RESOLUTION as f64).product::<f64>();
+            }
+        });
+        volume
+    }
+
+    #[test]
+    fn test_meshes_are_watertight() {
+        for mesher in MesherSelection::default().all() {
+            for (scene, chunk) in scenes() {
+                let view = ChunkNeighbourhood::isolated(&chunk, test_registry());
+                let layers = mesher.mesh(&view, &BlockAtlas::default());
+
+                // closed surfaces have a vector area of zero and enclose exactly the volume of the solid blocks (divergence theorem)
+                let mut area = DVec3::ZERO;
+                let mut volume_times_six = 0.0;
+                for chunk_mesh in [&layers.opaque, &layers.translucent] {
+                    // f64 keeps the sums below exact for the small integer coordinates of a chunk
+                    let vertices: Vec<DVec3> = chunk_mesh.vertices().iter().map(|v| Vec3::from_array(*v).as_dvec3()).collect();
+                    for [a, b, c] in triangles(chunk_mesh) {
+                        area += (vertices[b] - vertices[a]).cross(vertices[c] - vertices[a]);
+                        volume_times_six += vertices[a].dot(vertices[b].cross(vertices[c]));
+                    }
+                }
+
+                assert_eq!(DVec3::ZERO, area, "{} mesher, {scene} scene: surface is not closed", mesher.name());
+                assert_eq!(model_volume(&view), volume_times_six / 6.0, "{} mesher, {scene} scene: wrong enclosed volume", mesher.name());
+            }
+        }
+    }
+
+    #[test]
+    fn test_normals_match_winding() {
+        for mesher in MesherSelection::default().all() {
+            for (scene, chunk) in scenes() {
+                let layers = mesher.mesh(&ChunkNeighbourhood::isolated(&chunk, test_registry()), &BlockAtlas::default());
+                for chunk_mesh in [&layers.opaque, &layers.translucent] {
+                    let vertices: Vec<Vec3> = chunk_mesh.vertices().iter().map(|v| Vec3::from_array(*v)).collect();
+                    let normals: Vec<Vec3> = chunk_mesh.normals().iter().map(|n| Vec3::from_array(*n)).collect();
+
+                    for [a, b, c] in triangles(chunk_mesh) {
+                        let geometric = (vertices[b] - vertices[a]).cross(vertices[c] - vertices[a]).normalize();
+                        for vertex in [a, b, c] {
+                            // the crossed quads of plants run diagonally, their normals are only exact up to rounding
+                            if normals[vertex].to_array().iter().filter(|n| **n != 0.0).count() > 1 {
+                                assert!((normals[vertex].length() - 1.0).abs() < 1e-6, "{} mesher, {scene} scene: normal is not normalized", mesher.name());
+                                assert!(geometric.abs_diff_eq(normals[vertex], 1e-6), "{} mesher, {scene} scene: normal does not match the winding", mesher.name());
+                                continue;
+                            }
+                            assert_eq!(1.0, normals[vertex].length(), "{} mesher, {scene} scene: normal is not normalized", mesher.name());
+                            assert_eq!(geometric, normals[vertex], "{} mesher, {scene} scene: normal does not match the winding", mesher.name());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_naive_mesher_emits_all_six_faces() {
+        let chunk = Chunk::new_simple(test_block("stone"));
+        let chunk_mesh = NaiveMesher.mesh(&ChunkNeighbourhood::isolated(&chunk, test_registry()), &BlockAtlas::default());
+        assert_eq!(6 * chunk.iter_non_air().count() as u32, chunk_mesh.face_count());
+        assert!(chunk_mesh.opaque.normals().contains(&[0.0, -1.0, 0.0]));
+    }
+
+    #[test]
+    fn test_translucent_faces_get_their_own_mesh() {
+        let mut chunk = Chunk::new_simple(test_block("air"));
+        let glass = test_block("glass");
+        // two glass blocks next to each other, the first one also touches a stone block
+        chunk.set(LocalPos::at(5, 1, 5), glass);
+        chunk.set(LocalPos::at(6, 1, 5), glass);
+        chunk.set(LocalPos::at(4, 1, 5), test_block("stone"));
+
+        for mesher in [&CulledMesher as &dyn Mesher, &GreedyMesher] {
+            let layers = mesher.mesh(&ChunkNeighbourhood::isolated(&chunk, test_registry()), &BlockAtlas::default());
+            // the stone keeps its face towards the glass
+            assert_eq!(6, layers.opaque.face_count(), "{} mesher", mesher.name());
+            // the shared glass face and the glass face towards the stone are hidden
+            let translucent_normals = layers.translucent.normals();
+            assert!(!translucent_normals.contains(&[-1.0, 0.0, 0.0]), "{} mesher", mesher.name());
+            assert_eq!(1, translucent_normals.chunks(4).filter(|normal| normal[0] == [1.0, 0.0, 0.0]).count(), "{} mesher", mesher.name());
+        }
+    }
+
+    /// the mesh of a chunk of air with the ```blocks``` placed in it, from every culling ```Mesher```
+    fn model_meshes(blocks: &[([u32; 3], Block)]) -> Vec<(&'static str, ChunkMeshLayers)> {
+        let mut chunk = Chunk::new_simple(test_block("air"));
+        for (pos, block) in blocks {
+            chunk.set(LocalPos::at(pos[0], pos[1], pos[2]), *block);
+        }
+        [&CulledMesher as &dyn Mesher, &GreedyMesher].into_iter()
+            .map(|mesher| (mesher.name(), mesher.mesh(&ChunkNeighbourhood::isolated(&chunk, test_registry()), &BlockAtlas::default())))
+            .collect()
+    }
+
+    /// the amount of faces in ```chunk_mesh``` with the given ```normal``` whose vertices all lie on the plane ```axis``` = ```plane```
+    fn faces_on_plane(chunk_mesh: &ChunkMeshBuilder, normal: [f32; 3], axis: usize, plane: f32) -> usize {
+        chunk_mesh.vertices().chunks(4).zip(chunk_mesh.normals().chunks(4))
+            .filter(|(quad, normals)| normals[0] == normal && quad.iter().all(|vertex| vertex[axis] == plane))
+            .count()
+    }
+
+    #[test]
+    fn test_slab_does_not_cull_like_a_full_block() {
+        let [stone, slab] = ["stone", "stone_slab"].map(test_block);
+        for (mesher, layers) in model_meshes(&[([5, 1, 5], stone), ([6, 1, 5], slab)]) {
+            // the stone keeps its side towards the slab, the slab side towards the stone is hidden
+            assert_eq!(1, faces_on_plane(&layers.opaque, [1.0, 0.0, 0.0], 0, 6.0), "{mesher} mesher");
+            assert_eq!(0, faces_on_plane(&layers.opaque, [-1.0, 0.0, 0.0], 0, 6.0), "{mesher} mesher");
+            assert_eq!(6 + 5, layers.opaque.face_count(), "{mesher} mesher");
+        }
+
+        for (mesher, layers) in model_meshes(&[([5, 1, 5], stone), ([5, 2, 5], slab)]) {
+            // the bottom of the slab covers the whole top of the stone
+            assert_eq!(5 + 5, layers.opaque.face_count(), "{mesher} mesher");
+            assert_eq!(1, faces_on_plane(&layers.opaque, [0.0, 1.0, 0.0], 1, 2.5), "{mesher} mesher");
+        }
+
+        for (mesher, layers) in model_meshes(&[([5, 1, 5], slab), ([6, 1, 5], slab)]) {
+            // two slabs cover each others side exactly
+            assert_eq!(5 + 5, layers.opaque.face_count(), "{mesher} mesher");
+        }
+    }
+
+    #[test]
+    fn test_slab_side_shows_lower_half_of_texture() {
+        let atlas = BlockAtlas::new(["stone"]);
+        let mut chunk = Chunk::new_simple(test_block("air"));
+        chunk.set(LocalPos::at(5, 1, 5), test_block("stone_slab"));
+        let layers = CulledMesher.mesh(&ChunkNeighbourhood::isolated(&chunk, test_registry()), &atlas);
+        let side = layers.opaque.normals().chunks(4).position(|normals| normals[0] == [1.0, 0.0, 0.0]).unwrap();
+        let full = atlas.tile_uvs(atlas.tile("stone"), [1, 1]);
+        let uvs = &layers.opaque.uvs()[side * 4..side * 4 + 4];
+        // the bottom corners match the full face, the top corners end halfway up the tile
+        assert_eq!([full[0], full[2]], [uvs[0], uvs[2]]);
+        assert_eq!((full[0][1] + full[1][1]) / 2.0, uvs[1][1]);
+        assert_eq!(full[1][0], uvs[1][0]);
+    }
+
+    #[test]
+    fn test_stairs_and_cross_models() {
+        for (mesher, layers) in model_meshes(&[([5, 1, 5], test_block("planks_stairs"))]) {
+            // the bottom of the upper step lies on the lower step and is skipped, the half covered top of the lower step is not
+            assert_eq!(6 + 5, layers.opaque.face_count(), "{mesher} mesher");
+            assert_eq!(0, faces_on_plane(&layers.opaque, [0.0, -1.0, 0.0], 1, 1.5), "{mesher} mesher");
+        }
+        for (mesher, layers) in model_meshes(&[([5, 1, 5], test_block("flower")), ([5, 0, 5], test_block("stone"))]) {
+            // two double sided quads, which never hide the block below
+            assert_eq!(6 + 4, layers.opaque.face_count(), "{mesher} mesher");
+        }
+    }
+
+    #[test]
+    fn test_fences_connect_to_their_neighbours() {
+        let [stone, fence] = ["stone", "fence"].map(test_block);
+        // whether a rail of the fences has a corner at x, on the height of the lower rails
+        let rail_reaches = |layers: &ChunkMeshLayers, x: f32| layers.opaque.vertices().chunks(4).zip(layers.opaque.normals().chunks(4))
+            .any(|(quad, normals)| normals[0] == [0.0, 0.0, 1.0] && quad.iter().any(|vertex| vertex[0] == x && vertex[1] == 1.375));
+        for (mesher, layers) in model_meshes(&[([4, 1, 5], stone), ([5, 1, 5], fence), ([6, 1, 5], fence)]) {
+            // the rails run from the stone across both fences, but not past the last one
+            assert!(rail_reaches(&layers, 5.0), "{mesher} mesher");
+            assert!(rail_reaches(&layers, 6.0), "{mesher} mesher");
+            assert!(!rail_reaches(&layers, 7.0), "{mesher} mesher");
+            // where the rails of both fences meet, their ends are hidden
+            assert_eq!(0, faces_on_plane(&layers.opaque, [1.0, 0.0, 0.0], 0, 6.0), "{mesher} mesher");
+        }
+    }
+
+    #[test]
+    fn test_selection_cycles() {
+        let mut selection = MesherSelection::default();
+        assert_eq!("Culled", selection.active().name());
+        selection.select_next();
+        assert_eq!("Greedy", selection.active().name());
+        selection.select_next();
+        assert_eq!("Naive", selection.active().name());
+    }
+}
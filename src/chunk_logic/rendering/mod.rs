@@ -1,2 +1,7 @@
+pub mod ambient_occlusion;
 pub mod chunk_mesh_builder;
-pub mod rendering_const;
\ No newline at end of file
+pub mod greedy_mesher;
+pub mod mesher;
+pub mod rendering_const;
+pub mod smooth_lighting;
+pub mod texture_atlas;
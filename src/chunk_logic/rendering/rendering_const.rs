@@ -1,33 +1,38 @@
+// every per-face table is indexed with ```Facing::index```, so the order must match the ```Facing``` enum
+
 pub const VERTICES: [[[f32; 3]; 4]; 6] = [
-    // Top
-    [[0.0, 1.0, 0.0], [0.0, 1.0, 1.0], [1.0, 1.0, 0.0], [1.0, 1.0, 1.0]],
-    // Front
-    [[0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]],
-    // Left
-    [[0.0, 0.0, 1.0], [0.0, 1.0, 1.0], [0.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
-    // Right
+    // Right (XPositive)
     [[1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [1.0, 0.0, 1.0], [1.0, 1.0, 1.0]],
-    // Back
+    // Left (XNegative)
+    [[0.0, 0.0, 1.0], [0.0, 1.0, 1.0], [0.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+    // Top (YPositive)
+    [[0.0, 1.0, 0.0], [0.0, 1.0, 1.0], [1.0, 1.0, 0.0], [1.0, 1.0, 1.0]],
+    // Bottom (YNegative)
+    [[0.0, 0.0, 1.0], [0.0, 0.0, 0.0], [1.0, 0.0, 1.0], [1.0, 0.0, 0.0]],
+    // Back (ZPositive)
     [[1.0, 0.0, 1.0], [1.0, 1.0, 1.0], [0.0, 0.0, 1.0], [0.0, 1.0, 1.0]],
-    // Bottom
-    [[0.0, 0.0, 1.0], [0.0, 0.0, 0.0], [1.0, 0.0, 1.0], [1.0, 0.0, 0.0]]
+    // Front (ZNegative)
+    [[0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]]
 ];
 
 pub const TRIANGLES: [u32; 6] = [0, 1, 2, 2, 1, 3];
 
+/// The same quad as ```TRIANGLES```, split along the other diagonal (vertex 0 to 3)
+pub const FLIPPED_TRIANGLES: [u32; 6] = [0, 1, 3, 0, 3, 2];
+
 pub const NORMALS: [[f32; 3]; 6] = [
-    // Top
-    [0.0, 1.0, 0.0],
-    // Front
-    [0.0, 0.0, -1.0],
-    // Left
-    [-1.0, 0.0, 0.0],
-    // Right
+    // Right (XPositive)
     [1.0, 0.0, 0.0],
-    // Back
+    // Left (XNegative)
+    [-1.0, 0.0, 0.0],
+    // Top (YPositive)
+    [0.0, 1.0, 0.0],
+    // Bottom (YNegative)
+    [0.0, -1.0, 0.0],
+    // Back (ZPositive)
     [0.0, 0.0, 1.0],
-    // Bottom
-    [0.0, -1.0, 0.0]
+    // Front (ZNegative)
+    [0.0, 0.0, -1.0]
 ];
 
 pub const UVS: [[f32; 2]; 4] = [
@@ -35,4 +40,20 @@ pub const UVS: [[f32; 2]; 4] = [
     [0.0, 1.0],
     [1.0, 0.0],
     [1.0, 1.0]
-];
\ No newline at end of file
+];
+
+/// The axes (x = 0, y = 1, z = 2) that the u and v coordinate of every face run along
+pub const FACE_AXES: [[usize; 2]; 6] = [
+    // Right (XPositive)
+    [2, 1],
+    // Left (XNegative)
+    [2, 1],
+    // Top (YPositive)
+    [0, 2],
+    // Bottom (YNegative)
+    [0, 2],
+    // Back (ZPositive)
+    [0, 1],
+    // Front (ZNegative)
+    [0, 1]
+];
@@ -0,0 +1,200 @@
+// foreign imports
+use bevy::prelude::{IVec3, Resource};
+
+// 'self' imports
+use crate::blocks::Facing;
+use crate::chunk_logic::neighbourhood::ChunkNeighbourhood;
+use crate::chunk_logic::rendering::rendering_const;
+use crate::light_logic::light::{LightChannel, MAX_LIGHT};
+
+/// The vertex light of a fully lit vertex, vertex lights range from 0 (dark) to ```FULL_LIGHT```
+pub const FULL_LIGHT: u8 = u8::MAX;
+
+/// The brightness of light level 0, so that unlit caves are dark but not pitch black
+pub const MIN_BRIGHTNESS: f32 = 0.03;
+
+/// How much darker every light level is than the one above it
+const LEVEL_FALLOFF: f32 = 0.8;
+
+/// # Description:
+/// Whether the light levels of the chunks are baked into the vertex colors of their meshes (with unlit materials),
+/// or the meshes are fully lit and shaded by the PBR lights of the scene instead.
+///
+/// ```daylight``` (```0.0..=1.0```) scales the skylight, block light is not affected by it.
+/// # Structure:
+/// ```
+/// pub struct BakedLighting {
+///     pub enabled: bool,
+///     pub daylight: f32,
+/// }
+/// ```
+#[derive(Resource, Clone, Copy, PartialEq, Debug)]
+pub struct BakedLighting {
+    pub enabled: bool,
+    pub daylight: f32,
+}
+
+impl Default for BakedLighting {
+    /// # Description:
+    /// Baked lighting at full daylight
+    fn default() -> Self {
+        BakedLighting { enabled: true, daylight: 1.0 }
+    }
+}
+
+impl BakedLighting {
+
+    /// # Description:
+    /// The daylight to mesh the chunks with, ```None``` if the light is not baked into the meshes
+    pub fn daylight(&self) -> Option<f32> {
+        self.enabled.then_some(self.daylight.clamp(0.0, 1.0))
+    }
+}
+
+/// # Description:
+/// The brightness (```MIN_BRIGHTNESS..=1.0```) a possibly fractional light level is rendered with. Every level is roughly a constant factor darker than the one above it.
+pub fn brightness(level: f32) -> f32 {
+    let level = level.clamp(0.0, MAX_LIGHT as f32);
+    // rescaled so that level 0 ends up at exactly MIN_BRIGHTNESS
+    let darkest = LEVEL_FALLOFF.powf(MAX_LIGHT as f32);
+    let falloff = (LEVEL_FALLOFF.powf(MAX_LIGHT as f32 - level) - darkest) / (1.0 - darkest);
+    MIN_BRIGHTNESS + (1.0 - MIN_BRIGHTNESS) * falloff
+}
+
+/// # Description:
+/// Calculates the smooth light of the four vertices of a face, in the same order as ```rendering_const::VERTICES```.
+///
+/// Every vertex averages the block light and skylight of the (up to) four voxels in front of the face that touch the vertex.
+/// Solid voxels do not take part, neither does the corner voxel if both voxels next to it are solid, as no light can reach the vertex through it.
+/// The brighter of the two averages (with the skylight scaled by the daylight of the view) is turned into the vertex light.
+///
+/// Views without light levels are fully lit, as are vertices that only touch unknown voxels.
+pub fn face_light(view: &ChunkNeighbourhood, local: IVec3, facing: Facing) -> [u8; 4] {
+    let Some(daylight) = view.daylight() else { return [FULL_LIGHT; 4] };
+    let [u_axis, v_axis] = rendering_const::FACE_AXES[facing.index()];
+    let front = local + facing.offset();
+
+    rendering_const::VERTICES[facing.index()].map(|vertex| {
+        let mut step_u = IVec3::ZERO;
+        let mut step_v = IVec3::ZERO;
+        step_u[u_axis] = if vertex[u_axis] == 0.0 { -1 } else { 1 };
+        step_v[v_axis] = if vertex[v_axis] == 0.0 { -1 } else { 1 };
+        let corner_hidden = view.is_solid(front + step_u) && view.is_solid(front + step_v);
+
+        let samples = [front, front + step_u, front + step_v, front + step_u + step_v];
+        average_light(view, &samples[..if corner_hidden { 3 } else { 4 }], daylight)
+    })
+}
+
+/// # Description:
+/// The flat light of a single voxel for the geometry inside of it, e.g. the faces of a ```BlockModel``` that does not fill the whole cell
+pub fn voxel_light(view: &ChunkNeighbourhood, local: IVec3) -> u8 {
+    match view.daylight() {
+        Some(daylight) => average_light(view, &[local], daylight),
+        None => FULL_LIGHT,
+    }
+}
+
+/// the vertex light of the average light of the known, non solid ```samples```
+fn average_light(view: &ChunkNeighbourhood, samples: &[IVec3], daylight: f32) -> u8 {
+    let mut block = 0.0;
+    let mut sky = 0.0;
+    let mut count = 0.0;
+    for pos in samples.iter().copied().filter(|pos| !view.is_solid(*pos)) {
+        let (Some(block_level), Some(sky_level)) = (view.light(pos, LightChannel::Block), view.light(pos, LightChannel::Sky)) else { continue };
+        block += block_level as f32;
+        sky += sky_level as f32;
+        count += 1.0;
+    }
+    if count == 0.0 {
+        return FULL_LIGHT;
+    }
+    let level = (block / count).max(sky / count * daylight);
+    (brightness(level) * FULL_LIGHT as f32).round() as u8
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use crate::block_registry::{test_block, test_registry};
+    use crate::chunk_logic::chunk::ChunkComp;
+    use crate::chunk_logic::position_handling::{BlockPos, ChunkPos, LocalPos};
+    use crate::chunk_logic::world::VoxelWorld;
+    use crate::light_logic::light;
+    use super::*;
+
+    const TOP: Facing = Facing::YPositive;
+
+    /// a lit world with a stone chunk below an air chunk
+    fn ground_world() -> VoxelWorld {
+        let mut world = VoxelWorld::new();
+        world.insert_chunk(ChunkPos::ZERO, ChunkComp::new_simple(test_block("stone")));
+        world.insert_chunk(ChunkPos::new(0, 1, 0), ChunkComp::new_simple(test_block("air")));
+        for chunk_pos in [ChunkPos::ZERO, ChunkPos::new(0, 1, 0)] {
+            light::light_chunk(&mut world, test_registry(), chunk_pos);
+        }
+        world
+    }
+
+    #[test]
+    fn test_brightness_curve() {
+        assert!((brightness(MAX_LIGHT as f32) - 1.0).abs() < 1e-6);
+        assert_eq!(brightness(0.0), brightness(-3.0));
+        assert!(brightness(7.0) < brightness(8.0));
+        assert!((brightness(0.0) - MIN_BRIGHTNESS).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_unlit_view_is_fully_lit() {
+        let world = ground_world();
+        let view = world.neighbourhood(ChunkPos::ZERO, test_registry()).unwrap();
+        assert_eq!([FULL_LIGHT; 4], face_light(&view, IVec3::new(5, 4, 5), TOP));
+    }
+
+    #[test]
+    fn test_open_ground_and_daylight() {
+        let world = ground_world();
+        let view = world.lit_neighbourhood(ChunkPos::ZERO, test_registry(), 1.0).unwrap();
+        assert_eq!([FULL_LIGHT; 4], face_light(&view, IVec3::new(5, 4, 5), TOP));
+
+        let night = world.lit_neighbourhood(ChunkPos::ZERO, test_registry(), 0.0).unwrap();
+        let dark = (brightness(0.0) * FULL_LIGHT as f32).round() as u8;
+        assert_eq!([dark; 4], face_light(&night, IVec3::new(5, 4, 5), TOP));
+    }
+
+    #[test]
+    fn test_caves_are_dark_and_emitters_glow() {
+        let mut world = ground_world();
+        // a closed cave in the stone
+        for x in 3..10 {
+            light::set_block(&mut world, test_registry(), BlockPos::new(x, 2, 5), test_block("air")).unwrap();
+        }
+        let view = world.lit_neighbourhood(ChunkPos::ZERO, test_registry(), 1.0).unwrap();
+        let floor = face_light(&view, IVec3::new(6, 1, 5), TOP);
+        assert!(floor.iter().all(|light| *light < 10), "{floor:?}");
+
+        light::set_block(&mut world, test_registry(), BlockPos::new(9, 2, 5), test_block("redstone_block")).unwrap();
+        let view = world.lit_neighbourhood(ChunkPos::ZERO, test_registry(), 1.0).unwrap();
+        let near = face_light(&view, IVec3::new(8, 1, 5), TOP);
+        let far = face_light(&view, IVec3::new(4, 1, 5), TOP);
+        assert!(near.iter().all(|light| *light > floor[0]));
+        // the vertices closer to the emitter are brighter
+        assert!(near[2] > near[0], "{near:?}");
+        assert!(near[0] > far[0]);
+    }
+
+    #[test]
+    fn test_solid_voxels_do_not_darken_the_average() {
+        let mut world = ground_world();
+        // a wall next to the face: the vertices next to it only average the open voxels and stay fully lit
+        let mut chunk = world.remove_chunk(ChunkPos::new(0, 1, 0)).unwrap();
+        chunk.fill_box(LocalPos::at(4, 0, 0), LocalPos::at(4, 4, 15), test_block("stone"));
+        world.insert_chunk(ChunkPos::new(0, 1, 0), chunk);
+        light::light_chunk(&mut world, test_registry(), ChunkPos::new(0, 1, 0));
+        let view = world.lit_neighbourhood(ChunkPos::ZERO, test_registry(), 1.0).unwrap();
+        assert_eq!([FULL_LIGHT; 4], face_light(&view, IVec3::new(5, 4, 5), TOP));
+        assert_eq!(FULL_LIGHT, voxel_light(&view, IVec3::new(5, 5, 5)));
+    }
+}
@@ -0,0 +1,342 @@
+// foreign imports
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use bevy::prelude::{warn, AlphaMode, Assets, Commands, Handle, Image, Res, ResMut, Resource, StandardMaterial};
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::texture::{CompressedImageFormats, ImageType};
+
+// 'self' imports
+use crate::block_registry::BlockRegistry;
+use crate::blocks::{Block, Facing};
+use crate::chunk_logic::rendering::rendering_const;
+use crate::chunk_logic::rendering::smooth_lighting::BakedLighting;
+
+/// The directory every block texture is loaded from at startup
+pub const TEXTURE_DIRECTORY: &str = "assets/textures";
+/// The name of the generated tile that is used for every texture that could not be found
+pub const MISSING_TEXTURE: &str = "missing";
+
+/// The edge length of a single tile in pixels. Bigger textures are scaled down to it.
+pub const TILE_SIZE: u32 = 16;
+/// How often every tile is repeated along each axis of its atlas region.
+///
+/// Merged faces of the greedy mesher span up to a whole chunk, so the repetitions are baked into the atlas
+/// instead of relying on the sampler to repeat the texture (which would repeat the whole atlas).
+pub const TILE_REPEAT: u32 = 16;
+
+/// The edge length of the region of a single tile in pixels
+const REGION_SIZE: u32 = TILE_SIZE * TILE_REPEAT;
+
+/// # Description:
+/// Maps texture names to the tiles of a single atlas texture, so that every block of a chunk can be rendered with one shared material.
+///
+/// The tiles are laid out in a square-ish grid, row by row, in the order their names were given. Tile 0 is always the ```MISSING_TEXTURE```.
+/// # Structure:
+/// ```
+/// pub struct BlockAtlas {
+///     tiles: HashMap<String, u32>,
+///     columns: u32,
+///     rows: u32,
+/// }
+/// ```
+#[derive(Resource, Clone, Debug)]
+pub struct BlockAtlas {
+    tiles: HashMap<String, u32>,
+    columns: u32,
+    rows: u32,
+}
+
+/// # Description:
+/// The ```StandardMaterial```s that all chunks share, both using the ```BlockAtlas``` texture.
+/// ```opaque``` is alpha tested for solid and cutout blocks, ```translucent``` is alpha blended.
+/// # Structure:
+/// ```
+/// pub struct ChunkMaterials {
+///     pub opaque: Handle<StandardMaterial>,
+///     pub translucent: Handle<StandardMaterial>,
+/// }
+/// ```
+#[derive(Resource, Clone, Debug)]
+pub struct ChunkMaterials {
+    pub opaque: Handle<StandardMaterial>,
+    pub translucent: Handle<StandardMaterial>,
+}
+
+impl Default for BlockAtlas {
+    /// # Description:
+    /// An atlas that only contains the ```MISSING_TEXTURE```
+    fn default() -> Self {
+        BlockAtlas::new(std::iter::empty())
+    }
+}
+
+impl BlockAtlas {
+
+    /// # Description:
+    /// Lays out the tiles for the given texture names, after the ```MISSING_TEXTURE```. Duplicate names share one tile.
+    pub fn new<'a>(names: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut tiles = HashMap::from([(MISSING_TEXTURE.to_string(), 0)]);
+        for name in names {
+            let next = tiles.len() as u32;
+            tiles.entry(name.to_string()).or_insert(next);
+        }
+        let columns = (tiles.len() as f32).sqrt().ceil() as u32;
+        let rows = (tiles.len() as u32).div_ceil(columns);
+        BlockAtlas { tiles, columns, rows }
+    }
+
+    /// # Description:
+    /// Builds the atlas and its texture from named RGBA images. Every image is scaled to ```TILE_SIZE``` and repeated ```TILE_REPEAT``` times along both axes.
+    pub fn stitch(textures: &[(String, Image)]) -> (Self, Image) {
+        let atlas = BlockAtlas::new(textures.iter().map(|(name, _)| name.as_str()));
+        let (width, height) = (atlas.columns * REGION_SIZE, atlas.rows * REGION_SIZE);
+        let mut data = vec![0u8; (width * height * 4) as usize];
+
+        let mut place = |tile: u32, pixels: &[[u8; 4]]| {
+            let origin_x = (tile % atlas.columns) * REGION_SIZE;
+            let origin_y = (tile / atlas.columns) * REGION_SIZE;
+            for y in 0..REGION_SIZE {
+                for x in 0..REGION_SIZE {
+                    let pixel = pixels[((y % TILE_SIZE) * TILE_SIZE + x % TILE_SIZE) as usize];
+                    let offset = (((origin_y + y) * width + origin_x + x) * 4) as usize;
+                    data[offset..offset + 4].copy_from_slice(&pixel);
+                }
+            }
+        };
+
+        place(0, &missing_tile());
+        for (name, image) in textures {
+            place(atlas.tile(name), &scale_to_tile(image));
+        }
+
+        let image = Image::new(
+            Extent3d { width, height, depth_or_array_layers: 1 },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+        );
+        (atlas, image)
+    }
+
+    /// # Description:
+    /// Loads every PNG in ```directory``` (named after the file stem) and stitches them into an atlas.
+    /// Unreadable files are skipped with a warning, their blocks fall back to the ```MISSING_TEXTURE```.
+    pub fn load(directory: impl AsRef<Path>) -> (Self, Image) {
+        let mut paths: Vec<_> = match fs::read_dir(directory.as_ref()) {
+            Ok(entries) => entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect(),
+            Err(error) => {
+                warn!("could not read the texture directory {}: {error}", directory.as_ref().display());
+                Vec::new()
+            }
+        };
+        // a stable order keeps the atlas layout the same between runs
+        paths.sort();
+
+        let mut textures = Vec::new();
+        for path in paths.into_iter().filter(|path| path.extension().is_some_and(|extension| extension == "png")) {
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else { continue };
+            let image = fs::read(&path)
+                .map_err(|error| error.to_string())
+                .and_then(|bytes| Image::from_buffer(&bytes, ImageType::Extension("png"), CompressedImageFormats::NONE, true).map_err(|error| error.to_string()))
+                .and_then(|image| image.convert(TextureFormat::Rgba8UnormSrgb).ok_or_else(|| "unsupported pixel format".to_string()));
+            match image {
+                Ok(image) => textures.push((name.to_string(), image)),
+                Err(error) => warn!("skipping texture {}: {error}", path.display()),
+            }
+        }
+        BlockAtlas::stitch(&textures)
+    }
+
+    /// # Description:
+    /// The tile of the texture called ```name```, or the ```MISSING_TEXTURE``` tile if there is none
+    pub fn tile(&self, name: &str) -> u32 {
+        self.tiles.get(name).copied().unwrap_or(0)
+    }
+
+    /// # Description:
+    /// The amount of tiles in the atlas, including the ```MISSING_TEXTURE```
+    pub fn tile_count(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// # Description:
+    /// The UVs of the four vertices of a face (same order as ```rendering_const::VERTICES```) that spans ```size``` blocks and shows ```tile```.
+    ///
+    /// Every block of the face shows the tile once, upright on side faces.
+    /// # Warning:
+    /// ```size``` must not exceed ```TILE_REPEAT``` on either axis
+    pub fn tile_uvs(&self, tile: u32, size: [u32; 2]) -> [[f32; 2]; 4] {
+        let region_width = 1.0 / self.columns as f32;
+        let region_height = 1.0 / self.rows as f32;
+        let left = (tile % self.columns) as f32 * region_width;
+        // image rows run from top to bottom, so the faces start at the bottom of the region
+        let bottom = (tile / self.columns + 1) as f32 * region_height;
+
+        rendering_const::UVS.map(|uv| [
+            left + uv[0] * size[0] as f32 / TILE_REPEAT as f32 * region_width,
+            bottom - uv[1] * size[1] as f32 / TILE_REPEAT as f32 * region_height,
+        ])
+    }
+
+    /// # Description:
+    /// The tile that the ```facing``` face of ```block``` shows, see ```BlockRegistry::face_texture```
+    pub fn face_tile(&self, registry: &BlockRegistry, block: &Block, facing: Facing) -> u32 {
+        self.tile(registry.face_texture(block, facing))
+    }
+
+    /// # Description:
+    /// The UVs of a face of ```block``` that spans ```size``` blocks, see ```face_tile``` and ```tile_uvs```
+    pub fn face_uvs(&self, registry: &BlockRegistry, block: &Block, facing: Facing, size: [u32; 2]) -> [[f32; 2]; 4] {
+        self.tile_uvs(self.face_tile(registry, block, facing), size)
+    }
+}
+
+/// # Description:
+/// Startup system that builds the ```BlockAtlas``` from ```TEXTURE_DIRECTORY``` and creates the shared ```ChunkMaterials```.
+/// Both are inserted through ```Commands```, systems that use them have to run in a later startup set (it belongs into ```StartupSet::PreStartup```).
+/// The materials are unlit while the light is baked into the chunk meshes (see ```BakedLighting```).
+pub fn load_block_atlas(mut commands: Commands, mut images: ResMut<Assets<Image>>, mut materials: ResMut<Assets<StandardMaterial>>, lighting: Res<BakedLighting>) {
+    let (atlas, image) = BlockAtlas::load(TEXTURE_DIRECTORY);
+    let texture = images.add(image);
+    let opaque = materials.add(StandardMaterial {
+        base_color_texture: Some(texture.clone()),
+        alpha_mode: AlphaMode::Mask(0.5),
+        unlit: lighting.enabled,
+        ..Default::default()
+    });
+    let translucent = materials.add(StandardMaterial {
+        base_color_texture: Some(texture),
+        alpha_mode: AlphaMode::Blend,
+        unlit: lighting.enabled,
+        ..Default::default()
+    });
+    commands.insert_resource(atlas);
+    commands.insert_resource(ChunkMaterials { opaque, translucent });
+}
+
+/// # Description:
+/// Scales an RGBA image down to ```TILE_SIZE``` by averaging the pixels that fall into each tile pixel (or repeating them for smaller images)
+fn scale_to_tile(image: &Image) -> Vec<[u8; 4]> {
+    let width = image.texture_descriptor.size.width.max(1);
+    let height = image.texture_descriptor.size.height.max(1);
+    let pixel = |x: u32, y: u32| -> [u8; 4] {
+        let offset = ((y * width + x) * 4) as usize;
+        image.data.get(offset..offset + 4).map_or([0; 4], |rgba| [rgba[0], rgba[1], rgba[2], rgba[3]])
+    };
+
+    let mut tile = Vec::with_capacity((TILE_SIZE * TILE_SIZE) as usize);
+    for y in 0..TILE_SIZE {
+        for x in 0..TILE_SIZE {
+            let (x_start, y_start) = (x * width / TILE_SIZE, y * height / TILE_SIZE);
+            let x_end = ((x + 1) * width / TILE_SIZE).max(x_start + 1);
+            let y_end = ((y + 1) * height / TILE_SIZE).max(y_start + 1);
+
+            let mut sum = [0u32; 4];
+            for source_y in y_start..y_end {
+                for source_x in x_start..x_end {
+                    for (total, channel) in sum.iter_mut().zip(pixel(source_x, source_y)) {
+                        *total += channel as u32;
+                    }
+                }
+            }
+            let count = (x_end - x_start) * (y_end - y_start);
+            tile.push(sum.map(|total| (total / count) as u8));
+        }
+    }
+    tile
+}
+
+/// # Description:
+/// The classic magenta and black checkerboard for textures that could not be found
+fn missing_tile() -> Vec<[u8; 4]> {
+    let half = TILE_SIZE / 2;
+    (0..TILE_SIZE * TILE_SIZE)
+        .map(|i| if ((i % TILE_SIZE) / half + (i / TILE_SIZE) / half).is_multiple_of(2) { [255, 0, 255, 255] } else { [0, 0, 0, 255] })
+        .collect()
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use crate::block_registry::{test_block, test_registry};
+    use super::*;
+
+    fn solid_image(size: u32, rgba: [u8; 4]) -> Image {
+        Image::new_fill(Extent3d { width: size, height: size, depth_or_array_layers: 1 }, TextureDimension::D2, &rgba, TextureFormat::Rgba8UnormSrgb)
+    }
+
+    fn pixel(image: &Image, x: u32, y: u32) -> [u8; 4] {
+        let offset = ((y * image.texture_descriptor.size.width + x) * 4) as usize;
+        [image.data[offset], image.data[offset + 1], image.data[offset + 2], image.data[offset + 3]]
+    }
+
+    #[test]
+    fn test_layout() {
+        let atlas = BlockAtlas::new(["stone", "dirt", "stone"]);
+        assert_eq!(3, atlas.tile_count());
+        assert_eq!(0, atlas.tile(MISSING_TEXTURE));
+        assert_eq!(1, atlas.tile("stone"));
+        assert_eq!(2, atlas.tile("dirt"));
+        assert_eq!(0, atlas.tile("does_not_exist"));
+        assert_eq!((2, 2), (atlas.columns, atlas.rows));
+    }
+
+    #[test]
+    fn test_stitch_places_repeated_tiles() {
+        let red = [255, 0, 0, 255];
+        let (atlas, image) = BlockAtlas::stitch(&[("red".to_string(), solid_image(64, red)), ("blue".to_string(), solid_image(4, [0, 0, 255, 255]))]);
+        assert_eq!(Extent3d { width: 2 * REGION_SIZE, height: 2 * REGION_SIZE, depth_or_array_layers: 1 }, image.texture_descriptor.size);
+        assert_eq!(1, atlas.tile("red"));
+        // the red region is the top right one, the blue one the bottom left one
+        assert_eq!(red, pixel(&image, REGION_SIZE, 0));
+        assert_eq!(red, pixel(&image, 2 * REGION_SIZE - 1, REGION_SIZE - 1));
+        assert_eq!([0, 0, 255, 255], pixel(&image, 0, REGION_SIZE));
+        assert_eq!([255, 0, 255, 255], pixel(&image, 0, 0));
+    }
+
+    #[test]
+    fn test_load_texture_directory() {
+        let (atlas, image) = BlockAtlas::load(TEXTURE_DIRECTORY);
+        assert_ne!(0, atlas.tile("stone"));
+        assert_ne!(0, atlas.tile("dirt"));
+        assert_ne!(0, atlas.tile("grass_side"));
+        assert_ne!(0, atlas.tile("log_top"));
+        assert_eq!(TextureFormat::Rgba8UnormSrgb, image.texture_descriptor.format);
+    }
+
+    #[test]
+    fn test_scale_averages() {
+        // left half white, right half black
+        let mut image = solid_image(32, [255, 255, 255, 255]);
+        for y in 0..32 {
+            for x in 16..32 {
+                let offset = (y * 32 + x) * 4;
+                image.data[offset..offset + 3].fill(0);
+            }
+        }
+        let tile = scale_to_tile(&image);
+        assert_eq!([255, 255, 255, 255], tile[7]);
+        assert_eq!([0, 0, 0, 255], tile[8]);
+    }
+
+    #[test]
+    fn test_tile_uvs_stay_inside_region() {
+        let atlas = BlockAtlas::new(["stone", "dirt"]);
+        // the dirt tile is the bottom left region of the 2x2 grid
+        assert_eq!([[0.0, 1.0], [0.0, 0.5], [0.5, 1.0], [0.5, 0.5]], atlas.tile_uvs(2, [TILE_REPEAT, TILE_REPEAT]));
+        assert_eq!([[0.5, 0.5], [0.5, 0.5 - 1.0 / 32.0], [0.5 + 3.0 / 32.0, 0.5], [0.5 + 3.0 / 32.0, 0.5 - 1.0 / 32.0]], atlas.tile_uvs(1, [3, 1]));
+    }
+
+    #[test]
+    fn test_face_uvs_per_block_type() {
+        let atlas = BlockAtlas::new(["stone", "dirt"]);
+        let registry = test_registry();
+        let [stone, dirt, redstone] = ["stone", "dirt", "redstone_block"].map(test_block);
+        assert_eq!(atlas.tile_uvs(1, [1, 1]), atlas.face_uvs(registry, &stone, Facing::YPositive, [1, 1]));
+        assert_eq!(atlas.tile_uvs(2, [1, 1]), atlas.face_uvs(registry, &dirt, Facing::XPositive, [1, 1]));
+        assert_eq!(atlas.tile_uvs(0, [1, 1]), atlas.face_uvs(registry, &redstone, Facing::YNegative, [1, 1]));
+    }
+}
@@ -0,0 +1,383 @@
+// foreign imports
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use bevy::prelude::{Assets, Component, Entity, Handle, Mesh, Query, Res, ResMut, Resource, With};
+
+// 'self' imports
+use crate::block_registry::BlockRegistry;
+use crate::blocks::Block;
+use crate::chunk_logic::chunk::ChunkComp;
+use crate::chunk_logic::neighbourhood::{surrounding_offsets, ChunkNeighbourhood};
+use crate::chunk_logic::rendering::chunk_mesh_builder::ChunkMeshLayers;
+use crate::chunk_logic::rendering::mesher::{Mesher, MesherSelection};
+use crate::chunk_logic::rendering::smooth_lighting::BakedLighting;
+use crate::chunk_logic::rendering::texture_atlas::BlockAtlas;
+use crate::chunk_logic::position_handling::{BlockPos, ChunkPos};
+
+/// # Description:
+/// The errors that can occur when accessing the ```VoxelWorld``` by world coordinates
+/// # Format:
+/// ```
+/// pub enum WorldError {
+///     ChunkNotLoaded(ChunkPos)
+/// }
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WorldError {
+    ChunkNotLoaded(ChunkPos)
+}
+
+impl fmt::Display for WorldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorldError::ChunkNotLoaded(chunk_pos) => write!(f, "the chunk at {chunk_pos} is not loaded"),
+        }
+    }
+}
+
+impl std::error::Error for WorldError {}
+
+/// # Description:
+/// Marks the entity that renders the chunk at the given chunk coordinates
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ChunkEntity(pub ChunkPos);
+
+/// # Description:
+/// The alpha blended mesh of a chunk entity. It is rendered by a child entity with the translucent material,
+/// the chunk entity itself renders the opaque mesh.
+#[derive(Component, Clone, Debug)]
+pub struct TranslucentChunkMesh(pub Handle<Mesh>);
+
+/// # Description:
+/// Owns every loaded ```ChunkComp``` keyed by its ```ChunkPos```. All world-space block access should go through this resource.
+///
+/// It does not depend on the renderer, chunks without a rendered entity are perfectly valid (e.g. in unit tests).
+///
+/// Every change that can alter the mesh of a chunk (including its neighbours loading or changing) marks said chunk as dirty.
+/// Every block that ```set_block()``` changes is recorded in ```changes``` as well, so the neighbour updates (see ```tick_logic::neighbour_updates```) can react to it.
+/// # Structure:
+/// ```
+/// pub struct VoxelWorld {
+///     chunks: HashMap<ChunkPos, ChunkComp>,
+///     entities: HashMap<ChunkPos, Entity>,
+///     dirty: HashSet<ChunkPos>,
+///     changes: Vec<BlockPos>,
+/// }
+/// ```
+#[derive(Resource, Default)]
+pub struct VoxelWorld {
+    chunks: HashMap<ChunkPos, ChunkComp>,
+    entities: HashMap<ChunkPos, Entity>,
+    dirty: HashSet<ChunkPos>,
+    changes: Vec<BlockPos>,
+}
+
+impl VoxelWorld {
+
+    /// # Description:
+    /// Creates an empty world without any loaded chunks
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Description:
+    /// Loads ```chunk``` at ```chunk_pos``` and returns the chunk that was previously loaded there
+    pub fn insert_chunk(&mut self, chunk_pos: ChunkPos, chunk: ChunkComp) -> Option<ChunkComp> {
+        let previous = self.chunks.insert(chunk_pos, chunk);
+        self.mark_dirty(chunk_pos);
+        self.mark_neighbours_dirty(chunk_pos);
+        previous
+    }
+
+    /// # Description:
+    /// Unloads the chunk at ```chunk_pos``` along with its entity link
+    pub fn remove_chunk(&mut self, chunk_pos: ChunkPos) -> Option<ChunkComp> {
+        self.entities.remove(&chunk_pos);
+        self.dirty.remove(&chunk_pos);
+        self.mark_neighbours_dirty(chunk_pos);
+        self.chunks.remove(&chunk_pos)
+    }
+
+    /// # Description:
+    /// Returns ```true``` if there is a chunk loaded at ```chunk_pos```
+    pub fn is_loaded(&self, chunk_pos: ChunkPos) -> bool {
+        self.chunks.contains_key(&chunk_pos)
+    }
+
+    /// # Description:
+    /// Read access to the chunk at ```chunk_pos```
+    pub fn get_chunk(&self, chunk_pos: ChunkPos) -> Option<&ChunkComp> {
+        self.chunks.get(&chunk_pos)
+    }
+
+    /// # Description:
+    /// Write access to the chunk at ```chunk_pos```
+    pub fn get_chunk_mut(&mut self, chunk_pos: ChunkPos) -> Option<&mut ChunkComp> {
+        self.chunks.get_mut(&chunk_pos)
+    }
+
+    /// # Description:
+    /// The coordinates of every loaded chunk, in no particular order
+    pub fn chunk_positions(&self) -> impl Iterator<Item = ChunkPos> + '_ {
+        self.chunks.keys().copied()
+    }
+
+    /// # Description:
+    /// The amount of loaded chunks
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// # Description:
+    /// Returns ```true``` if no chunk is loaded
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// # Description:
+    /// Returns the ```Block``` at the world position
+    /// # Errors:
+    /// ```WorldError::ChunkNotLoaded``` if the containing chunk is not loaded
+    pub fn get_block(&self, block_pos: BlockPos) -> Result<&Block, WorldError> {
+        let (chunk_pos, local) = block_pos.split();
+        let chunk = self.chunks.get(&chunk_pos).ok_or(WorldError::ChunkNotLoaded(chunk_pos))?;
+        Ok(chunk.get(local))
+    }
+
+    /// # Description:
+    /// Replaces the ```Block``` at the world position and returns the ```Block``` that was there before.
+    /// The position is recorded as changed if the ```Block``` differs from the previous one.
+    /// # Errors:
+    /// ```WorldError::ChunkNotLoaded``` if the containing chunk is not loaded
+    pub fn set_block(&mut self, block_pos: BlockPos, block: Block) -> Result<Block, WorldError> {
+        let (chunk_pos, local) = block_pos.split();
+        let chunk = self.chunks.get_mut(&chunk_pos).ok_or(WorldError::ChunkNotLoaded(chunk_pos))?;
+        let previous = chunk.set(local, block);
+        if previous != block {
+            self.changes.push(block_pos);
+            self.mark_dirty(chunk_pos);
+            // blocks on the border are visible from (and shade) the chunks around it as well
+            for offset in surrounding_offsets() {
+                self.mark_dirty((block_pos + offset).chunk());
+            }
+        }
+        Ok(previous)
+    }
+
+    /// # Description:
+    /// Returns a read-only view of the chunk at ```chunk_pos``` together with all loaded chunks around it
+    pub fn neighbourhood<'a>(&'a self, chunk_pos: ChunkPos, registry: &'a BlockRegistry) -> Option<ChunkNeighbourhood<'a>> {
+        let chunk = self.chunks.get(&chunk_pos)?;
+        Some(ChunkNeighbourhood::from_fn(chunk.chunk(), registry, |offset| self.chunks.get(&(chunk_pos + offset)).map(ChunkComp::chunk)))
+    }
+
+    /// # Description:
+    /// Same as ```neighbourhood()```, but the view includes the light levels of the chunks with the skylight scaled by ```daylight```
+    pub fn lit_neighbourhood<'a>(&'a self, chunk_pos: ChunkPos, registry: &'a BlockRegistry, daylight: f32) -> Option<ChunkNeighbourhood<'a>> {
+        let view = self.neighbourhood(chunk_pos, registry)?;
+        let light = self.chunks.get(&chunk_pos)?.light();
+        Some(view.with_light(light, daylight, |offset| self.chunks.get(&(chunk_pos + offset)).map(ChunkComp::light)))
+    }
+
+    /// # Description:
+    /// Builds the mesh of the chunk at ```chunk_pos``` with the given ```Mesher```, which sees the loaded neighbours as well, textured from the ```atlas```.
+    ///
+    /// With a ```daylight``` the light levels are baked into the vertex colors (see ```smooth_lighting```), without it the mesh is fully lit.
+    pub fn build_mesh(&self, chunk_pos: ChunkPos, mesher: &dyn Mesher, atlas: &BlockAtlas, registry: &BlockRegistry, daylight: Option<f32>) -> Option<ChunkMeshLayers> {
+        let view = match daylight {
+            Some(daylight) => self.lit_neighbourhood(chunk_pos, registry, daylight)?,
+            None => self.neighbourhood(chunk_pos, registry)?,
+        };
+        Some(mesher.mesh(&view, atlas))
+    }
+
+    /// # Description:
+    /// Flags the loaded chunk at ```chunk_pos``` for remeshing. Unloaded positions are ignored.
+    pub fn mark_dirty(&mut self, chunk_pos: ChunkPos) {
+        if self.chunks.contains_key(&chunk_pos) {
+            self.dirty.insert(chunk_pos);
+        }
+    }
+
+    /// # Description:
+    /// Returns the positions changed by ```set_block()``` since the last call, in the order of the changes
+    pub fn take_changes(&mut self) -> Vec<BlockPos> {
+        std::mem::take(&mut self.changes)
+    }
+
+    /// # Description:
+    /// Returns ```true``` if ```set_block()``` changed a block since the last ```take_changes()```
+    pub fn has_changes(&self) -> bool {
+        !self.changes.is_empty()
+    }
+
+    /// # Description:
+    /// Flags every loaded chunk for remeshing, e.g. after the active ```Mesher``` changed
+    pub fn mark_all_dirty(&mut self) {
+        self.dirty.extend(self.chunks.keys().copied());
+    }
+
+    fn mark_neighbours_dirty(&mut self, chunk_pos: ChunkPos) {
+        for offset in surrounding_offsets() {
+            self.mark_dirty(chunk_pos + offset);
+        }
+    }
+
+    /// # Description:
+    /// Returns every chunk that needs to be remeshed and clears the dirty flags
+    pub fn take_dirty(&mut self) -> Vec<ChunkPos> {
+        self.dirty.drain().collect()
+    }
+
+    /// # Description:
+    /// Links the loaded chunk at ```chunk_pos``` to the entity that renders it
+    pub fn link_entity(&mut self, chunk_pos: ChunkPos, entity: Entity) {
+        self.entities.insert(chunk_pos, entity);
+    }
+
+    /// # Description:
+    /// Returns the entity that renders the chunk at ```chunk_pos```, if any
+    pub fn entity(&self, chunk_pos: ChunkPos) -> Option<Entity> {
+        self.entities.get(&chunk_pos).copied()
+    }
+}
+
+/// # Description:
+/// Rebuilds the opaque and translucent meshes of all dirty chunks that have a rendered entity, with the light baked in if ```BakedLighting``` is enabled
+pub fn remesh_dirty_chunks(mut voxel_world: ResMut<VoxelWorld>, mut meshes: ResMut<Assets<Mesh>>, mesher_selection: Res<MesherSelection>, atlas: Res<BlockAtlas>, registry: Res<BlockRegistry>, lighting: Res<BakedLighting>, query: Query<(&Handle<Mesh>, &TranslucentChunkMesh), With<ChunkEntity>>) {
+    for chunk_pos in voxel_world.take_dirty() {
+        let Some(entity) = voxel_world.entity(chunk_pos) else { continue };
+        let Ok((opaque_handle, translucent)) = query.get(entity) else { continue };
+        let Some(layers) = voxel_world.build_mesh(chunk_pos, mesher_selection.active(), &atlas, &registry, lighting.daylight()) else { continue };
+        if let Some(mesh) = meshes.get_mut(opaque_handle) {
+            *mesh = layers.opaque.build();
+        }
+        if let Some(mesh) = meshes.get_mut(&translucent.0) {
+            *mesh = layers.translucent.build();
+        }
+    }
+}
+
+/// # Description:
+/// A ```VoxelWorld``` for tests with one chunk at each x of ```chunks_x``` (and y = z = 0), stone up to y = 1 with air above it
+#[cfg(test)]
+pub(crate) fn test_floor_world(chunks_x: impl IntoIterator<Item = i32>) -> VoxelWorld {
+    use crate::block_registry::test_block;
+    use crate::chunk_logic::position_handling::LocalPos;
+    let mut world = VoxelWorld::new();
+    for x in chunks_x {
+        let mut chunk = ChunkComp::new_simple(test_block("stone"));
+        chunk.fill_box(LocalPos::at(0, 2, 0), LocalPos::at(15, 4, 15), test_block("air"));
+        world.insert_chunk(ChunkPos::new(x, 0, 0), chunk);
+    }
+    world
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_registry::{test_block, test_registry};
+    use crate::chunk_logic::rendering::mesher::CulledMesher;
+    use crate::chunk_logic::position_handling::LocalPos;
+
+    fn stone_chunk() -> ChunkComp {
+        ChunkComp::new_simple(test_block("stone"))
+    }
+
+    #[test]
+    fn test_get_block_routes_to_chunk() {
+        let mut world = VoxelWorld::new();
+        world.insert_chunk(ChunkPos::new(-1, 0, 2), stone_chunk());
+        assert_eq!(test_block("stone").block_type(), world.get_block(BlockPos::new(-1, 0, 32)).unwrap().block_type());
+        assert_eq!(test_block("stone").block_type(), world.get_block(BlockPos::new(-16, 4, 47)).unwrap().block_type());
+        assert_eq!(Err(WorldError::ChunkNotLoaded(ChunkPos::new(0, 0, 2))), world.get_block(BlockPos::new(0, 0, 32)));
+        assert_eq!(Err(WorldError::ChunkNotLoaded(ChunkPos::new(-1, 1, 2))), world.get_block(BlockPos::new(-1, 5, 32)));
+        assert_eq!(Err(WorldError::ChunkNotLoaded(ChunkPos::new(-1, 0, 1))), world.get_block(BlockPos::new(-1, 0, 31)));
+    }
+
+    #[test]
+    fn test_set_block() {
+        let mut world = VoxelWorld::new();
+        world.insert_chunk(ChunkPos::new(-1, -1, -1), stone_chunk());
+        let dirt = test_block("dirt");
+        let previous = world.set_block(BlockPos::new(-16, -1, -3), dirt).unwrap();
+        assert_eq!(test_block("stone").block_type(), previous.block_type());
+        assert_eq!(&dirt, world.get_block(BlockPos::new(-16, -1, -3)).unwrap());
+        assert_eq!(&dirt, world.get_chunk(ChunkPos::new(-1, -1, -1)).unwrap().get(LocalPos::at(0, 4, 13)));
+        assert_eq!(Err(WorldError::ChunkNotLoaded(ChunkPos::ZERO)), world.set_block(BlockPos::ZERO, dirt));
+
+        // only real changes are recorded
+        world.set_block(BlockPos::new(-16, -1, -3), dirt).unwrap();
+        world.set_block(BlockPos::new(-2, -2, -2), dirt).unwrap();
+        assert!(world.has_changes());
+        assert_eq!(vec![BlockPos::new(-16, -1, -3), BlockPos::new(-2, -2, -2)], world.take_changes());
+        assert!(!world.has_changes());
+    }
+
+    #[test]
+    fn test_entity_link() {
+        let mut world = VoxelWorld::new();
+        world.insert_chunk(ChunkPos::ZERO, stone_chunk());
+        world.link_entity(ChunkPos::ZERO, Entity::from_raw(7));
+        assert_eq!(Some(Entity::from_raw(7)), world.entity(ChunkPos::ZERO));
+        world.remove_chunk(ChunkPos::ZERO);
+        assert_eq!(None, world.entity(ChunkPos::ZERO));
+        assert!(world.is_empty());
+    }
+
+    #[test]
+    fn test_border_faces_culled_against_neighbours() {
+        let mut world = VoxelWorld::new();
+        world.insert_chunk(ChunkPos::ZERO, stone_chunk());
+        // 2 * (16*5 + 16*16 + 16*5) faces with 4 vertices each
+        assert_eq!(832 * 4, world.build_mesh(ChunkPos::ZERO, &CulledMesher, &BlockAtlas::default(), test_registry(), None).unwrap().opaque.vertices().len());
+
+        world.insert_chunk(ChunkPos::new(1, 0, 0), stone_chunk());
+        world.insert_chunk(ChunkPos::new(0, 0, -1), stone_chunk());
+        assert_eq!((832 - 2 * 80) * 4, world.build_mesh(ChunkPos::ZERO, &CulledMesher, &BlockAtlas::default(), test_registry(), None).unwrap().opaque.vertices().len());
+        assert_eq!((832 - 80) * 4, world.build_mesh(ChunkPos::new(1, 0, 0), &CulledMesher, &BlockAtlas::default(), test_registry(), None).unwrap().opaque.vertices().len());
+    }
+
+    #[test]
+    fn test_border_faces_reappear_next_to_air() {
+        let mut world = VoxelWorld::new();
+        world.insert_chunk(ChunkPos::ZERO, stone_chunk());
+        world.insert_chunk(ChunkPos::new(1, 0, 0), stone_chunk());
+        world.set_block(BlockPos::new(16, 2, 7), test_block("air")).unwrap();
+        // the removed block opens one face in its own chunk's border and one in the neighbour
+        assert_eq!((832 - 80 + 1) * 4, world.build_mesh(ChunkPos::ZERO, &CulledMesher, &BlockAtlas::default(), test_registry(), None).unwrap().opaque.vertices().len());
+    }
+
+    #[test]
+    fn test_dirty_tracking() {
+        let mut world = VoxelWorld::new();
+        world.insert_chunk(ChunkPos::ZERO, stone_chunk());
+        world.insert_chunk(ChunkPos::new(1, 0, 0), stone_chunk());
+        let mut dirty = world.take_dirty();
+        dirty.sort_by_key(|pos| pos.0.x);
+        assert_eq!(vec![ChunkPos::ZERO, ChunkPos::new(1, 0, 0)], dirty);
+
+        let dirt = test_block("dirt");
+        world.set_block(BlockPos::new(5, 2, 5), dirt).unwrap();
+        assert_eq!(vec![ChunkPos::ZERO], world.take_dirty());
+
+        world.set_block(BlockPos::new(15, 2, 5), dirt).unwrap();
+        assert_eq!(2, world.take_dirty().len());
+
+        world.set_block(BlockPos::new(15, 2, 5), dirt).unwrap();
+        assert!(world.take_dirty().is_empty());
+
+        // a block in the corner changes the ambient occlusion of the diagonal chunk as well
+        world.insert_chunk(ChunkPos::new(1, 0, 1), stone_chunk());
+        world.take_dirty();
+        world.set_block(BlockPos::new(15, 2, 15), test_block("air")).unwrap();
+        assert_eq!(3, world.take_dirty().len());
+
+        world.remove_chunk(ChunkPos::new(1, 0, 1));
+        world.remove_chunk(ChunkPos::new(1, 0, 0));
+        assert_eq!(vec![ChunkPos::ZERO], world.take_dirty());
+    }
+}
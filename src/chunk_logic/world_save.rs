@@ -0,0 +1,238 @@
+// foreign imports
+use std::path::Path;
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// 'self' imports
+use crate::block_registry::BlockRegistry;
+use crate::blocks::{Block, BlockId, Facing};
+use crate::chunk_logic::chunk::{Chunk, ChunkComp};
+use crate::chunk_logic::position_handling::ChunkPos;
+use crate::chunk_logic::world::VoxelWorld;
+use crate::light_logic::light;
+use crate::save::{load_ron, write_ron, SaveError, AUTOSAVE_INTERVAL};
+use crate::tick_logic::scheduler::{ChunkTicks, TickScheduler};
+
+/// Where the world is saved, relative to the working directory like ```BLOCK_DIRECTORY```
+pub const WORLD_SAVE_FILE: &str = "saves/world.ron";
+
+/// # Description:
+/// One chunk as it is saved: its blocks and its scheduled block updates. The light is not saved, it is computed again once the chunk is loaded.
+/// # Format:
+/// ```
+/// (
+///     chunk_pos: ((-1, 0, 2)),
+///     palette: [("stone", 0), ("redstone_torch", 8)],
+///     blocks: [(0, 1200), (1, 1), (0, 79)],
+///     scheduled: [(block_pos: ((-9, 2, 37)), due: 40, priority: 0, sequence: 12)],
+/// )
+/// ```
+/// The palette holds every distinct block by name and state ID (see ```Block::state_id()```), so the save survives new block types shifting the IDs.
+/// ```blocks``` are runs of palette indices and their lengths in the order of ```Chunk::iter()```.
+/// Names that are not in the ```BlockRegistry``` anymore are restored as air, state IDs that are not valid for the block type as its default state.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChunkSave {
+    pub chunk_pos: ChunkPos,
+    pub palette: Vec<(String, u16)>,
+    pub blocks: Vec<(u16, u16)>,
+    #[serde(default)]
+    pub scheduled: ChunkTicks,
+}
+
+/// # Description:
+/// Every loaded chunk and the clock of the ```TickScheduler```, which the ```due``` ticks of the saved updates are counted on
+/// # Format:
+/// ```
+/// (
+///     scheduler: (tick: 1200, sequence: 53, budget: 4096),
+///     chunks: [(chunk_pos: ((-1, 0, 2)), palette: [...], blocks: [...], scheduled: [...])],
+/// )
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WorldSave {
+    pub scheduler: TickScheduler,
+    pub chunks: Vec<ChunkSave>,
+}
+
+impl ChunkSave {
+
+    /// # Description:
+    /// The save of the ```chunk``` at ```chunk_pos```
+    pub fn new(chunk_pos: ChunkPos, chunk: &ChunkComp, registry: &BlockRegistry) -> Self {
+        let mut palette: Vec<Block> = Vec::new();
+        let mut blocks: Vec<(u16, u16)> = Vec::new();
+        for (_, block) in chunk.iter() {
+            let index = match palette.iter().position(|entry| entry == block) {
+                Some(index) => index,
+                None => {
+                    palette.push(*block);
+                    palette.len() - 1
+                }
+            } as u16;
+            match blocks.last_mut() {
+                Some((last, length)) if *last == index => *length += 1,
+                _ => blocks.push((index, 1)),
+            }
+        }
+        ChunkSave {
+            chunk_pos,
+            palette: palette.iter().map(|block| (registry.definition(block.block_type()).name.clone(), block.state_id())).collect(),
+            blocks,
+            scheduled: chunk.scheduled().clone(),
+        }
+    }
+
+    /// # Description:
+    /// The chunk that was saved, without light. Cells the runs do not reach are air.
+    pub fn restore(&self, registry: &BlockRegistry) -> ChunkComp {
+        let air = Block::new(BlockId::AIR, Facing::XPositive);
+        let palette: Vec<Block> = self.palette.iter()
+            .map(|(name, state_id)| match registry.id(name) {
+                Some(id) => registry.block_from_state_id(id, *state_id).unwrap_or_else(|| Block::new(id, Facing::XPositive)),
+                None => air,
+            })
+            .collect();
+        let cells = self.blocks.iter()
+            .flat_map(|(index, length)| std::iter::repeat_n(palette.get(*index as usize).copied().unwrap_or(air), *length as usize));
+        let mut chunk = ChunkComp::new(Chunk::from_cells(cells, air));
+        *chunk.scheduled_mut() = self.scheduled.clone();
+        chunk
+    }
+}
+
+impl WorldSave {
+
+    /// # Description:
+    /// The save of every chunk in ```world``` together with the clock of ```scheduler```
+    pub fn new(world: &VoxelWorld, scheduler: &TickScheduler, registry: &BlockRegistry) -> Self {
+        let mut chunk_positions: Vec<ChunkPos> = world.chunk_positions().collect();
+        // the same world always gives the same file
+        chunk_positions.sort_by_key(|chunk_pos| chunk_pos.0.to_array());
+        WorldSave {
+            scheduler: scheduler.clone(),
+            chunks: chunk_positions.into_iter()
+                .filter_map(|chunk_pos| Some(ChunkSave::new(chunk_pos, world.get_chunk(chunk_pos)?, registry)))
+                .collect(),
+        }
+    }
+
+    /// # Description:
+    /// The world that was saved, lit once all of its chunks are loaded, and its ```TickScheduler```
+    pub fn restore(&self, registry: &BlockRegistry) -> (VoxelWorld, TickScheduler) {
+        let mut world = VoxelWorld::new();
+        for chunk in &self.chunks {
+            world.insert_chunk(chunk.chunk_pos, chunk.restore(registry));
+        }
+        for chunk in &self.chunks {
+            light::light_chunk(&mut world, registry, chunk.chunk_pos);
+        }
+        (world, self.scheduler.clone())
+    }
+
+    /// # Description:
+    /// Reads the save at ```path```, ```Ok(None)``` if there is none yet
+    /// # Errors:
+    /// fails if the file exists but cannot be read or parsed
+    pub fn load(path: impl AsRef<Path>) -> Result<Option<Self>, SaveError> {
+        load_ron(path.as_ref())
+    }
+
+    /// # Description:
+    /// Writes the save to ```path```, see ```save::write_ron()```
+    /// # Errors:
+    /// fails if the directory or the file cannot be written
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), SaveError> {
+        write_ron(self, path.as_ref())
+    }
+}
+
+/// # Description:
+/// System that writes the world to ```WORLD_SAVE_FILE``` every ```AUTOSAVE_INTERVAL``` and when the game closes.
+/// It belongs at the end of the frame, after the window sent ```AppExit```.
+pub fn save_world(time: Res<Time>, mut exit: EventReader<AppExit>, mut since_save: Local<f32>, voxel_world: Res<VoxelWorld>, scheduler: Res<TickScheduler>, registry: Res<BlockRegistry>) {
+    let exiting = exit.iter().count() > 0;
+    *since_save += time.delta_seconds();
+    if !exiting && *since_save < AUTOSAVE_INTERVAL {
+        return;
+    }
+    *since_save = 0.0;
+    if let Err(error) = WorldSave::new(&voxel_world, &scheduler, &registry).write(WORLD_SAVE_FILE) {
+        warn!("{error}");
+    }
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use crate::block_registry::{test_block, test_registry};
+    use crate::chunk_logic::position_handling::BlockPos;
+    use crate::light_logic::light::LightChannel;
+    use super::*;
+
+    /// a directory of its own for every test, so they can run in parallel
+    fn test_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("voxel_rust_{name}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        directory
+    }
+
+    #[test]
+    fn test_world_round_trip() {
+        let registry = test_registry();
+        let mut world = VoxelWorld::new();
+        for x in -1..=0 {
+            world.insert_chunk(ChunkPos::new(x, 0, 0), ChunkComp::new_simple(test_block("stone")));
+        }
+        let torch = Block::new(test_block("redstone_torch").block_type(), Facing::ZNegative).with_power_lvl(15);
+        light::set_block(&mut world, registry, BlockPos::new(-3, 4, 5), Block::new(BlockId::AIR, Facing::XPositive)).unwrap();
+        light::set_block(&mut world, registry, BlockPos::new(-3, 3, 5), torch).unwrap();
+        light::set_block(&mut world, registry, BlockPos::new(7, 3, 2), test_block("glass")).unwrap();
+        let mut scheduler = TickScheduler::new();
+        for _ in 0..5 {
+            scheduler.advance(&mut world);
+        }
+        scheduler.schedule(&mut world, BlockPos::new(-3, 3, 5), 2, 0).unwrap();
+        scheduler.schedule(&mut world, BlockPos::new(7, 3, 2), 8, 0).unwrap();
+
+        let directory = test_directory("world_round_trip");
+        let path = directory.join("world.ron");
+        assert!(matches!(WorldSave::load(&path), Ok(None)));
+        WorldSave::new(&world, &scheduler, registry).write(&path).unwrap();
+
+        let save = WorldSave::load(&path).unwrap().unwrap();
+        assert_eq!(2, save.chunks.len());
+        let (mut restored, mut restored_scheduler) = save.restore(registry);
+        for chunk_pos in world.chunk_positions() {
+            let (saved, loaded) = (world.get_chunk(chunk_pos).unwrap(), restored.get_chunk(chunk_pos).unwrap());
+            assert!(saved.iter().zip(loaded.iter()).all(|(a, b)| a == b));
+        }
+        assert_eq!(Ok(&torch), restored.get_block(BlockPos::new(-3, 3, 5)));
+        // the light is computed again
+        assert_eq!(light::light_level(&world, LightChannel::Sky, BlockPos::new(-3, 4, 5)), light::light_level(&restored, LightChannel::Sky, BlockPos::new(-3, 4, 5)));
+
+        // the scheduled updates are still due on the same ticks
+        assert!(restored_scheduler.is_scheduled(&restored, BlockPos::new(-3, 3, 5)));
+        assert!(restored_scheduler.advance(&mut restored).is_empty());
+        assert_eq!(vec![BlockPos::new(-3, 3, 5)], restored_scheduler.advance(&mut restored).iter().map(|update| update.block_pos).collect::<Vec<_>>());
+        assert!(restored_scheduler.is_scheduled(&restored, BlockPos::new(7, 3, 2)));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_restore_replaces_what_it_does_not_know() {
+        let text = r#"(chunk_pos: ((0, 0, 0)), palette: [("stone", 0), ("diamond", 0), ("glass", 65535)], blocks: [(0, 2), (1, 1), (2, 1), (7, 1)])"#;
+        let save: ChunkSave = ron::from_str(text).unwrap();
+        let chunk = save.restore(test_registry());
+        let cells: Vec<Block> = chunk.iter().map(|(_, block)| *block).collect();
+        assert_eq!([test_block("stone"), test_block("stone")], cells[..2]);
+        // unknown names and palette indices are air, invalid states the default state
+        assert!(cells[2].is_air());
+        assert_eq!(test_block("glass"), cells[3]);
+        assert!(cells[4..].iter().all(Block::is_air));
+        assert!(save.scheduled.is_empty());
+    }
+}
@@ -0,0 +1,693 @@
+// foreign imports
+use std::collections::{HashSet, VecDeque};
+use bevy::prelude::IVec3;
+
+// 'self' imports
+use crate::block_registry::BlockRegistry;
+use crate::blocks::{Block, Facing, MAX_LIGHT_OPACITY};
+use crate::chunk_logic::chunk::{Chunk, ChunkComp, CHUNK_HEIGHT, CHUNK_SIZE_HORIZONTAL, CHUNK_VOLUME};
+use crate::chunk_logic::neighbourhood::surrounding_offsets;
+use crate::chunk_logic::position_handling::{BlockPos, ChunkPos, LocalPos, CHUNK_DIMENSIONS};
+use crate::chunk_logic::world::{VoxelWorld, WorldError};
+
+/// The brightest light level, every light level fits into 4 bits
+pub const MAX_LIGHT: u8 = 15;
+
+/// # Description:
+/// The two kinds of light every cell stores. ```Block``` light comes from emitting blocks, ```Sky``` light shines down from above the world.
+///
+/// Both spread the same way and lose at least one level per step, except that full skylight travels straight down through
+/// blocks without any light opacity (e.g. air and glass) without getting darker.
+/// # Format:
+/// ```
+/// pub enum LightChannel {
+///     Block,
+///     Sky
+/// }
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum LightChannel {
+    Block,
+    Sky
+}
+
+impl LightChannel {
+    pub const ALL: [LightChannel; 2] = [LightChannel::Block, LightChannel::Sky];
+}
+
+/// # Description:
+/// The light levels of every cell of a chunk, two 4 bit levels per byte in the same order as the ```Block```s of a ```Chunk```,
+/// one array per ```LightChannel```.
+///
+/// The ```heightmap``` stores for every column (indexed as ```[x][z]```) the local y coordinate right above the highest block that has any
+/// light opacity, or 0 if the column of this chunk does not contain any. Everything at or above it sees the sky if nothing above the chunk blocks it.
+/// # Structure:
+/// ```
+/// pub struct ChunkLight {
+///     block: Vec<u8>,
+///     sky: Vec<u8>,
+///     heightmap: Vec<u8>,
+/// }
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ChunkLight {
+    block: Vec<u8>,
+    sky: Vec<u8>,
+    heightmap: Vec<u8>,
+}
+
+impl Default for ChunkLight {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChunkLight {
+
+    /// # Description:
+    /// Creates completely dark light levels with an empty heightmap
+    pub fn new() -> Self {
+        ChunkLight {
+            block: vec![0; CHUNK_VOLUME.div_ceil(2)],
+            sky: vec![0; CHUNK_VOLUME.div_ceil(2)],
+            heightmap: vec![0; CHUNK_SIZE_HORIZONTAL * CHUNK_SIZE_HORIZONTAL],
+        }
+    }
+
+    /// # Description:
+    /// The light level of the ```channel``` at the local position
+    pub fn level(&self, channel: LightChannel, local: LocalPos) -> u8 {
+        let index = Chunk::local_index(local);
+        self.levels(channel)[index / 2] >> (index % 2 * 4) & 0xF
+    }
+
+    /// # Description:
+    /// Sets the light level of the ```channel``` at the local position, levels above ```MAX_LIGHT``` are clamped
+    pub fn set_level(&mut self, channel: LightChannel, local: LocalPos, level: u8) {
+        let index = Chunk::local_index(local);
+        let shift = index % 2 * 4;
+        let levels = match channel {
+            LightChannel::Block => &mut self.block,
+            LightChannel::Sky => &mut self.sky,
+        };
+        let byte = &mut levels[index / 2];
+        *byte = *byte & !(0xF << shift) | level.min(MAX_LIGHT) << shift;
+    }
+
+    /// # Description:
+    /// Returns ```true``` if no cell is lit at all by the ```channel```
+    pub fn is_dark(&self, channel: LightChannel) -> bool {
+        self.levels(channel).iter().all(|byte| *byte == 0)
+    }
+
+    /// # Description:
+    /// The local y coordinate right above the highest block with any light opacity in the column, see ```ChunkLight```
+    pub fn height(&self, x: u32, z: u32) -> u8 {
+        self.heightmap[x as usize * CHUNK_SIZE_HORIZONTAL + z as usize]
+    }
+
+    fn set_height(&mut self, x: u32, z: u32, height: u8) {
+        self.heightmap[x as usize * CHUNK_SIZE_HORIZONTAL + z as usize] = height;
+    }
+
+    fn levels(&self, channel: LightChannel) -> &[u8] {
+        match channel {
+            LightChannel::Block => &self.block,
+            LightChannel::Sky => &self.sky,
+        }
+    }
+}
+
+/// # Description:
+/// Returns the light level of the ```channel``` at the world position
+/// # Errors:
+/// ```WorldError::ChunkNotLoaded``` if the containing chunk is not loaded
+pub fn light_level(world: &VoxelWorld, channel: LightChannel, block_pos: BlockPos) -> Result<u8, WorldError> {
+    let (chunk_pos, local) = block_pos.split();
+    let chunk = world.get_chunk(chunk_pos).ok_or(WorldError::ChunkNotLoaded(chunk_pos))?;
+    Ok(chunk.light().level(channel, local))
+}
+
+/// # Description:
+/// Returns the block light level at the world position, see ```light_level()```
+pub fn block_light(world: &VoxelWorld, block_pos: BlockPos) -> Result<u8, WorldError> {
+    light_level(world, LightChannel::Block, block_pos)
+}
+
+/// # Description:
+/// Returns the skylight level at the world position, see ```light_level()```
+pub fn sky_light(world: &VoxelWorld, block_pos: BlockPos) -> Result<u8, WorldError> {
+    light_level(world, LightChannel::Sky, block_pos)
+}
+
+/// # Description:
+/// Computes the light of the chunk at ```chunk_pos``` from scratch: its heightmap, the light of its emitting blocks, the skylight
+/// and the light that shines in from the loaded chunks around it. Its own light spreads into the loaded neighbours as well.
+///
+/// Call this after a chunk was inserted into (or replaced in) the ```VoxelWorld```. Chunks that are not loaded block all light,
+/// except that a chunk without a loaded chunk right above it is open to the sky. Loading a chunk on top of another one therefore
+/// takes the direct skylight away from the one below.
+pub fn light_chunk(world: &mut VoxelWorld, registry: &BlockRegistry, chunk_pos: ChunkPos) {
+    let Some(chunk) = world.get_chunk_mut(chunk_pos) else { return };
+    let mut light = ChunkLight::new();
+    for x in 0..CHUNK_SIZE_HORIZONTAL as u32 {
+        for z in 0..CHUNK_SIZE_HORIZONTAL as u32 {
+            light.set_height(x, z, column_height(chunk.chunk(), registry, x, z));
+        }
+    }
+    *chunk.light_mut() = light;
+
+    let cells: Vec<BlockPos> = chunk.iter().map(|(local, _)| chunk_pos.block(local)).collect();
+    let border = border_cells(chunk_pos);
+
+    for channel in LightChannel::ALL {
+        let mut update = LightUpdate::new(world, registry, channel);
+        // whatever the previous content of the chunk (or the lack of it) spread into the neighbours is removed first
+        update.darken(border.iter().copied());
+        for pos in &cells {
+            update.seed(*pos);
+        }
+        update.lit.extend(border.iter().copied());
+        update.spread();
+        update.finish();
+    }
+}
+
+/// # Description:
+/// Removes the chunk at ```chunk_pos``` from the ```VoxelWorld``` like ```VoxelWorld::remove_chunk()``` does and removes the light
+/// it spread into its neighbours. The chunk below it is open to the sky again afterwards.
+pub fn unload_chunk(world: &mut VoxelWorld, registry: &BlockRegistry, chunk_pos: ChunkPos) -> Option<ChunkComp> {
+    let chunk = world.remove_chunk(chunk_pos)?;
+    let border = border_cells(chunk_pos);
+    for channel in LightChannel::ALL {
+        let mut update = LightUpdate::new(world, registry, channel);
+        update.darken(border.iter().copied());
+        update.spread();
+        update.finish();
+    }
+    Some(chunk)
+}
+
+/// # Description:
+/// Replaces the ```Block``` at the world position like ```VoxelWorld::set_block()``` does and updates the light around it
+/// incrementally, see ```relight_block()```. Returns the ```Block``` that was there before.
+/// # Errors:
+/// ```WorldError::ChunkNotLoaded``` if the containing chunk is not loaded
+pub fn set_block(world: &mut VoxelWorld, registry: &BlockRegistry, block_pos: BlockPos, block: Block) -> Result<Block, WorldError> {
+    let previous = world.set_block(block_pos, block)?;
+    if previous != block {
+        relight_block(world, registry, block_pos);
+    }
+    Ok(previous)
+}
+
+/// # Description:
+/// Updates the heightmap and the light after the ```Block``` at ```block_pos``` changed, e.g. an emitter or an opaque block was placed or removed.
+///
+/// First the light that may have depended on the old block is removed: starting at ```block_pos``` every cell that is darker than the
+/// cell the removal came from is un-lit, while brighter cells are lit by something else and are remembered. Then the new block and
+/// all remembered cells spread their light again. Only the cells around the change are visited and only the chunks whose light
+/// changed are marked dirty.
+pub fn relight_block(world: &mut VoxelWorld, registry: &BlockRegistry, block_pos: BlockPos) {
+    let (chunk_pos, local) = block_pos.split();
+    let Some(chunk) = world.get_chunk_mut(chunk_pos) else { return };
+    let height = column_height(chunk.chunk(), registry, local.x(), local.z());
+    chunk.light_mut().set_height(local.x(), local.z(), height);
+
+    for channel in LightChannel::ALL {
+        let mut update = LightUpdate::new(world, registry, channel);
+        update.darken([block_pos]);
+        // the light around the block flows back in if it lets any light through
+        update.lit.extend(Facing::ALL.map(|facing| block_pos.neighbour(facing)));
+        update.spread();
+        update.finish();
+    }
+}
+
+/// the local y coordinate right above the highest block with any light opacity in the column, see ```ChunkLight```
+fn column_height(chunk: &Chunk, registry: &BlockRegistry, x: u32, z: u32) -> u8 {
+    (0..CHUNK_HEIGHT as u32).rev()
+        .find(|y| registry.definition(chunk.get(LocalPos::at(x, *y, z)).block_type()).light_opacity > 0)
+        .map_or(0, |y| y as u8 + 1)
+}
+
+/// the cells right outside of the six faces of the chunk at ```chunk_pos```
+fn border_cells(chunk_pos: ChunkPos) -> Vec<BlockPos> {
+    let origin = chunk_pos.origin();
+    let mut cells = Vec::new();
+    for x in -1..=CHUNK_DIMENSIONS.x {
+        for y in -1..=CHUNK_DIMENSIONS.y {
+            for z in -1..=CHUNK_DIMENSIONS.z {
+                let local = IVec3::new(x, y, z);
+                let outside = [local.x, local.y, local.z].iter().zip(CHUNK_DIMENSIONS.to_array()).filter(|(c, size)| **c < 0 || **c >= *size).count();
+                // only the cells that share a face with the chunk, not the edges and corners
+                if outside == 1 {
+                    cells.push(origin + local);
+                }
+            }
+        }
+    }
+    cells
+}
+
+/// the light level ```block``` shines with on its own
+fn emission(registry: &BlockRegistry, block: &Block) -> u8 {
+    registry.light_emission(block).min(MAX_LIGHT)
+}
+
+/// A single light update of one ```LightChannel``` on the ```VoxelWorld```, it remembers which cells it changed so the affected chunks can be remeshed
+struct LightUpdate<'a> {
+    world: &'a mut VoxelWorld,
+    registry: &'a BlockRegistry,
+    channel: LightChannel,
+    /// cells whose light has to spread to their neighbours
+    lit: VecDeque<BlockPos>,
+    changed: HashSet<BlockPos>,
+}
+
+impl<'a> LightUpdate<'a> {
+    fn new(world: &'a mut VoxelWorld, registry: &'a BlockRegistry, channel: LightChannel) -> Self {
+        LightUpdate { world, registry, channel, lit: VecDeque::new(), changed: HashSet::new() }
+    }
+
+    fn block(&self, pos: BlockPos) -> Option<Block> {
+        self.world.get_block(pos).ok().copied()
+    }
+
+    fn light(&self, pos: BlockPos) -> Option<u8> {
+        light_level(self.world, self.channel, pos).ok()
+    }
+
+    fn set_light(&mut self, pos: BlockPos, level: u8) {
+        let (chunk_pos, local) = pos.split();
+        let Some(chunk) = self.world.get_chunk_mut(chunk_pos) else { return };
+        if chunk.light().level(self.channel, local) != level {
+            chunk.light_mut().set_level(self.channel, local, level);
+            self.changed.insert(pos);
+        }
+    }
+
+    /// the light level the cell at ```pos``` has on its own: the emission of its block, or full skylight above the heightmap of a chunk open to the sky
+    fn source(&self, pos: BlockPos) -> u8 {
+        let (chunk_pos, local) = pos.split();
+        let Some(chunk) = self.world.get_chunk(chunk_pos) else { return 0 };
+        match self.channel {
+            LightChannel::Block => emission(self.registry, chunk.get(local)),
+            LightChannel::Sky => {
+                let open = !self.world.is_loaded(chunk_pos + IVec3::Y) && local.y() as u8 >= chunk.light().height(local.x(), local.z());
+                if open { MAX_LIGHT } else { 0 }
+            }
+        }
+    }
+
+    /// Lights the cell at ```pos``` with its ```source()``` level, if that is brighter than its current light
+    fn seed(&mut self, pos: BlockPos) {
+        let level = self.source(pos);
+        if level > self.light(pos).unwrap_or(0) {
+            self.set_light(pos, level);
+            self.lit.push_back(pos);
+        }
+    }
+
+    /// returns ```true``` if light of ```level``` keeps its level when moving towards ```facing```, which only full skylight going down does
+    fn keeps_level(&self, facing: Facing, level: u8) -> bool {
+        self.channel == LightChannel::Sky && facing == Facing::YNegative && level == MAX_LIGHT
+    }
+
+    /// the level light of ```level``` has after moving towards ```facing``` into ```block```
+    fn step(&self, level: u8, facing: Facing, block: &Block) -> u8 {
+        let opacity = self.registry.definition(block.block_type()).light_opacity;
+        if opacity >= MAX_LIGHT_OPACITY {
+            0
+        } else if opacity == 0 && self.keeps_level(facing, level) {
+            level
+        } else {
+            level.saturating_sub(opacity.max(1))
+        }
+    }
+
+    /// Breadth first flood fill from all ```lit``` cells
+    fn spread(&mut self) {
+        while let Some(pos) = self.lit.pop_front() {
+            let Some(level) = self.light(pos) else { continue };
+            if level <= 1 {
+                continue;
+            }
+            for facing in Facing::ALL {
+                let next = pos.neighbour(facing);
+                let Some(block) = self.block(next) else { continue };
+                let next_level = self.step(level, facing, &block);
+                if next_level > self.light(next).unwrap_or(0) {
+                    self.set_light(next, next_level);
+                    self.lit.push_back(next);
+                }
+            }
+        }
+    }
+
+    /// Breadth first removal pass starting at the loaded cells in ```positions```, which are darkened first.
+    /// Every neighbour that may have been lit through a darkened cell is darkened as well, brighter neighbours are queued in ```lit```
+    /// to fill the darkened area again. Afterwards every darkened cell is lit by its ```source()``` again.
+    fn darken(&mut self, positions: impl IntoIterator<Item = BlockPos>) {
+        let mut removed = VecDeque::new();
+        let mut darkened = Vec::new();
+        for pos in positions {
+            let Some(level) = self.light(pos) else { continue };
+            self.set_light(pos, 0);
+            removed.push_back((pos, level));
+            darkened.push(pos);
+        }
+
+        while let Some((pos, level)) = removed.pop_front() {
+            if level == 0 {
+                continue;
+            }
+            for facing in Facing::ALL {
+                let next = pos.neighbour(facing);
+                let Some(next_level) = self.light(next) else { continue };
+                if next_level == 0 {
+                    continue;
+                }
+                if next_level < level || next_level == level && self.keeps_level(facing, level) {
+                    // this cell may have been lit by the removed light
+                    self.set_light(next, 0);
+                    removed.push_back((next, next_level));
+                    darkened.push(next);
+                } else {
+                    self.lit.push_back(next);
+                }
+            }
+        }
+        for pos in darkened {
+            self.seed(pos);
+        }
+    }
+
+    /// Marks every chunk that shows a changed cell as dirty, border cells are visible from the chunks around them as well
+    fn finish(self) {
+        let mut chunks = HashSet::new();
+        for pos in &self.changed {
+            chunks.insert(pos.chunk());
+            chunks.extend(surrounding_offsets().map(|offset| (*pos + offset).chunk()));
+        }
+        for chunk_pos in chunks {
+            self.world.mark_dirty(chunk_pos);
+        }
+    }
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_registry::{test_block, test_registry};
+    use crate::chunk_logic::chunk::ChunkComp;
+
+    /// a world of air chunks from ```min``` to ```max``` (both inclusive) with the light computed
+    fn air_world(min: ChunkPos, max: ChunkPos) -> VoxelWorld {
+        let mut world = VoxelWorld::new();
+        for x in min.0.x..=max.0.x {
+            for y in min.0.y..=max.0.y {
+                for z in min.0.z..=max.0.z {
+                    world.insert_chunk(ChunkPos::new(x, y, z), ChunkComp::new_simple(test_block("air")));
+                }
+            }
+        }
+        relight_all(&mut world);
+        world
+    }
+
+    fn relight_all(world: &mut VoxelWorld) {
+        let chunk_positions: Vec<ChunkPos> = world.chunk_positions().collect();
+        for chunk_pos in chunk_positions {
+            light_chunk(world, test_registry(), chunk_pos);
+        }
+    }
+
+    fn light(world: &VoxelWorld, pos: BlockPos) -> u8 {
+        block_light(world, pos).unwrap()
+    }
+
+    fn sky(world: &VoxelWorld, pos: BlockPos) -> u8 {
+        sky_light(world, pos).unwrap()
+    }
+
+    fn place(world: &mut VoxelWorld, pos: BlockPos, name: &str) {
+        set_block(world, test_registry(), pos, test_block(name)).unwrap();
+    }
+
+    /// asserts that the incrementally updated light matches the light computed from scratch
+    fn assert_matches_fresh_light(world: &VoxelWorld) {
+        let mut fresh = VoxelWorld::new();
+        for chunk_pos in world.chunk_positions() {
+            fresh.insert_chunk(chunk_pos, ChunkComp::new(world.get_chunk(chunk_pos).unwrap().chunk().clone()));
+        }
+        relight_all(&mut fresh);
+        for chunk_pos in world.chunk_positions() {
+            assert_eq!(fresh.get_chunk(chunk_pos).unwrap().light(), world.get_chunk(chunk_pos).unwrap().light(), "light of the chunk at {chunk_pos} differs");
+        }
+    }
+
+    #[test]
+    fn test_nibble_storage() {
+        let mut light = ChunkLight::new();
+        assert!(light.is_dark(LightChannel::Block));
+        light.set_level(LightChannel::Block, LocalPos::at(3, 2, 4), 9);
+        light.set_level(LightChannel::Block, LocalPos::at(3, 2, 5), 15);
+        light.set_level(LightChannel::Block, LocalPos::at(3, 2, 6), 200);
+        light.set_level(LightChannel::Sky, LocalPos::at(3, 2, 4), 4);
+        assert_eq!(9, light.level(LightChannel::Block, LocalPos::at(3, 2, 4)));
+        assert_eq!(15, light.level(LightChannel::Block, LocalPos::at(3, 2, 5)));
+        assert_eq!(MAX_LIGHT, light.level(LightChannel::Block, LocalPos::at(3, 2, 6)));
+        assert_eq!(4, light.level(LightChannel::Sky, LocalPos::at(3, 2, 4)));
+        light.set_level(LightChannel::Block, LocalPos::at(3, 2, 5), 0);
+        assert_eq!(9, light.level(LightChannel::Block, LocalPos::at(3, 2, 4)));
+        assert_eq!(0, light.level(LightChannel::Block, LocalPos::at(3, 2, 5)));
+        assert!(!light.is_dark(LightChannel::Sky));
+    }
+
+    #[test]
+    fn test_emitter_falls_off_by_one_per_step() {
+        let mut world = air_world(ChunkPos::ZERO, ChunkPos::ZERO);
+        let emitter = BlockPos::new(8, 2, 8);
+        place(&mut world, emitter, "redstone_block");
+        assert_eq!(7, light(&world, emitter));
+        assert_eq!(6, light(&world, emitter + IVec3::X));
+        assert_eq!(5, light(&world, emitter + IVec3::new(-1, 1, 0)));
+        assert_eq!(1, light(&world, emitter + IVec3::new(4, 2, 0)));
+        assert_eq!(0, light(&world, emitter + IVec3::new(-7, 0, 0)));
+        assert_matches_fresh_light(&world);
+    }
+
+    #[test]
+    fn test_opaque_blocks_stop_light() {
+        let mut world = air_world(ChunkPos::ZERO, ChunkPos::ZERO);
+        // a stone wall across the whole chunk at x = 6 with a single gap at z = 12
+        let mut chunk = world.remove_chunk(ChunkPos::ZERO).unwrap();
+        chunk.fill_box(LocalPos::at(6, 0, 0), LocalPos::at(6, 4, 15), test_block("stone"));
+        chunk.set(LocalPos::at(6, 2, 12), test_block("air"));
+        world.insert_chunk(ChunkPos::ZERO, chunk);
+        light_chunk(&mut world, test_registry(), ChunkPos::ZERO);
+
+        place(&mut world, BlockPos::new(7, 2, 10), "redstone_block");
+        assert_eq!(0, light(&world, BlockPos::new(6, 2, 10)));
+        // the light has to go around through the gap: 3 steps into the gap, then 3 more back to z = 10 behind the wall
+        assert_eq!(7 - 3, light(&world, BlockPos::new(6, 2, 12)));
+        assert_eq!(7 - 6, light(&world, BlockPos::new(5, 2, 10)));
+        assert_matches_fresh_light(&world);
+    }
+
+    #[test]
+    fn test_light_crosses_chunk_borders() {
+        let mut world = air_world(ChunkPos::new(-1, 0, 0), ChunkPos::new(0, 0, 1));
+        world.take_dirty();
+        place(&mut world, BlockPos::new(0, 2, 15), "redstone_block");
+        assert_eq!(6, light(&world, BlockPos::new(-1, 2, 15)));
+        assert_eq!(6, light(&world, BlockPos::new(0, 2, 16)));
+        assert_eq!(5, light(&world, BlockPos::new(-1, 2, 16)));
+        assert_eq!(4, world.take_dirty().len());
+        assert_matches_fresh_light(&world);
+    }
+
+    #[test]
+    fn test_loading_a_chunk_next_to_light() {
+        let mut world = air_world(ChunkPos::ZERO, ChunkPos::ZERO);
+        place(&mut world, BlockPos::new(15, 2, 8), "redstone_block");
+        // unloaded chunks stay dark
+        assert_eq!(Err(WorldError::ChunkNotLoaded(ChunkPos::new(1, 0, 0))), block_light(&world, BlockPos::new(16, 2, 8)));
+
+        world.insert_chunk(ChunkPos::new(1, 0, 0), ChunkComp::new_simple(test_block("air")));
+        light_chunk(&mut world, test_registry(), ChunkPos::new(1, 0, 0));
+        assert_eq!(6, light(&world, BlockPos::new(16, 2, 8)));
+        assert_eq!(3, light(&world, BlockPos::new(19, 2, 8)));
+        assert_matches_fresh_light(&world);
+    }
+
+    #[test]
+    fn test_removing_an_emitter_unlights() {
+        let mut world = air_world(ChunkPos::ZERO, ChunkPos::new(1, 0, 0));
+        place(&mut world, BlockPos::new(15, 2, 8), "redstone_block");
+        place(&mut world, BlockPos::new(15, 2, 8), "air");
+        for chunk_pos in [ChunkPos::ZERO, ChunkPos::new(1, 0, 0)] {
+            assert!(world.get_chunk(chunk_pos).unwrap().light().is_dark(LightChannel::Block));
+        }
+    }
+
+    #[test]
+    fn test_removing_one_of_two_emitters() {
+        let mut world = air_world(ChunkPos::ZERO, ChunkPos::ZERO);
+        place(&mut world, BlockPos::new(4, 2, 8), "redstone_block");
+        place(&mut world, BlockPos::new(8, 2, 8), "redstone_block");
+        assert_eq!(5, light(&world, BlockPos::new(6, 2, 8)));
+        place(&mut world, BlockPos::new(8, 2, 8), "air");
+        // the remaining emitter lights the area of the removed one again
+        assert_eq!(5, light(&world, BlockPos::new(6, 2, 8)));
+        assert_eq!(3, light(&world, BlockPos::new(8, 2, 8)));
+        assert_eq!(0, light(&world, BlockPos::new(12, 2, 8)));
+        assert_matches_fresh_light(&world);
+    }
+
+    #[test]
+    fn test_placing_and_removing_opaque_blocks() {
+        let mut world = air_world(ChunkPos::ZERO, ChunkPos::ZERO);
+        place(&mut world, BlockPos::new(2, 2, 8), "redstone_block");
+        assert_eq!(3, light(&world, BlockPos::new(6, 2, 8)));
+
+        // a stone box around the emitter keeps the light inside
+        for offset in surrounding_offsets() {
+            place(&mut world, BlockPos::new(2, 2, 8) + offset, "stone");
+        }
+        assert_eq!(7, light(&world, BlockPos::new(2, 2, 8)));
+        assert_eq!(0, light(&world, BlockPos::new(4, 2, 8)));
+        assert_matches_fresh_light(&world);
+
+        // opening one side lets it out again
+        place(&mut world, BlockPos::new(3, 2, 8), "air");
+        assert_eq!(6, light(&world, BlockPos::new(3, 2, 8)));
+        assert_eq!(3, light(&world, BlockPos::new(6, 2, 8)));
+        assert_matches_fresh_light(&world);
+    }
+
+    #[test]
+    fn test_see_through_blocks_pass_light() {
+        let mut world = air_world(ChunkPos::ZERO, ChunkPos::ZERO);
+        place(&mut world, BlockPos::new(8, 2, 8), "redstone_block");
+        place(&mut world, BlockPos::new(9, 2, 8), "glass");
+        place(&mut world, BlockPos::new(10, 2, 8), "leaves");
+        assert_eq!(6, light(&world, BlockPos::new(9, 2, 8)));
+        assert_eq!(5, light(&world, BlockPos::new(10, 2, 8)));
+        assert_matches_fresh_light(&world);
+    }
+
+    #[test]
+    fn test_random_edits_match_fresh_light() {
+        let mut world = air_world(ChunkPos::new(-1, 0, -1), ChunkPos::new(0, 1, 0));
+        let names = ["redstone_block", "stone", "air", "glass", "air", "redstone_block", "leaves", "stone", "stone_slab"];
+        let mut seed = 7u32;
+        for step in 0..400 {
+            // a small linear congruential generator keeps the scene reproducible
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let pos = BlockPos::new((seed >> 8) as i32 % 12 - 6, (seed >> 4) as i32 % 10, (seed >> 16) as i32 % 12 - 6);
+            place(&mut world, pos, names[step % names.len()]);
+            if step % 50 == 0 {
+                assert_matches_fresh_light(&world);
+            }
+        }
+        assert_matches_fresh_light(&world);
+    }
+
+    #[test]
+    fn test_open_sky_is_fully_lit() {
+        let world = air_world(ChunkPos::ZERO, ChunkPos::new(0, 2, 0));
+        for y in 0..15 {
+            assert_eq!(MAX_LIGHT, sky(&world, BlockPos::new(3, y, 9)));
+        }
+        assert!(world.get_chunk(ChunkPos::ZERO).unwrap().light().is_dark(LightChannel::Block));
+    }
+
+    #[test]
+    fn test_skylight_spreads_under_overhangs() {
+        let mut world = air_world(ChunkPos::ZERO, ChunkPos::ZERO);
+        // a stone roof over the half of the chunk with x < 8
+        let mut chunk = world.remove_chunk(ChunkPos::ZERO).unwrap();
+        chunk.fill_box(LocalPos::at(0, 4, 0), LocalPos::at(7, 4, 15), test_block("stone"));
+        world.insert_chunk(ChunkPos::ZERO, chunk);
+        light_chunk(&mut world, test_registry(), ChunkPos::ZERO);
+
+        assert_eq!(5, world.get_chunk(ChunkPos::ZERO).unwrap().light().height(3, 3));
+        assert_eq!(0, world.get_chunk(ChunkPos::ZERO).unwrap().light().height(8, 3));
+        assert_eq!(MAX_LIGHT, sky(&world, BlockPos::new(8, 0, 3)));
+        assert_eq!(0, sky(&world, BlockPos::new(7, 4, 3)));
+        assert_eq!(14, sky(&world, BlockPos::new(7, 3, 3)));
+        assert_eq!(14, sky(&world, BlockPos::new(7, 0, 3)));
+        assert_eq!(10, sky(&world, BlockPos::new(3, 2, 3)));
+        assert_matches_fresh_light(&world);
+    }
+
+    #[test]
+    fn test_underground_is_dark() {
+        let mut world = VoxelWorld::new();
+        world.insert_chunk(ChunkPos::ZERO, ChunkComp::new_simple(test_block("stone")));
+        world.insert_chunk(ChunkPos::new(0, 1, 0), ChunkComp::new_simple(test_block("air")));
+        relight_all(&mut world);
+        // a cave in the stone that is only closed off by a single block
+        for x in 3..10 {
+            place(&mut world, BlockPos::new(x, 2, 5), "air");
+        }
+        place(&mut world, BlockPos::new(3, 3, 5), "air");
+        assert_eq!(0, sky(&world, BlockPos::new(6, 2, 5)));
+        assert_eq!(MAX_LIGHT, sky(&world, BlockPos::new(3, 5, 5)));
+
+        // digging the last block opens the cave
+        place(&mut world, BlockPos::new(3, 4, 5), "air");
+        assert_eq!(MAX_LIGHT, sky(&world, BlockPos::new(3, 2, 5)));
+        assert_eq!(MAX_LIGHT - 3, sky(&world, BlockPos::new(6, 2, 5)));
+        assert_matches_fresh_light(&world);
+
+        place(&mut world, BlockPos::new(3, 4, 5), "stone");
+        assert_eq!(0, sky(&world, BlockPos::new(6, 2, 5)));
+        assert_matches_fresh_light(&world);
+    }
+
+    #[test]
+    fn test_heightmap_ignores_see_through_blocks() {
+        let mut world = air_world(ChunkPos::ZERO, ChunkPos::ZERO);
+        let height = |world: &VoxelWorld| world.get_chunk(ChunkPos::ZERO).unwrap().light().height(4, 4);
+        place(&mut world, BlockPos::new(4, 3, 4), "glass");
+        assert_eq!(0, height(&world));
+        assert_eq!(MAX_LIGHT, sky(&world, BlockPos::new(4, 0, 4)));
+        place(&mut world, BlockPos::new(4, 1, 4), "leaves");
+        assert_eq!(2, height(&world));
+        assert_eq!(MAX_LIGHT - 1, sky(&world, BlockPos::new(4, 1, 4)));
+        place(&mut world, BlockPos::new(4, 1, 4), "air");
+        assert_eq!(0, height(&world));
+        assert_eq!(MAX_LIGHT, sky(&world, BlockPos::new(4, 0, 4)));
+        assert_matches_fresh_light(&world);
+    }
+
+    #[test]
+    fn test_stacked_chunks() {
+        let mut world = air_world(ChunkPos::ZERO, ChunkPos::ZERO);
+        assert_eq!(MAX_LIGHT, sky(&world, BlockPos::new(5, 0, 5)));
+
+        // a solid chunk on top takes the sky away from the one below
+        world.insert_chunk(ChunkPos::new(0, 1, 0), ChunkComp::new_simple(test_block("stone")));
+        light_chunk(&mut world, test_registry(), ChunkPos::new(0, 1, 0));
+        assert!(world.get_chunk(ChunkPos::ZERO).unwrap().light().is_dark(LightChannel::Sky));
+        assert_matches_fresh_light(&world);
+
+        // a shaft through the upper chunk lights the one below again
+        for y in 5..10 {
+            place(&mut world, BlockPos::new(5, y, 5), "air");
+        }
+        assert_eq!(MAX_LIGHT, sky(&world, BlockPos::new(5, 0, 5)));
+        assert_eq!(MAX_LIGHT - 2, sky(&world, BlockPos::new(6, 0, 6)));
+        assert_matches_fresh_light(&world);
+
+        unload_chunk(&mut world, test_registry(), ChunkPos::new(0, 1, 0));
+        assert_eq!(MAX_LIGHT, sky(&world, BlockPos::new(6, 0, 6)));
+        assert_matches_fresh_light(&world);
+    }
+}
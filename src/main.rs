@@ -9,47 +9,103 @@ use bevy::{
 };
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use bevy_egui::{egui, EguiContexts};
-use bevy_flycam::prelude::*;
-use chunk_logic::position_handling::to_chunk_coordinates;
+use block_registry::{BlockRegistry, BLOCK_DIRECTORY};
+use chunk_logic::position_handling::{BlockPos, ChunkPos, LocalPos};
+use chunk_logic::rendering::mesher::MesherSelection;
+use chunk_logic::rendering::smooth_lighting::BakedLighting;
+use chunk_logic::rendering::texture_atlas::{load_block_atlas, ChunkMaterials};
+use chunk_logic::world::{remesh_dirty_chunks, ChunkEntity, TranslucentChunkMesh, VoxelWorld};
+use chunk_logic::world_save::{save_world, WorldSave, WORLD_SAVE_FILE};
+use player_logic::game_mode::GameMode;
+use player_logic::inventory::Inventory;
+use player_logic::player::{grab_cursor, player_look, player_physics, spawn_player, switch_game_mode, toggle_cursor_grab, toggle_flight, update_player_camera, Player};
+use player_logic::player_save::save_player;
+use player_logic::block_interaction::{edit_targeted_block, select_block, spawn_target_outline, target_block, update_target_outline, BlockSelection, BreakProgress, TargetedBlock};
+use redstone_logic::redstone::RedstoneSimulation;
+use tick_logic::game_tick::game_tick;
+use tick_logic::neighbour_updates::BlockBehaviours;
+use tick_logic::scheduler::{TickScheduler, TICKS_PER_SECOND};
 // use chunks::{chunk::ChunkComp, rendering::rendering_const};
 
 // 'self' imports
+mod block_model;
+mod block_registry;
+mod block_state;
 mod blocks;
 // #[path ="./chunks/chunks.rs"]
 // pub mod chunks;
 // #[path ="./chunks/position_handling.rs"]
 // mod position_handling;
 mod chunk_logic;
+mod light_logic;
+mod player_logic;
+mod redstone_logic;
+mod save;
+mod tick_logic;
 
 fn main() {
+    let registry = BlockRegistry::load(BLOCK_DIRECTORY).unwrap_or_else(|error| panic!("invalid block definitions: {error}"));
+    // the redstone components react to their scheduled updates on top of the default behaviours
+    let mut behaviours = BlockBehaviours::with_defaults(&registry);
+    redstone_logic::redstone::register_behaviours(&mut behaviours, &registry);
     App::new()
         //sampeling for the renderer
         .insert_resource(Msaa::Sample4)
-        // movement speed for the fly_cam
-        .insert_resource(MovementSettings {
-            sensitivity: 0.00010, // default: 0.00012
-            speed: 12.0, // default: 12.0
-        })
-        // change Keybinds if necessary
-        .insert_resource(KeyBindings {
-            ..Default::default()
-        })
         // change wgpu setting to prevent texture blur
         .add_plugins(DefaultPlugins.set(RenderPlugin {
             wgpu_settings: WgpuSettings { features: WgpuFeatures::POLYGON_MODE_LINE, ..default()}
         }).set(ImagePlugin::default_nearest()))
         // setup for the wireframe mode
         .add_plugin(WireframePlugin)
-        // adds the fly_cam
-        .add_plugin(PlayerPlugin)
         //create the gui world inspector
         .add_plugin(WorldInspectorPlugin::new())
+        // the properties of every block type, loaded from the block definition files
+        .insert_resource(behaviours)
+        // the blocks the player can place in creative, selected with the number keys and the mouse wheel
+        .insert_resource(BlockSelection::from_registry(&registry))
+        .insert_resource(registry)
+        // holds the block data of every loaded chunk
+        .init_resource::<VoxelWorld>()
+        .init_resource::<MesherSelection>()
+        // bakes the voxel light into the chunk meshes, Numpad1 switches to the PBR lights of the scene
+        .init_resource::<BakedLighting>()
+        // the simulation runs on a fixed game tick, independent of the frame rate
+        .insert_resource(FixedTime::new_from_secs((1.0 / TICKS_PER_SECOND) as f32))
+        .init_resource::<TickScheduler>()
+        .init_resource::<RedstoneSimulation>()
+        .add_system(game_tick.in_schedule(CoreSchedule::FixedUpdate))
+        // the player collides with the blocks unless it is a spectator, its physics runs on the game tick as well and the camera moves smoothly in between
+        .add_startup_system(spawn_player)
+        .add_startup_system(grab_cursor)
+        .add_system(player_physics.in_schedule(CoreSchedule::FixedUpdate))
+        .add_system(toggle_cursor_grab)
+        // F4 and the UI switch the game mode, the player with its game mode and inventory is saved when it changes and when the game closes
+        .add_system(switch_game_mode)
+        .add_system(toggle_flight)
+        .add_system(save_player.in_base_set(CoreSet::Last))
+        .add_system(player_look)
+        .add_system(update_player_camera.after(player_look))
         .add_startup_system(setup)
+        // all chunks share one material with the texture atlas of every block,
+        // it is inserted with commands, which only apply at the end of a startup set, so it is loaded in the set before the chunks are spawned
+        .add_startup_system(load_block_atlas.in_base_set(StartupSet::PreStartup))
         .add_startup_system(spawn_chunks)
         .add_system(wireframe_toggle)
+        .add_system(mesher_toggle)
         // .add_system(debug_distance)
         .add_system(lightup_toggle)
         .add_system(ui_example_system)
+        // the world is saved every few seconds and when the game closes
+        .add_system(save_world.in_base_set(CoreSet::Last))
+        // the block the camera looks at is outlined, left click breaks it and right click places the selected block against it
+        .init_resource::<TargetedBlock>()
+        .init_resource::<BreakProgress>()
+        .add_startup_system(spawn_target_outline)
+        .add_system(select_block)
+        .add_system(target_block.after(update_player_camera).after(switch_game_mode))
+        .add_system(edit_targeted_block.after(target_block).after(select_block))
+        .add_system(update_target_outline.after(edit_targeted_block))
+        .add_system(remesh_dirty_chunks.after(mesher_toggle).after(lightup_toggle).after(ui_example_system).after(edit_targeted_block))
         .run();
 }
 
@@ -77,59 +133,107 @@ fn wireframe_toggle(mut wireframe_config: ResMut<WireframeConfig>, input: Res<In
         wireframe_config.global = !wireframe_config.global
     }
 }
-fn lightup_toggle(mut materials: ResMut<Assets<StandardMaterial>>, query: Query<&mut Handle<StandardMaterial>>, input: Res<Input<KeyCode>>) {
+fn mesher_toggle(mut mesher_selection: ResMut<MesherSelection>, mut voxel_world: ResMut<VoxelWorld>, input: Res<Input<KeyCode>>) {
+    if input.just_pressed(KeyCode::Numpad2) {
+        mesher_selection.select_next();
+        voxel_world.mark_all_dirty();
+    }
+}
+fn lightup_toggle(mut lighting: ResMut<BakedLighting>, mut materials: ResMut<Assets<StandardMaterial>>, chunk_materials: Res<ChunkMaterials>, mut voxel_world: ResMut<VoxelWorld>, input: Res<Input<KeyCode>>) {
     if input.just_pressed(KeyCode::Numpad1) {
-        for handle in query.iter() {
-            match materials.get_mut(handle) {
-                Some(material) => material.unlit = !material.unlit,
-                None => (),
+        // baked voxel lighting needs unlit materials, otherwise the PBR lights would shade the chunks a second time
+        lighting.enabled = !lighting.enabled;
+        for handle in [&chunk_materials.opaque, &chunk_materials.translucent] {
+            if let Some(material) = materials.get_mut(handle) {
+                material.unlit = lighting.enabled
             }
         }
+        voxel_world.mark_all_dirty();
     }
 }
 
-fn spawn_chunks(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<StandardMaterial>>, asset_server: Res<AssetServer>) {
+fn spawn_chunks(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, chunk_materials: Res<ChunkMaterials>, registry: Res<BlockRegistry>, mut voxel_world: ResMut<VoxelWorld>, mut scheduler: ResMut<TickScheduler>, mut simulation: ResMut<RedstoneSimulation>) {
 
-    let texture_handle_stone:Handle<Image> = asset_server.load("textures/stone.png");
-    let texture_handle_dirt:Handle<Image> = asset_server.load("textures/dirt.png");
+    // the saved world with its scheduled updates, a new one if there is none yet
+    match WorldSave::load(WORLD_SAVE_FILE) {
+        Ok(Some(save)) => (*voxel_world, *scheduler) = save.restore(&registry),
+        Ok(None) => generate_world(&registry, &mut voxel_world, &mut simulation),
+        Err(error) => {
+            warn!("{error}");
+            generate_world(&registry, &mut voxel_world, &mut simulation);
+        }
+    }
 
+    let chunk_positions: Vec<ChunkPos> = voxel_world.chunk_positions().collect();
+    for chunk_pos in chunk_positions {
+        // the mesh is filled in by remesh_dirty_chunks once all neighbours are known
+        let translucent_mesh = meshes.add(Mesh::new(bevy::render::mesh::PrimitiveTopology::TriangleList));
+        let entity = commands.spawn((PbrBundle {
+            mesh: meshes.add(Mesh::new(bevy::render::mesh::PrimitiveTopology::TriangleList)),
+            material: chunk_materials.opaque.clone(),
+            transform: Transform::from_translation(chunk_pos.to_world()),
+            ..Default::default()
+        }, ChunkEntity(chunk_pos), TranslucentChunkMesh(translucent_mesh.clone()))).with_children(|parent| {
+            parent.spawn(PbrBundle {
+                mesh: translucent_mesh,
+                material: chunk_materials.translucent.clone(),
+                ..Default::default()
+            });
+        }).id();
+        voxel_world.link_entity(chunk_pos, entity);
+    }
+}
+
+fn generate_world(registry: &BlockRegistry, voxel_world: &mut VoxelWorld, simulation: &mut RedstoneSimulation) {
+
+    let block = |name: &str| registry.block(name).unwrap_or_else(|| panic!("the block '{name}' is not defined"));
+    let [stone, dirt, grass, glass, leaves] = ["stone", "dirt", "grass", "glass", "leaves"].map(block);
+    let [slab, fence, pane, flower] = ["stone_slab", "fence", "glass_pane", "flower"].map(block);
+    let log = blocks::Block::new(block("log").block_type(), blocks::Facing::ZPositive);
+    let stairs = blocks::Block::new(block("planks_stairs").block_type(), blocks::Facing::ZNegative);
 
     //let x = 0;
     // let y = 0;
     //for y in 0..20{
         for x in -5..5{
-            let chunk = chunk_logic::chunk::ChunkComp::new_simple();
-            let white_material = materials.add(StandardMaterial {
-                base_color_texture: Some(texture_handle_stone.clone()),
-                unlit: false,
-                ..Default::default()
-            });
-            commands.spawn(PbrBundle {
-                mesh: meshes.add(chunk.build_mesh_culling()),
-                material: white_material.clone(),
-                transform: Transform::from_xyz((x * chunk_logic::chunk::CHUNK_SIZE_HORIZONTAL as i32) as f32, 0., (1 * chunk_logic::chunk::CHUNK_SIZE_HORIZONTAL) as f32),
-                ..Default::default()
-            });
-            let chunk = chunk_logic::chunk::ChunkComp::new_simple();
-            let white_material = materials.add(StandardMaterial {
-                base_color_texture: Some(texture_handle_dirt.clone()),
-                unlit: false,
-                ..Default::default()
-            });
-            commands.spawn(PbrBundle {
-                mesh: meshes.add(chunk.build_mesh_culling()),
-                material: white_material.clone(),
-                transform: Transform::from_xyz((x * chunk_logic::chunk::CHUNK_SIZE_HORIZONTAL as i32) as f32, 0., (2 * chunk_logic::chunk::CHUNK_SIZE_HORIZONTAL) as f32),
-                ..Default::default()
-            });
+            for z in 1..=2 {
+                let chunk_pos = ChunkPos::new(x, 0, z);
+                // stone with a layer of dirt and grass on top, a fallen log lying along z, a glass window into the dirt and a leaf bush
+                let mut chunk = chunk_logic::chunk::ChunkComp::new_simple(stone);
+                chunk.fill_box(LocalPos::at(0, 3, 0), LocalPos::at(15, 3, 15), dirt);
+                chunk.fill_box(LocalPos::at(0, 4, 0), LocalPos::at(15, 4, 15), grass);
+                chunk.fill_box(LocalPos::at(7, 4, 3), LocalPos::at(7, 4, 12), log);
+                chunk.fill_box(LocalPos::at(2, 3, 2), LocalPos::at(4, 4, 4), glass);
+                chunk.fill_box(LocalPos::at(11, 4, 11), LocalPos::at(13, 4, 13), leaves);
+                // a few non-cube models on top of the grass
+                chunk.set(LocalPos::at(2, 4, 10), slab);
+                chunk.set(LocalPos::at(3, 4, 10), stairs);
+                chunk.set(LocalPos::at(10, 4, 2), fence);
+                chunk.set(LocalPos::at(12, 4, 4), pane);
+                chunk.set(LocalPos::at(4, 4, 13), flower);
+                voxel_world.insert_chunk(chunk_pos, chunk);
+            }
         }
     // }
+
+    // light spreads across chunk borders, so every chunk is lit once all of them are loaded
+    let chunk_positions: Vec<ChunkPos> = voxel_world.chunk_positions().collect();
+    for chunk_pos in chunk_positions {
+        light_logic::light::light_chunk(voxel_world, registry, chunk_pos);
+    }
+
+    // a redstone block powering a wire that runs across a chunk border into a lamp
+    let [redstone_block, wire, lamp] = ["redstone_block", "redstone_wire", "redstone_lamp"].map(block);
+    redstone_logic::redstone::set_block(voxel_world, registry, simulation, BlockPos::new(8, 4, 22), redstone_block).unwrap();
+    for x in 9..24 {
+        redstone_logic::redstone::set_block(voxel_world, registry, simulation, BlockPos::new(x, 4, 22), wire).unwrap();
+    }
+    redstone_logic::redstone::set_block(voxel_world, registry, simulation, BlockPos::new(24, 4, 22), lamp).unwrap();
 }
-fn ui_example_system(query: Query<(&mut Transform, &Camera)>, mut contexts: EguiContexts) {
+#[allow(clippy::too_many_arguments)]
+fn ui_example_system(query: Query<(&mut Transform, &Camera)>, mut contexts: EguiContexts, mesher_selection: Res<MesherSelection>, mut lighting: ResMut<BakedLighting>, mut voxel_world: ResMut<VoxelWorld>, scheduler: Res<TickScheduler>, registry: Res<BlockRegistry>, targeted: Res<TargetedBlock>, progress: Res<BreakProgress>, selection: Res<BlockSelection>, mut players: Query<(&mut Player, &Inventory)>) {
     let translation = query.single().0.translation;
-    let offset_chunk_grid = chunk_logic::chunk::CHUNK_SIZE_HORIZONTAL as f32;
-    let b = chunk_logic::position_handling::chunk_translation_fix((translation - (translation % offset_chunk_grid)) / offset_chunk_grid);
-    let c = translation % offset_chunk_grid;
+    let (chunk_pos, local) = BlockPos::from_world(translation).split();
     egui::Window::new("Info").show(contexts.ctx_mut(), |ui| {
         ui.label("Position:");
         ui.label(translation.round().to_string());
@@ -138,17 +242,68 @@ fn ui_example_system(query: Query<(&mut Transform, &Camera)>, mut contexts: Egui
         ui.label(translation.distance(bevy::math::Vec3::ZERO).to_string());
         ui.separator();
         ui.label("Chunk coordinates:");
-        ui.label(b.round().to_string());
+        ui.label(chunk_pos.to_string());
         ui.separator();
         ui.label("In-chunk coordinates:");
-        ui.label(c.round().to_string());
-        ui.label("Chunk coordinates 2222:");
-        ui.label(to_chunk_coordinates(c).to_string());
+        ui.label(local.to_string());
+        ui.separator();
+        ui.label("Mesher (Numpad2):");
+        ui.label(mesher_selection.active().name());
+        ui.separator();
+        ui.label("Lighting (Numpad1):");
+        ui.label(if lighting.enabled { "Baked voxel light" } else { "PBR" });
+        if lighting.enabled {
+            let mut daylight = lighting.daylight;
+            if ui.add(egui::Slider::new(&mut daylight, 0.0..=1.0).text("Daylight")).changed() {
+                lighting.daylight = daylight;
+                voxel_world.mark_all_dirty();
+            }
+        }
+        ui.separator();
+        let stats = scheduler.stats();
+        ui.label(format!("Game tick: {} ({TICKS_PER_SECOND} TPS)", scheduler.tick()));
+        ui.label(format!("Scheduled updates: {} queued, {} ran, {} deferred", stats.queued, stats.ran, stats.deferred));
+        ui.label(format!("Tick time: {:.3} ms", stats.duration.as_secs_f64() * 1000.0));
+        ui.separator();
+        let Ok((mut player, inventory)) = players.get_single_mut() else { return };
+        ui.label("Game mode (F4):");
+        ui.horizontal(|ui| {
+            for game_mode in GameMode::ALL {
+                if ui.selectable_label(player.game_mode == game_mode, game_mode.name()).clicked() && player.game_mode != game_mode {
+                    player.set_game_mode(game_mode);
+                }
+            }
+        });
+        if player.game_mode == GameMode::Creative {
+            ui.label(if player.flying { "Flying (land to stop)" } else { "Walking (double tap Space to fly)" });
+        }
+        ui.separator();
+        ui.label("Targeted block:");
+        match targeted.0.and_then(|hit| voxel_world.get_block(hit.block_pos).ok().map(|block| (hit, registry.describe(block)))) {
+            Some((hit, description)) => ui.label(format!("{description} at {} ({:?} face)", hit.block_pos, hit.face)),
+            None => ui.label("-"),
+        };
+        if let Some((block_pos, fraction)) = progress.progress() {
+            ui.label(format!("Breaking {block_pos}: {:.0}%", fraction * 100.0));
+        }
+        ui.label("Selected block (1-9, mouse wheel):");
+        if player.game_mode.uses_inventory() {
+            for (index, slot) in inventory.slots().iter().enumerate() {
+                let marker = if index == inventory.selected_index() { ">" } else { " " };
+                match slot {
+                    Some(stack) => ui.label(format!("{marker} {}: {} x{}", index + 1, registry.describe(&stack.block()), stack.count)),
+                    None => ui.label(format!("{marker} {}: -", index + 1)),
+                };
+            }
+        } else {
+            ui.label(selection.selected().map_or_else(|| "-".to_string(), |block| registry.describe(&block)));
+        }
         ui.separator();
     });
 }
 
 
+#[allow(clippy::type_complexity)]
 fn debug_distance(mut param_set: ParamSet<(Query<(&mut Transform, &Handle<Mesh>)>, Query<&mut Transform, &Camera>)>) {
 
     let cam_pos = param_set.p1().single().translation;
@@ -160,6 +315,7 @@ fn debug_distance(mut param_set: ParamSet<(Query<(&mut Transform, &Handle<Mesh>)
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn debug_player(mut param_set: ParamSet<(Query<(&mut Transform, &Handle<Mesh>)>, Query<&mut Transform, &Camera>)>) {
 
     let cam_pos = param_set.p1().single().translation;
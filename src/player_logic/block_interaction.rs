@@ -0,0 +1,500 @@
+// foreign imports
+use bevy::input::mouse::MouseWheel;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::window::PrimaryWindow;
+
+// 'self' imports
+use crate::block_registry::BlockRegistry;
+use crate::blocks::{Block, BlockId, Facing};
+use crate::chunk_logic::position_handling::BlockPos;
+use crate::chunk_logic::raycast::{raycast, RaycastHit};
+use crate::chunk_logic::world::{VoxelWorld, WorldError};
+use crate::player_logic::collision::Aabb;
+use crate::player_logic::game_mode::GameMode;
+use crate::player_logic::inventory::Inventory;
+use crate::player_logic::player::{is_cursor_grabbed, Player};
+use crate::redstone_logic::redstone::{self, RedstoneComponent, RedstoneSimulation};
+use crate::tick_logic::neighbour_updates::BlockBehaviours;
+
+/// How far away from the camera blocks can be targeted
+pub const REACH: f32 = 8.0;
+
+/// How long holding the left mouse button waits after a block broke before it starts on the next one, in seconds
+pub const BREAK_DELAY: f32 = 0.25;
+
+/// How much bigger than a block the outline around the targeted block is, so it is not hidden by the faces of the block
+const OUTLINE_MARGIN: f32 = 0.005;
+
+/// The number keys that select the first blocks of the ```BlockSelection```
+const SELECTION_KEYS: [KeyCode; 9] = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4, KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9];
+
+/// # Description:
+/// The block the camera looks at, updated every frame by ```target_block```
+#[derive(Resource, Default, Clone, Copy, PartialEq, Debug)]
+pub struct TargetedBlock(pub Option<RaycastHit>);
+
+/// # Description:
+/// Marks the entity that outlines the targeted block
+#[derive(Component)]
+pub struct TargetOutline;
+
+/// # Description:
+/// How far breaking the targeted block has come while the left mouse button is held, see ```GameMode::break_time()``` for how long it takes
+/// # Structure:
+/// ```
+/// pub struct BreakProgress {
+///     target: Option<BlockPos>,
+///     elapsed: f32,
+///     duration: f32,
+/// }
+/// ```
+/// ```elapsed``` is negative during the ```BREAK_DELAY``` after a block broke.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Debug)]
+pub struct BreakProgress {
+    target: Option<BlockPos>,
+    elapsed: f32,
+    duration: f32,
+}
+
+impl BreakProgress {
+
+    /// # Description:
+    /// Keeps breaking the block at ```target``` for another ```dt``` seconds, returns ```true``` once it has been broken for ```duration``` seconds.
+    /// Turning to another block starts over.
+    pub fn advance(&mut self, target: BlockPos, duration: f32, dt: f32) -> bool {
+        if self.target != Some(target) {
+            self.target = Some(target);
+            self.elapsed = self.elapsed.min(0.0);
+        }
+        self.duration = duration;
+        self.elapsed += dt;
+        if self.elapsed < duration {
+            return false;
+        }
+        self.target = None;
+        self.elapsed = -BREAK_DELAY;
+        true
+    }
+
+    /// # Description:
+    /// Decides whether the block at ```target``` with ```hardness``` breaks in this frame, ```held``` and ```clicked``` tell whether the button is held down and whether it was just pressed.
+    ///
+    /// Creative breaks the block instantly on every click, holding the button breaks nothing more.
+    /// Survival breaks it once the button has been held on it for the ```GameMode::break_time()``` (see ```advance()```).
+    pub fn mine(&mut self, target: BlockPos, game_mode: GameMode, hardness: f32, held: bool, clicked: bool, dt: f32) -> bool {
+        match game_mode.break_time(hardness) {
+            Some(_) if game_mode.breaks_instantly() => {
+                self.reset();
+                clicked
+            }
+            Some(duration) if held => self.advance(target, duration, dt),
+            _ => {
+                self.reset();
+                false
+            }
+        }
+    }
+
+    /// # Description:
+    /// Stops breaking, e.g. when the mouse button is released
+    pub fn reset(&mut self) {
+        *self = BreakProgress::default();
+    }
+
+    /// # Description:
+    /// The block that is being broken and how much of it is done, from 0 to 1
+    pub fn progress(&self) -> Option<(BlockPos, f32)> {
+        let fraction = if self.duration > 0.0 { (self.elapsed / self.duration).clamp(0.0, 1.0) } else { 0.0 };
+        self.target.map(|target| (target, fraction))
+    }
+}
+
+/// # Description:
+/// The blocks that can be placed in creative and which of them right click places. The number keys select one of the first nine, the mouse wheel cycles through all of them.
+/// # Structure:
+/// ```
+/// pub struct BlockSelection {
+///     blocks: Vec<Block>,
+///     selected: usize,
+/// }
+/// ```
+#[derive(Resource, Clone, Debug)]
+pub struct BlockSelection {
+    blocks: Vec<Block>,
+    selected: usize,
+}
+
+impl BlockSelection {
+
+    /// # Description:
+    /// The default state of every block type of ```registry``` but air, in ID order
+    pub fn from_registry(registry: &BlockRegistry) -> Self {
+        let blocks = registry.iter()
+            .filter(|(id, _)| *id != BlockId::AIR)
+            .map(|(id, _)| Block::new(id, Facing::XPositive))
+            .collect();
+        BlockSelection { blocks, selected: 0 }
+    }
+
+    /// # Description:
+    /// The block right click places, ```None``` if there are no blocks to choose from
+    pub fn selected(&self) -> Option<Block> {
+        self.blocks.get(self.selected).copied()
+    }
+
+    /// # Description:
+    /// Selects the block at ```index```, indices past the end are ignored
+    pub fn select(&mut self, index: usize) {
+        if index < self.blocks.len() {
+            self.selected = index;
+        }
+    }
+
+    /// # Description:
+    /// Moves the selection by ```steps``` blocks, wrapping around at both ends
+    pub fn scroll(&mut self, steps: i32) {
+        if !self.blocks.is_empty() {
+            self.selected = (self.selected as i32 + steps).rem_euclid(self.blocks.len() as i32) as usize;
+        }
+    }
+}
+
+/// # Description:
+/// Removes the targeted block. Returns the removed ```Block```.
+///
+/// The block is replaced through ```redstone::set_block()```, so the light, the redstone and the neighbour updates of the next tick react to it
+/// and only the chunks that show the block are remeshed.
+/// # Errors:
+/// ```WorldError::ChunkNotLoaded``` if the chunk of the hit was unloaded in the meantime
+pub fn break_block(world: &mut VoxelWorld, registry: &BlockRegistry, simulation: &mut RedstoneSimulation, hit: &RaycastHit) -> Result<Block, WorldError> {
+    redstone::set_block(world, registry, simulation, hit.block_pos, Block::new(BlockId::AIR, Facing::XPositive))
+}
+
+/// # Description:
+/// Places ```block``` against the hit face of the targeted block, turned by ```placement_facing()``` for a camera looking along ```look```.
+/// Returns where the block was placed, or ```None``` if that position is taken or not loaded, or if the ```behaviours``` say the block would not be supported there
+/// (e.g. wire in mid air or a torch under a ceiling). See ```break_block()``` for how the world is updated.
+pub fn place_block(world: &mut VoxelWorld, registry: &BlockRegistry, behaviours: &BlockBehaviours, simulation: &mut RedstoneSimulation, hit: &RaycastHit, block: Block, look: Vec3) -> Option<BlockPos> {
+    let block_pos = hit.adjacent();
+    if !world.get_block(block_pos).is_ok_and(Block::is_air) {
+        return None;
+    }
+    let block = Block::new(block.block_type(), placement_facing(registry, &block, hit.face, look)).with_state(block.state());
+    if !behaviours.can_place(world, registry, block_pos, &block) {
+        return None;
+    }
+    redstone::set_block(world, registry, simulation, block_pos, block).ok()?;
+    Some(block_pos)
+}
+
+/// # Description:
+/// The ```Facing``` of a placed block: torches face away from the face they are attached to, every other block faces the horizontal direction the camera looks in
+/// (e.g. repeaters and comparators send their signal away from the player)
+pub fn placement_facing(registry: &BlockRegistry, block: &Block, face: Facing, look: Vec3) -> Facing {
+    if registry.definition(block.block_type()).redstone == RedstoneComponent::Torch {
+        return face;
+    }
+    match (look.x.abs() >= look.z.abs(), look.x >= 0.0, look.z >= 0.0) {
+        (true, true, _) => Facing::XPositive,
+        (true, false, _) => Facing::XNegative,
+        (false, _, true) => Facing::ZPositive,
+        (false, _, false) => Facing::ZNegative,
+    }
+}
+
+/// # Description:
+/// Spawns the (hidden) outline that ```update_target_outline``` moves onto the targeted block
+pub fn spawn_target_outline(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<StandardMaterial>>) {
+    commands.spawn((PbrBundle {
+        mesh: meshes.add(outline_mesh()),
+        material: materials.add(StandardMaterial { base_color: Color::BLACK, unlit: true, ..default() }),
+        visibility: Visibility::Hidden,
+        ..default()
+    }, TargetOutline, Name::new("Target outline")));
+}
+
+/// # Description:
+/// System that casts a ray from the camera along its view direction and remembers the block it hits, spectators target no blocks
+pub fn target_block(camera: Query<(&Transform, &Player)>, voxel_world: Res<VoxelWorld>, mut targeted: ResMut<TargetedBlock>) {
+    let Ok((transform, player)) = camera.get_single() else { return };
+    let hit = if player.game_mode.edits_blocks() { raycast(&voxel_world, transform.translation, transform.forward(), REACH) } else { None };
+    if targeted.0 != hit {
+        targeted.0 = hit;
+    }
+}
+
+/// # Description:
+/// System that moves the outline onto the targeted block and hides it while nothing is targeted
+pub fn update_target_outline(targeted: Res<TargetedBlock>, mut outline: Query<(&mut Transform, &mut Visibility), With<TargetOutline>>) {
+    if !targeted.is_changed() {
+        return;
+    }
+    for (mut transform, mut visibility) in &mut outline {
+        match targeted.0 {
+            Some(hit) => {
+                transform.translation = hit.block_pos.to_world();
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+/// # Description:
+/// System that selects the block to place with the number keys and the mouse wheel, from the ```Inventory``` in survival and from the ```BlockSelection``` otherwise
+pub fn select_block(mut selection: ResMut<BlockSelection>, mut players: Query<(&Player, &mut Inventory)>, input: Res<Input<KeyCode>>, mut wheel: EventReader<MouseWheel>) {
+    let index = SELECTION_KEYS.iter().position(|key| input.just_pressed(*key));
+    let scrolled: f32 = wheel.iter().map(|event| event.y).sum();
+    let steps = if scrolled != 0.0 { -scrolled.signum() as i32 } else { 0 };
+    if index.is_none() && steps == 0 {
+        return;
+    }
+    for (player, mut inventory) in &mut players {
+        if player.game_mode.uses_inventory() {
+            if let Some(index) = index {
+                inventory.select(index);
+            }
+            inventory.scroll(steps);
+        } else {
+            if let Some(index) = index {
+                selection.select(index);
+            }
+            selection.scroll(steps);
+        }
+    }
+}
+
+/// # Description:
+/// System that breaks the targeted block with the left mouse button and places the selected block against it on right click.
+/// Clicks only count while the game holds the cursor, so clicking into the UI does not change the world.
+///
+/// How breaking works depends on the ```GameMode``` of the player, see ```BreakProgress::mine()```. In survival broken blocks go into the ```Inventory``` and placing takes them out of it again.
+/// Blocks that collide are not placed where they would overlap the body of the player, blocks that need support only where they have it.
+#[allow(clippy::too_many_arguments)]
+pub fn edit_targeted_block(time: Res<Time>, mouse: Res<Input<MouseButton>>, window: Query<&Window, With<PrimaryWindow>>, mut camera: Query<(&Transform, &Player, &mut Inventory)>, targeted: Res<TargetedBlock>, selection: Res<BlockSelection>, mut progress: ResMut<BreakProgress>, mut voxel_world: ResMut<VoxelWorld>, registry: Res<BlockRegistry>, behaviours: Res<BlockBehaviours>, mut simulation: ResMut<RedstoneSimulation>) {
+    let grabbed = window.get_single().is_ok_and(is_cursor_grabbed);
+    let (Some(hit), true, Ok((transform, player, mut inventory))) = (targeted.0, grabbed, camera.get_single_mut()) else {
+        progress.reset();
+        return;
+    };
+    let (held, clicked) = (mouse.pressed(MouseButton::Left), mouse.just_pressed(MouseButton::Left));
+    if held || clicked {
+        // the chunk of a hit from this frame is loaded
+        let Ok(block) = voxel_world.get_block(hit.block_pos).copied() else { return };
+        let hardness = registry.definition(block.block_type()).hardness;
+        if !progress.mine(hit.block_pos, player.game_mode, hardness, held, clicked, time.delta_seconds()) {
+            return;
+        }
+        let broken = break_block(&mut voxel_world, &registry, &mut simulation, &hit);
+        if let (Ok(broken), true) = (broken, player.game_mode.uses_inventory()) {
+            // without room in the inventory the block is lost
+            inventory.add(broken.block_type());
+        }
+        return;
+    }
+    progress.reset();
+    if mouse.just_pressed(MouseButton::Right) {
+        let block = if player.game_mode.uses_inventory() { inventory.selected().map(|stack| stack.block()) } else { selection.selected() };
+        let Some(block) = block else { return };
+        let target = hit.adjacent().to_world();
+        if registry.definition(block.block_type()).collision && Aabb::new(target, target + Vec3::ONE).intersects(&player.body()) {
+            return;
+        }
+        if place_block(&mut voxel_world, &registry, &behaviours, &mut simulation, &hit, block, transform.forward()).is_some() && player.game_mode.uses_inventory() {
+            inventory.take_selected();
+        }
+    }
+}
+
+/// the twelve edges of a block as a line list, slightly bigger than the block
+fn outline_mesh() -> Mesh {
+    let corners: Vec<[f32; 3]> = (0..8)
+        .map(|corner| [0, 1, 2].map(|axis| if corner >> axis & 1 == 1 { 1.0 + OUTLINE_MARGIN } else { -OUTLINE_MARGIN }))
+        .collect();
+    // every pair of corners that differs in exactly one axis
+    let mut edges = Vec::new();
+    for corner in 0..8u32 {
+        for axis in 0..3 {
+            if corner >> axis & 1 == 0 {
+                edges.extend([corner, corner | 1 << axis]);
+            }
+        }
+    }
+    let mut mesh = Mesh::new(PrimitiveTopology::LineList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, corners);
+    mesh.set_indices(Some(Indices::U32(edges)));
+    mesh
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use crate::block_registry::{test_block, test_registry};
+    use crate::block_state::PropertyValue;
+    use crate::chunk_logic::position_handling::ChunkPos;
+    use crate::chunk_logic::world::test_floor_world;
+    use crate::light_logic::light;
+    use super::*;
+
+    /// two lit chunks along x, stone up to y = 1 with air above
+    fn floor_world() -> VoxelWorld {
+        let mut world = test_floor_world(0..2);
+        for x in 0..2 {
+            light::light_chunk(&mut world, test_registry(), ChunkPos::new(x, 0, 0));
+        }
+        world.take_dirty();
+        world
+    }
+
+    fn look_at(world: &VoxelWorld, block_pos: BlockPos) -> RaycastHit {
+        let hit = raycast(world, block_pos.center() + Vec3::new(0.0, 2.5, 0.0), Vec3::NEG_Y, REACH).unwrap();
+        assert_eq!(block_pos, hit.block_pos);
+        hit
+    }
+
+    #[test]
+    fn test_break_and_place_remesh_only_affected_chunks() {
+        let registry = test_registry();
+        let behaviours = BlockBehaviours::with_defaults(registry);
+        let mut world = floor_world();
+        let mut simulation = RedstoneSimulation::new();
+
+        let hit = look_at(&world, BlockPos::new(8, 1, 8));
+        assert_eq!(Ok(test_block("stone")), break_block(&mut world, registry, &mut simulation, &hit));
+        assert_eq!(vec![ChunkPos::ZERO], world.take_dirty());
+        assert_eq!(vec![BlockPos::new(8, 1, 8)], world.take_changes());
+
+        // the block on the border is visible from the chunk next to it
+        let hit = look_at(&world, BlockPos::new(15, 1, 8));
+        let placed = place_block(&mut world, registry, &behaviours, &mut simulation, &hit, test_block("planks"), Vec3::X);
+        assert_eq!(Some(BlockPos::new(15, 2, 8)), placed);
+        assert_eq!(test_block("planks"), *world.get_block(BlockPos::new(15, 2, 8)).unwrap());
+        let mut dirty = world.take_dirty();
+        dirty.sort_by_key(|chunk_pos| chunk_pos.0.x);
+        assert_eq!(vec![ChunkPos::ZERO, ChunkPos::new(1, 0, 0)], dirty);
+    }
+
+    #[test]
+    fn test_place_needs_free_space() {
+        let registry = test_registry();
+        let behaviours = BlockBehaviours::with_defaults(registry);
+        let mut world = floor_world();
+        let mut simulation = RedstoneSimulation::new();
+        world.set_block(BlockPos::new(4, 2, 4), test_block("glass")).unwrap();
+
+        let hit = raycast(&world, Vec3::new(2.5, 2.5, 4.5), Vec3::X, REACH).unwrap();
+        assert_eq!(Facing::XNegative, hit.face);
+        world.set_block(BlockPos::new(3, 2, 4), test_block("leaves")).unwrap();
+        assert_eq!(None, place_block(&mut world, registry, &behaviours, &mut simulation, &hit, test_block("planks"), Vec3::X));
+        assert_eq!(test_block("leaves"), *world.get_block(BlockPos::new(3, 2, 4)).unwrap());
+
+        // the hit face borders an unloaded chunk
+        let hit = RaycastHit { block_pos: BlockPos::new(20, 1, 0), face: Facing::ZNegative, point: Vec3::new(20.5, 1.5, 0.0), distance: 1.0 };
+        assert_eq!(None, place_block(&mut world, registry, &behaviours, &mut simulation, &hit, test_block("planks"), Vec3::X));
+    }
+
+    #[test]
+    fn test_place_needs_support() {
+        let registry = test_registry();
+        let behaviours = BlockBehaviours::with_defaults(registry);
+        let mut world = floor_world();
+        let mut simulation = RedstoneSimulation::new();
+        world.set_block(BlockPos::new(4, 2, 4), test_block("stone")).unwrap();
+        world.set_block(BlockPos::new(8, 3, 8), test_block("stone")).unwrap();
+        world.set_block(BlockPos::new(9, 2, 4), test_block("stone_slab")).unwrap();
+
+        // wire hangs in the air next to the side of a block
+        let hit = RaycastHit { block_pos: BlockPos::new(8, 3, 8), face: Facing::XNegative, point: Vec3::new(8.0, 3.5, 8.5), distance: 1.0 };
+        assert_eq!(None, place_block(&mut world, registry, &behaviours, &mut simulation, &hit, test_block("redstone_wire"), Vec3::X));
+        // but rests on top of it
+        let hit = look_at(&world, BlockPos::new(4, 2, 4));
+        assert_eq!(Some(BlockPos::new(4, 3, 4)), place_block(&mut world, registry, &behaviours, &mut simulation, &hit, test_block("redstone_wire"), Vec3::NEG_Y));
+
+        // torches do not hang from the block above them, but stand on the wall next to it
+        let hit = RaycastHit { block_pos: BlockPos::new(8, 3, 8), face: Facing::YNegative, point: Vec3::new(8.5, 3.0, 8.5), distance: 1.0 };
+        assert_eq!(None, place_block(&mut world, registry, &behaviours, &mut simulation, &hit, test_block("redstone_torch"), Vec3::Y));
+        let hit = RaycastHit { block_pos: BlockPos::new(8, 3, 8), face: Facing::XPositive, point: Vec3::new(9.0, 3.5, 8.5), distance: 1.0 };
+        assert_eq!(Some(BlockPos::new(9, 3, 8)), place_block(&mut world, registry, &behaviours, &mut simulation, &hit, test_block("redstone_torch"), Vec3::NEG_X));
+
+        // the top of a slab is not a full face
+        let hit = look_at(&world, BlockPos::new(9, 2, 4));
+        assert_eq!(None, place_block(&mut world, registry, &behaviours, &mut simulation, &hit, test_block("flower"), Vec3::NEG_Y));
+        assert!(world.get_block(BlockPos::new(9, 3, 4)).unwrap().is_air());
+    }
+
+    #[test]
+    fn test_placement_facing() {
+        let registry = test_registry();
+        let behaviours = BlockBehaviours::with_defaults(registry);
+        let mut world = floor_world();
+        let mut simulation = RedstoneSimulation::new();
+        world.set_block(BlockPos::new(4, 2, 4), test_block("stone")).unwrap();
+
+        // torches face away from the face they are placed on
+        let hit = raycast(&world, Vec3::new(6.5, 2.5, 4.5), Vec3::NEG_X, REACH).unwrap();
+        let torch = registry.with_property(test_block("redstone_torch"), "lit", PropertyValue::Bool(true)).unwrap();
+        place_block(&mut world, registry, &behaviours, &mut simulation, &hit, torch, Vec3::NEG_X).unwrap();
+        let placed = *world.get_block(BlockPos::new(5, 2, 4)).unwrap();
+        assert_eq!(Facing::XPositive, placed.face_direction());
+        assert_eq!(Ok(PropertyValue::Bool(true)), registry.property(&placed, "lit"));
+
+        // repeaters point where the camera looks, ignoring the pitch
+        let repeater = test_block("repeater");
+        assert_eq!(Facing::ZNegative, placement_facing(registry, &repeater, Facing::YPositive, Vec3::new(0.3, -0.9, -0.4)));
+        assert_eq!(Facing::XNegative, placement_facing(registry, &repeater, Facing::YPositive, Vec3::new(-0.5, -0.5, 0.4)));
+    }
+
+    #[test]
+    fn test_break_progress() {
+        let stone = BlockPos::new(4, 1, 4);
+        let dirt = BlockPos::new(5, 1, 4);
+        let mut progress = BreakProgress::default();
+        assert!(!progress.advance(stone, 1.0, 0.5));
+        assert_eq!(Some((stone, 0.5)), progress.progress());
+        // looking at another block starts over
+        assert!(!progress.advance(dirt, 0.75, 0.5));
+        assert!(progress.advance(dirt, 0.75, 0.5));
+        assert_eq!(None, progress.progress());
+
+        // even blocks that break instantly wait for the delay after the last break
+        assert!(!progress.advance(stone, 0.0, BREAK_DELAY / 2.0));
+        assert!(progress.advance(stone, 0.0, BREAK_DELAY / 2.0));
+        progress.reset();
+        assert!(progress.advance(dirt, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_creative_breaks_instantly() {
+        let stone = BlockPos::new(4, 1, 4);
+        let dirt = BlockPos::new(5, 1, 4);
+        let mut progress = BreakProgress::default();
+        // every click breaks a block right away, however hard it is, and without a delay in between
+        assert!(progress.mine(stone, GameMode::Creative, 50.0, true, true, 0.0));
+        assert!(progress.mine(dirt, GameMode::Creative, 0.5, true, true, 0.0));
+        // holding the button does not break more blocks
+        assert!(!progress.mine(stone, GameMode::Creative, 1.5, true, false, 10.0));
+        assert_eq!(None, progress.progress());
+
+        // survival has to hold the button for the break time, spectators break nothing
+        assert!(!progress.mine(stone, GameMode::Survival, 1.0, true, true, 1.0));
+        assert!(progress.mine(stone, GameMode::Survival, 1.0, true, false, 0.5));
+        assert!(!progress.mine(stone, GameMode::Spectator, 0.0, true, true, 1.0));
+    }
+
+    #[test]
+    fn test_selection() {
+        let mut selection = BlockSelection::from_registry(test_registry());
+        assert_eq!(Some(test_block("stone")), selection.selected());
+        selection.scroll(-1);
+        assert_eq!(Some(test_block("redstone_lamp")), selection.selected());
+        selection.scroll(2);
+        assert_eq!(Some(test_block("dirt")), selection.selected());
+        selection.select(100);
+        assert_eq!(Some(test_block("dirt")), selection.selected());
+        selection.select(8);
+        assert_eq!(Some(test_block("planks")), selection.selected());
+    }
+}
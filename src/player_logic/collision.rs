@@ -0,0 +1,478 @@
+// foreign imports
+use bevy::prelude::{BVec3, IVec3, Vec3};
+
+// 'self' imports
+use crate::block_model::MODEL_RESOLUTION;
+use crate::block_registry::BlockRegistry;
+use crate::chunk_logic::position_handling::BlockPos;
+use crate::chunk_logic::world::VoxelWorld;
+
+/// How far a body may overlap a collider (or keep away from it) because of float rounding and still count as touching it
+pub const EPSILON: f32 = 1e-4;
+
+/// How much a sneaking body cuts its horizontal motion per try until it stays on the ledge, see ```clamp_to_ledge()```
+const SNEAK_STEP: f32 = 0.05;
+
+/// # Description:
+/// An axis aligned box in world space, used for the bodies that move through the world and for the collision boxes of blocks
+/// # Structure:
+/// ```
+/// pub struct Aabb {
+///     pub min: Vec3,
+///     pub max: Vec3,
+/// }
+/// ```
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+
+    /// # Description:
+    /// The box between two corners, in any order
+    pub fn new(corner_a: Vec3, corner_b: Vec3) -> Self {
+        Aabb { min: corner_a.min(corner_b), max: corner_a.max(corner_b) }
+    }
+
+    /// # Description:
+    /// A body standing at ```feet``` (the center of its bottom face) that is ```width``` wide along x and z and ```height``` high
+    pub fn from_feet(feet: Vec3, width: f32, height: f32) -> Self {
+        let half = width / 2.0;
+        Aabb { min: feet - Vec3::new(half, 0.0, half), max: feet + Vec3::new(half, height, half) }
+    }
+
+    /// # Description:
+    /// The center of the bottom face
+    pub fn feet(&self) -> Vec3 {
+        Vec3::new((self.min.x + self.max.x) / 2.0, self.min.y, (self.min.z + self.max.z) / 2.0)
+    }
+
+    /// # Description:
+    /// The same box moved by ```offset```
+    pub fn translated(self, offset: Vec3) -> Self {
+        Aabb { min: self.min + offset, max: self.max + offset }
+    }
+
+    /// # Description:
+    /// The box stretched to cover every position it passes while moving by ```motion```
+    pub fn expanded_by(self, motion: Vec3) -> Self {
+        Aabb { min: self.min + motion.min(Vec3::ZERO), max: self.max + motion.max(Vec3::ZERO) }
+    }
+
+    /// # Description:
+    /// The box grown by ```margin``` on every side
+    pub fn inflated(self, margin: f32) -> Self {
+        Aabb { min: self.min - Vec3::splat(margin), max: self.max + Vec3::splat(margin) }
+    }
+
+    /// # Description:
+    /// Returns ```true``` if the boxes overlap by more than ```EPSILON```, boxes that only touch do not intersect
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        (0..3).all(|axis| self.overlaps_on(other, axis))
+    }
+
+    /// # Description:
+    /// How far the box can move by ```motion``` along ```axis``` before it runs into ```other```.
+    ///
+    /// Only colliders that overlap the box on the two other axes and lie ahead of it can stop it,
+    /// no matter how far away they are, so fast bodies cannot skip over thin colliders.
+    pub fn clip_motion(&self, other: &Aabb, axis: usize, motion: f32) -> f32 {
+        if !(0..3).filter(|other_axis| *other_axis != axis).all(|other_axis| self.overlaps_on(other, other_axis)) {
+            return motion;
+        }
+        if motion > 0.0 && self.max[axis] <= other.min[axis] + EPSILON {
+            motion.min(other.min[axis] - self.max[axis])
+        } else if motion < 0.0 && self.min[axis] >= other.max[axis] - EPSILON {
+            motion.max(other.max[axis] - self.min[axis])
+        } else {
+            motion
+        }
+    }
+
+    fn overlaps_on(&self, other: &Aabb, axis: usize) -> bool {
+        self.max[axis] - EPSILON > other.min[axis] && self.min[axis] + EPSILON < other.max[axis]
+    }
+}
+
+/// # Description:
+/// The collision boxes of every block that overlaps ```region```. Blocks without ```collision``` have none,
+/// the others collide with the boxes of their model (e.g. half a block for slabs or the rails of a fence, see ```BlockRegistry::connected_shape()```).
+///
+/// Unloaded chunks are empty, e.g. the space above the highest chunk.
+pub fn block_colliders(world: &VoxelWorld, registry: &BlockRegistry, region: Aabb) -> Vec<Aabb> {
+    let min = region.min.floor().as_ivec3();
+    let max = region.max.ceil().as_ivec3() - IVec3::ONE;
+    let mut colliders = Vec::new();
+    for x in min.x..=max.x {
+        for y in min.y..=max.y {
+            for z in min.z..=max.z {
+                let block_pos = BlockPos::new(x, y, z);
+                let Ok(block) = world.get_block(block_pos) else { continue };
+                if !registry.definition(block.block_type()).collision {
+                    continue;
+                }
+                for model_box in registry.connected_shape(block, |facing| world.get_block(block_pos.neighbour(facing)).ok()).boxes() {
+                    let corner = |corner: [u8; 3]| block_pos.to_world() + Vec3::from_array(corner.map(f32::from)) / MODEL_RESOLUTION as f32;
+                    colliders.push(Aabb::new(corner(model_box.from), corner(model_box.to)));
+                }
+            }
+        }
+    }
+    colliders
+}
+
+/// # Description:
+/// How a body moves, see ```move_body()```
+/// # Structure:
+/// ```
+/// pub struct MoveOptions {
+///     pub step_height: f32,
+///     pub sneaking: bool,
+/// }
+/// ```
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct MoveOptions {
+    pub step_height: f32,
+    pub sneaking: bool,
+}
+
+/// # Description:
+/// Where a body ended up after ```move_body()```
+/// # Structure:
+/// ```
+/// pub struct Movement {
+///     pub body: Aabb,
+///     pub motion: Vec3,
+///     pub collided: BVec3,
+///     pub on_ground: bool,
+/// }
+/// ```
+/// ```motion``` is how far the body really moved, ```collided``` tells which axes were stopped by a collider
+/// and ```on_ground``` is ```true``` if the body moved down onto a collider.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Movement {
+    pub body: Aabb,
+    pub motion: Vec3,
+    pub collided: BVec3,
+    pub on_ground: bool,
+}
+
+/// # Description:
+/// Moves ```body``` by ```motion``` through the ```colliders```, which have to cover ```body.expanded_by(motion)``` grown by the step height.
+///
+/// The motion is applied one axis after the other (y, x, then z), each clipped by ```Aabb::clip_motion()```,
+/// so a body that runs into a wall at an angle keeps sliding along it and no speed lets it pass through a collider.
+/// A body that starts inside of a collider is not pushed out, it can only move away from it.
+///
+/// A body that was ```on_ground``` before the move (or lands during it) climbs obstacles up to ```options.step_height``` that stop it horizontally.
+/// A sneaking body on the ground does not move further over a ledge than it could step down, see ```clamp_to_ledge()```.
+pub fn move_body(body: Aabb, motion: Vec3, colliders: &[Aabb], on_ground: bool, options: MoveOptions) -> Movement {
+    let motion = if options.sneaking && on_ground { clamp_to_ledge(body, motion, colliders, options.step_height) } else { motion };
+    let (mut moved_body, mut moved) = sweep(body, motion, colliders);
+
+    let landed = motion.y < 0.0 && moved.y > motion.y;
+    let blocked = moved.x != motion.x || moved.z != motion.z;
+    if options.step_height > 0.0 && blocked && (on_ground || landed) {
+        // climb up, move across and settle down onto the step again, keep it if it gets further
+        let (raised, rise) = sweep(body, Vec3::Y * options.step_height, colliders);
+        let (across, horizontal) = sweep(raised, Vec3::new(motion.x, 0.0, motion.z), colliders);
+        let (stepped, drop) = sweep(across, Vec3::Y * -(rise.y - motion.y.min(0.0)), colliders);
+        let distance = |motion: Vec3| motion.x * motion.x + motion.z * motion.z;
+        if distance(horizontal) > distance(moved) + EPSILON {
+            moved_body = stepped;
+            moved = Vec3::new(horizontal.x, rise.y + drop.y, horizontal.z);
+        }
+    }
+
+    let collided = BVec3::new(moved.x != motion.x, moved.y != motion.y, moved.z != motion.z);
+    Movement { body: moved_body, motion: moved, collided, on_ground: motion.y < 0.0 && collided.y }
+}
+
+/// # Description:
+/// Moves ```body``` by ```motion``` one axis after the other (y, x, then z) and stops every axis at the first collider ahead, without stepping.
+/// Returns the moved body and how far it moved.
+pub fn sweep(body: Aabb, motion: Vec3, colliders: &[Aabb]) -> (Aabb, Vec3) {
+    let mut body = body;
+    let mut moved = Vec3::ZERO;
+    for axis in [1, 0, 2] {
+        if motion[axis] == 0.0 {
+            continue;
+        }
+        let clipped = colliders.iter().fold(motion[axis], |motion, collider| body.clip_motion(collider, axis, motion));
+        let mut offset = Vec3::ZERO;
+        offset[axis] = clipped;
+        body = body.translated(offset);
+        moved[axis] = clipped;
+    }
+    (body, moved)
+}
+
+/// # Description:
+/// Returns ```true``` if a collider lies within ```depth``` below the body
+pub fn is_supported(body: Aabb, colliders: &[Aabb], depth: f32) -> bool {
+    let below = Aabb { min: body.min - Vec3::Y * depth, max: Vec3::new(body.max.x, body.min.y, body.max.z) };
+    colliders.iter().any(|collider| below.intersects(collider))
+}
+
+/// # Description:
+/// Shortens the horizontal ```motion``` of a body standing on the ground until it would still stand on something at most ```step_height``` below it.
+/// Each axis is shortened on its own first, so a sneaking body can still walk along the edge.
+pub fn clamp_to_ledge(body: Aabb, motion: Vec3, colliders: &[Aabb], step_height: f32) -> Vec3 {
+    let depth = step_height.max(EPSILON * 2.0);
+    let supported = |x: f32, z: f32| is_supported(body.translated(Vec3::new(x, 0.0, z)), colliders, depth);
+    let shorten = |value: f32| if value.abs() <= SNEAK_STEP { 0.0 } else { value - SNEAK_STEP * value.signum() };
+
+    let (mut x, mut z) = (motion.x, motion.z);
+    while x != 0.0 && !supported(x, 0.0) {
+        x = shorten(x);
+    }
+    while z != 0.0 && !supported(0.0, z) {
+        z = shorten(z);
+    }
+    while x != 0.0 && z != 0.0 && !supported(x, z) {
+        x = shorten(x);
+        z = shorten(z);
+    }
+    Vec3::new(x, motion.y, z)
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+pub(super) mod tests {
+    use proptest::prelude::*;
+    use crate::block_registry::{test_block, test_registry};
+    use crate::blocks::{Block, Facing};
+    use crate::chunk_logic::world::test_floor_world;
+    use super::*;
+
+    const WIDTH: f32 = 0.6;
+    const HEIGHT: f32 = 1.8;
+    const WALK: MoveOptions = MoveOptions { step_height: 0.6, sneaking: false };
+    const SNEAK: MoveOptions = MoveOptions { step_height: 0.6, sneaking: true };
+
+    /// asserts that two positions or motions are equal up to the rounding of a few float operations, the tests of the player use it as well
+    pub(crate) fn assert_close(expected: Vec3, actual: Vec3) {
+        assert!((expected - actual).abs().max_element() < 1e-4, "expected {expected}, got {actual}");
+    }
+
+    fn unit(x: i32, y: i32, z: i32) -> Aabb {
+        let min = Vec3::new(x as f32, y as f32, z as f32);
+        Aabb::new(min, min + Vec3::ONE)
+    }
+
+    /// a floor of full blocks at y = 0 from ```-size``` to ```size``` along x and z
+    fn floor(size: i32) -> Vec<Aabb> {
+        (-size..=size).flat_map(|x| (-size..=size).map(move |z| unit(x, 0, z))).collect()
+    }
+
+    fn body(x: f32, y: f32, z: f32) -> Aabb {
+        Aabb::from_feet(Vec3::new(x, y, z), WIDTH, HEIGHT)
+    }
+
+    #[test]
+    fn test_aabb() {
+        let a = body(0.5, 1.0, 0.5);
+        assert_close(Vec3::new(0.2, 1.0, 0.2), a.min);
+        assert_close(Vec3::new(0.8, 2.8, 0.8), a.max);
+        assert_close(Vec3::new(0.5, 1.0, 0.5), a.feet());
+        // touching is not intersecting
+        assert!(!a.intersects(&unit(0, 0, 0)));
+        assert!(a.intersects(&unit(0, 1, 0)));
+        assert!(!a.intersects(&unit(1, 1, 0)));
+        let swept = a.expanded_by(Vec3::new(-2.0, 0.5, 0.0));
+        assert_close(Vec3::new(-1.8, 1.0, 0.2), swept.min);
+        assert_close(Vec3::new(0.8, 3.3, 0.8), swept.max);
+        assert_eq!(a, Aabb::new(a.max, a.min));
+    }
+
+    #[test]
+    fn test_landing() {
+        let colliders = floor(2);
+        let movement = move_body(body(0.5, 1.5, 0.5), Vec3::new(0.0, -0.8, 0.0), &colliders, false, WALK);
+        assert_close(Vec3::new(0.5, 1.0, 0.5), movement.body.feet());
+        assert_close(Vec3::new(0.0, -0.5, 0.0), movement.motion);
+        assert!(movement.on_ground);
+        assert_eq!(BVec3::new(false, true, false), movement.collided);
+
+        // falling short of the floor
+        let movement = move_body(body(0.5, 3.0, 0.5), Vec3::new(0.0, -0.8, 0.0), &colliders, false, WALK);
+        assert!(!movement.on_ground);
+        assert_eq!(BVec3::FALSE, movement.collided);
+
+        // standing still on the floor
+        let movement = move_body(body(0.5, 1.0, 0.5), Vec3::new(0.0, -0.01, 0.0), &colliders, true, WALK);
+        assert_close(Vec3::ZERO, movement.motion);
+        assert!(movement.on_ground);
+    }
+
+    #[test]
+    fn test_ceiling() {
+        let colliders = vec![unit(0, 0, 0), unit(0, 3, 0)];
+        let movement = move_body(body(0.5, 1.0, 0.5), Vec3::new(0.0, 1.0, 0.0), &colliders, true, WALK);
+        assert_close(Vec3::new(0.5, 1.2, 0.5), movement.body.feet());
+        assert!(movement.collided.y);
+        assert!(!movement.on_ground);
+    }
+
+    #[test]
+    fn test_high_speed_does_not_tunnel() {
+        // a thin floor, much thinner than one step of the fall
+        let colliders = vec![Aabb::new(Vec3::new(-5.0, 0.9, -5.0), Vec3::new(5.0, 1.0, 5.0))];
+        let movement = move_body(body(0.0, 40.0, 0.0), Vec3::new(0.0, -500.0, 0.0), &colliders, false, WALK);
+        assert_close(Vec3::new(0.0, 1.0, 0.0), movement.body.feet());
+        assert!(movement.on_ground);
+
+        // and a thin wall
+        let colliders = vec![Aabb::new(Vec3::new(3.0, 0.0, -5.0), Vec3::new(3.05, 5.0, 5.0))];
+        let movement = move_body(body(0.0, 1.0, 0.0), Vec3::new(300.0, 0.0, 0.0), &colliders, false, MoveOptions::default());
+        assert_close(Vec3::new(3.0 - WIDTH / 2.0, 1.0, 0.0), movement.body.feet());
+        assert!(movement.collided.x);
+    }
+
+    #[test]
+    fn test_slide_along_walls() {
+        let mut colliders = floor(3);
+        // a wall along z at x = 2
+        colliders.extend((-3..=3).map(|z| unit(2, 1, z)));
+        let movement = move_body(body(0.5, 1.0, 0.5), Vec3::new(2.0, 0.0, 1.5), &colliders, true, WALK);
+        assert_close(Vec3::new(2.0 - WIDTH / 2.0, 1.0, 2.0), movement.body.feet());
+        assert_eq!(BVec3::new(true, false, false), movement.collided);
+
+        // an inner corner stops both axes
+        colliders.extend((-3..=1).map(|x| unit(x, 1, 2)));
+        let movement = move_body(body(0.5, 1.0, 0.5), Vec3::new(2.0, 0.0, 2.0), &colliders, true, WALK);
+        assert_close(Vec3::new(2.0 - WIDTH / 2.0, 1.0, 2.0 - WIDTH / 2.0), movement.body.feet());
+        assert_eq!(BVec3::new(true, false, true), movement.collided);
+    }
+
+    #[test]
+    fn test_corners() {
+        // a single pillar, the body passes its outer corner diagonally
+        let pillar = vec![unit(1, 0, 1)];
+        let start = body(0.5, 0.0, 0.5);
+        let movement = move_body(start, Vec3::new(1.0, 0.0, 1.0), &pillar, false, MoveOptions::default());
+        // x moves first and runs into nothing, then z is stopped by the side of the pillar
+        assert_close(Vec3::new(1.0, 0.0, 0.2), movement.motion);
+        assert_eq!(BVec3::new(false, false, true), movement.collided);
+        assert!(!movement.body.intersects(&pillar[0]));
+
+        // a body whose edge lines up with the corner exactly slides past it
+        let start = Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.8, 1.0));
+        let movement = move_body(start, Vec3::new(0.0, 0.0, 3.0), &pillar, false, MoveOptions::default());
+        assert_close(Vec3::new(0.0, 0.0, 3.0), movement.motion);
+        let movement = move_body(start, Vec3::new(0.5, 0.0, 3.0), &pillar, false, MoveOptions::default());
+        assert_close(Vec3::new(0.5, 0.0, 0.0), movement.motion);
+        assert!(movement.collided.z);
+    }
+
+    #[test]
+    fn test_step_up() {
+        let mut colliders = floor(3);
+        colliders.push(Aabb::new(Vec3::new(1.0, 1.0, -3.0), Vec3::new(2.0, 1.5, 3.0)));
+        colliders.push(unit(-1, 1, 0));
+
+        // onto a slab
+        let movement = move_body(body(0.5, 1.0, 0.5), Vec3::new(0.8, -0.1, 0.0), &colliders, true, WALK);
+        assert_close(Vec3::new(1.3, 1.5, 0.5), movement.body.feet());
+        assert!(movement.on_ground);
+        assert!(!movement.collided.x);
+
+        // not onto a full block
+        let movement = move_body(body(0.5, 1.0, 0.5), Vec3::new(-0.8, -0.1, 0.0), &colliders, true, WALK);
+        assert_close(Vec3::new(WIDTH / 2.0, 1.0, 0.5), movement.body.feet());
+        assert!(movement.collided.x);
+
+        // not while flying past it
+        let movement = move_body(body(0.5, 1.2, 0.5), Vec3::new(0.8, 0.1, 0.0), &colliders, false, WALK);
+        assert_close(Vec3::new(1.0 - WIDTH / 2.0, 1.3, 0.5), movement.body.feet());
+
+        // and not without a step height
+        let movement = move_body(body(0.5, 1.0, 0.5), Vec3::new(0.8, -0.1, 0.0), &colliders, true, MoveOptions::default());
+        assert_close(Vec3::new(1.0 - WIDTH / 2.0, 1.0, 0.5), movement.body.feet());
+    }
+
+    #[test]
+    fn test_step_up_below_a_ceiling() {
+        let colliders = vec![unit(0, 0, 0), unit(1, 0, 0), Aabb::new(Vec3::new(1.0, 1.0, 0.0), Vec3::new(2.0, 1.5, 1.0)), unit(1, 3, 0), unit(0, 3, 0)];
+        // the body fits under the ceiling, but not on top of the slab below it
+        let movement = move_body(body(0.5, 1.0, 0.5), Vec3::new(0.5, 0.0, 0.0), &colliders, true, WALK);
+        assert_close(Vec3::new(1.0 - WIDTH / 2.0, 1.0, 0.5), movement.body.feet());
+    }
+
+    #[test]
+    fn test_sneaking_at_ledges() {
+        // the floor ends at x = 3 (the last block spans 2..3)
+        let colliders: Vec<Aabb> = (-3..=2).flat_map(|x| (-3..=3).map(move |z| unit(x, 0, z))).collect();
+        let start = body(2.5, 1.0, 0.5);
+        let movement = move_body(start, Vec3::new(1.0, -0.1, 0.4), &colliders, true, SNEAK);
+        // the body stays over the edge with part of its bottom, the z motion is not affected
+        assert!(movement.body.min.x < 3.0);
+        assert!(movement.body.min.x > 3.0 - SNEAK_STEP - EPSILON);
+        assert_close(Vec3::new(0.0, 0.0, 0.4), movement.motion * Vec3::new(0.0, 1.0, 1.0));
+        assert!(is_supported(movement.body, &colliders, 0.1));
+
+        // without sneaking, it walks off (and starts falling with the next move)
+        let movement = move_body(start, Vec3::new(1.0, -0.1, 0.4), &colliders, true, WALK);
+        assert_close(Vec3::new(3.5, 1.0, 0.9), movement.body.feet());
+        assert!(!is_supported(movement.body, &colliders, 0.6));
+
+        // sneaking down a step that is low enough is fine
+        let mut colliders = colliders;
+        colliders.extend((-3..=3).map(|z| Aabb::new(Vec3::new(3.0, 0.0, z as f32), Vec3::new(4.0, 0.5, z as f32 + 1.0))));
+        let movement = move_body(start, Vec3::new(1.0, -0.1, 0.0), &colliders, true, SNEAK);
+        assert_close(Vec3::new(3.5, 1.0, 0.5), movement.body.feet());
+        // and the fall onto the step happens over the following moves
+        let movement = move_body(movement.body, Vec3::new(0.0, -0.6, 0.0), &colliders, false, SNEAK);
+        assert_close(Vec3::new(3.5, 0.5, 0.5), movement.body.feet());
+    }
+
+    #[test]
+    fn test_block_colliders() {
+        let registry = test_registry();
+        let mut world = test_floor_world([0]);
+        world.set_block(BlockPos::new(3, 2, 3), test_block("stone_slab")).unwrap();
+        world.set_block(BlockPos::new(4, 2, 3), test_block("flower")).unwrap();
+        world.set_block(BlockPos::new(5, 2, 3), Block::new(test_block("planks_stairs").block_type(), Facing::ZNegative)).unwrap();
+
+        let around = |x: f32| block_colliders(&world, registry, Aabb::new(Vec3::new(x, 2.0, 3.0), Vec3::new(x + 1.0, 3.0, 4.0)));
+        assert_eq!(vec![Aabb::new(Vec3::new(3.0, 2.0, 3.0), Vec3::new(4.0, 2.5, 4.0))], around(3.0));
+        assert_eq!(Vec::<Aabb>::new(), around(4.0));
+        // the high step of the stairs turns with them
+        assert_eq!(vec![Aabb::new(Vec3::new(5.0, 2.0, 3.0), Vec3::new(6.0, 2.5, 4.0)), Aabb::new(Vec3::new(5.0, 2.5, 3.0), Vec3::new(6.0, 3.0, 3.5))], around(5.0));
+
+        // regions reach into every block they overlap, unloaded chunks have no colliders
+        let colliders = block_colliders(&world, registry, Aabb::new(Vec3::new(-0.5, 1.5, 0.2), Vec3::new(0.5, 2.0, 0.8)));
+        assert_eq!(vec![unit(0, 1, 0)], colliders);
+        let colliders = block_colliders(&world, registry, Aabb::new(Vec3::new(-3.0, 0.0, 0.0), Vec3::new(2.0, 1.0, 1.0)));
+        assert_eq!(vec![unit(0, 0, 0), unit(1, 0, 0)], colliders);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_bodies_never_end_up_inside_of_colliders(
+            blocks in prop::collection::vec((-3..3, -3..3, -3..3), 0..30),
+            start in (-2.5f32..2.5, -2.5f32..2.5, -2.5f32..2.5),
+            motion in (-20.0f32..20.0, -20.0f32..20.0, -20.0f32..20.0),
+            on_ground in any::<bool>(),
+            sneaking in any::<bool>(),
+        ) {
+            // blocks that the body starts inside of are left out, it never gets into them by moving
+            let start = body(start.0, start.1, start.2);
+            let colliders: Vec<Aabb> = blocks.into_iter().map(|(x, y, z)| unit(x, y, z)).filter(|collider| !start.intersects(collider)).collect();
+
+            let motion = Vec3::new(motion.0, motion.1, motion.2);
+            let movement = move_body(start, motion, &colliders, on_ground, MoveOptions { step_height: 0.6, sneaking });
+            for collider in &colliders {
+                prop_assert!(!movement.body.intersects(collider), "{:?} ends inside of {:?}", movement.body, collider);
+            }
+            // the body never moves further than it was asked to along x and z
+            prop_assert!(movement.motion.x.abs() <= motion.x.abs() + EPSILON);
+            prop_assert!(movement.motion.z.abs() <= motion.z.abs() + EPSILON);
+            prop_assert!((movement.body.feet() - start.feet() - movement.motion).abs().max_element() < 1e-3);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+// foreign imports
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+/// How long breaking a block takes in survival, in seconds per point of ```BlockDefinition::hardness```
+pub const BREAK_TIME_PER_HARDNESS: f32 = 1.5;
+
+/// # Description:
+/// What a player can do in the world, every player has its own (see ```Player::game_mode```)
+/// # Format:
+/// ```
+/// pub enum GameMode {
+///     Survival,
+///     Creative,
+///     Spectator
+/// }
+/// ```
+/// Survival walks and collides with the blocks, breaks them over time by their hardness and only places the blocks it has collected.
+/// Creative can fly (jumping twice toggles it), breaks blocks instantly and places every block type.
+/// Spectator flies through the blocks and neither breaks nor places any.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum GameMode {
+    #[default]
+    Survival,
+    Creative,
+    Spectator
+}
+
+impl GameMode {
+
+    /// # Description:
+    /// Every game mode, in the order ```next()``` cycles through them
+    pub const ALL: [GameMode; 3] = [GameMode::Survival, GameMode::Creative, GameMode::Spectator];
+
+    /// # Description:
+    /// The game mode after this one in ```ALL```, wrapping around at the end
+    pub fn next(self) -> Self {
+        let index = GameMode::ALL.iter().position(|mode| *mode == self).expect("every game mode is in ALL");
+        GameMode::ALL[(index + 1) % GameMode::ALL.len()]
+    }
+
+    /// # Description:
+    /// The name shown in the UI
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Survival => "Survival",
+            GameMode::Creative => "Creative",
+            GameMode::Spectator => "Spectator",
+        }
+    }
+
+    /// # Description:
+    /// Whether the body of the player collides with the blocks, spectators move through them
+    pub fn collides(self) -> bool {
+        self != GameMode::Spectator
+    }
+
+    /// # Description:
+    /// Whether the player may fly, spectators always do
+    pub fn can_fly(self) -> bool {
+        self != GameMode::Survival
+    }
+
+    /// # Description:
+    /// Whether the player targets, breaks and places blocks
+    pub fn edits_blocks(self) -> bool {
+        self != GameMode::Spectator
+    }
+
+    /// # Description:
+    /// Whether a click breaks the targeted block at once, otherwise the button has to be held for the ```break_time()```
+    pub fn breaks_instantly(self) -> bool {
+        self == GameMode::Creative
+    }
+
+    /// # Description:
+    /// Whether placing takes the blocks from the ```Inventory```, otherwise every block type can be placed as often as wanted
+    pub fn uses_inventory(self) -> bool {
+        self == GameMode::Survival
+    }
+
+    /// # Description:
+    /// How many seconds breaking a block with ```hardness``` takes, ```None``` if the player cannot break blocks at all.
+    /// A hardness of 0 (e.g. flowers and redstone wire) breaks instantly in survival as well.
+    pub fn break_time(self, hardness: f32) -> Option<f32> {
+        match self {
+            GameMode::Survival => Some(hardness * BREAK_TIME_PER_HARDNESS),
+            GameMode::Creative => Some(0.0),
+            GameMode::Spectator => None,
+        }
+    }
+}
+
+impl fmt::Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_game_mode_rules() {
+        assert_eq!(GameMode::Creative, GameMode::Survival.next());
+        assert_eq!(GameMode::Survival, GameMode::Spectator.next());
+
+        assert_eq!(Some(3.0), GameMode::Survival.break_time(2.0));
+        assert_eq!(Some(0.0), GameMode::Survival.break_time(0.0));
+        assert_eq!(Some(0.0), GameMode::Creative.break_time(50.0));
+        assert_eq!(None, GameMode::Spectator.break_time(0.0));
+
+        assert!(!GameMode::Survival.can_fly() && GameMode::Survival.collides());
+        assert!(GameMode::Creative.can_fly() && GameMode::Creative.collides() && !GameMode::Creative.uses_inventory() && GameMode::Creative.breaks_instantly());
+        assert!(GameMode::Spectator.can_fly() && !GameMode::Spectator.collides() && !GameMode::Spectator.edits_blocks());
+    }
+}
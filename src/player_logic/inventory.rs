@@ -0,0 +1,173 @@
+// foreign imports
+use bevy::prelude::Component;
+
+// 'self' imports
+use crate::blocks::{Block, BlockId, Facing};
+
+/// How many stacks of blocks the inventory holds, one for each number key
+pub const INVENTORY_SLOTS: usize = 9;
+/// How many blocks of one type fit into a single slot
+pub const MAX_STACK_SIZE: u32 = 64;
+
+/// # Description:
+/// A number of blocks of one type in a slot of the ```Inventory```
+/// # Structure:
+/// ```
+/// pub struct ItemStack {
+///     pub block_type: BlockId,
+///     pub count: u32,
+/// }
+/// ```
+/// ```count``` is between 1 and ```MAX_STACK_SIZE```, empty slots hold no ```ItemStack``` at all.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ItemStack {
+    pub block_type: BlockId,
+    pub count: u32,
+}
+
+impl ItemStack {
+
+    /// # Description:
+    /// The block a stack places, in the default state of its type, ```block_interaction::place_block()``` turns it
+    pub fn block(&self) -> Block {
+        Block::new(self.block_type, Facing::XPositive)
+    }
+}
+
+/// # Description:
+/// The blocks a player in survival has collected. Broken blocks are added to it and placing a block takes one from the selected slot.
+/// # Structure:
+/// ```
+/// pub struct Inventory {
+///     slots: [Option<ItemStack>; INVENTORY_SLOTS],
+///     selected: usize,
+/// }
+/// ```
+#[derive(Component, Clone, PartialEq, Debug, Default)]
+pub struct Inventory {
+    slots: [Option<ItemStack>; INVENTORY_SLOTS],
+    selected: usize,
+}
+
+impl Inventory {
+
+    /// # Description:
+    /// An empty inventory with the first slot selected
+    pub fn new() -> Self {
+        Inventory::default()
+    }
+
+    /// # Description:
+    /// Every slot in order, ```None``` for the empty ones
+    pub fn slots(&self) -> &[Option<ItemStack>; INVENTORY_SLOTS] {
+        &self.slots
+    }
+
+    /// # Description:
+    /// Puts ```stack``` into the slot at ```index```, replacing what was there. Stacks without blocks empty the slot and counts above ```MAX_STACK_SIZE``` are cut down to it.
+    /// Indices past the end are ignored.
+    pub fn set_slot(&mut self, index: usize, stack: Option<ItemStack>) {
+        if let Some(slot) = self.slots.get_mut(index) {
+            *slot = stack.filter(|stack| stack.count > 0).map(|stack| ItemStack { count: stack.count.min(MAX_STACK_SIZE), ..stack });
+        }
+    }
+
+    /// # Description:
+    /// The index of the selected slot
+    pub fn selected_index(&self) -> usize {
+        self.selected
+    }
+
+    /// # Description:
+    /// The stack in the selected slot, ```None``` if it is empty
+    pub fn selected(&self) -> Option<ItemStack> {
+        self.slots[self.selected]
+    }
+
+    /// # Description:
+    /// Selects the slot at ```index```, indices past the end are ignored
+    pub fn select(&mut self, index: usize) {
+        if index < INVENTORY_SLOTS {
+            self.selected = index;
+        }
+    }
+
+    /// # Description:
+    /// Moves the selection by ```steps``` slots, wrapping around at both ends
+    pub fn scroll(&mut self, steps: i32) {
+        self.selected = (self.selected as i32 + steps).rem_euclid(INVENTORY_SLOTS as i32) as usize;
+    }
+
+    /// # Description:
+    /// Adds one block of ```block_type```, to a stack of the same type that is not full yet or else to the first empty slot.
+    /// Returns ```false``` if there is no room for it, the block is lost then.
+    pub fn add(&mut self, block_type: BlockId) -> bool {
+        let existing = self.slots.iter().position(|slot| slot.is_some_and(|stack| stack.block_type == block_type && stack.count < MAX_STACK_SIZE));
+        let index = match existing.or_else(|| self.slots.iter().position(Option::is_none)) {
+            Some(index) => index,
+            None => return false,
+        };
+        let count = self.slots[index].map_or(0, |stack| stack.count);
+        self.slots[index] = Some(ItemStack { block_type, count: count + 1 });
+        true
+    }
+
+    /// # Description:
+    /// Takes one block out of the selected slot, the slot is emptied with its last block. Returns ```None``` if the slot is empty.
+    pub fn take_selected(&mut self) -> Option<Block> {
+        let slot = &mut self.slots[self.selected];
+        let stack = (*slot)?;
+        *slot = (stack.count > 1).then_some(ItemStack { count: stack.count - 1, ..stack });
+        Some(stack.block())
+    }
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use crate::block_registry::test_block;
+    use super::*;
+
+    #[test]
+    fn test_stacking() {
+        let stone = test_block("stone").block_type();
+        let dirt = test_block("dirt").block_type();
+        let mut inventory = Inventory::new();
+        for _ in 0..MAX_STACK_SIZE + 1 {
+            assert!(inventory.add(stone));
+        }
+        assert!(inventory.add(dirt));
+        assert_eq!(Some(ItemStack { block_type: stone, count: MAX_STACK_SIZE }), inventory.slots()[0]);
+        assert_eq!(Some(ItemStack { block_type: stone, count: 1 }), inventory.slots()[1]);
+        assert_eq!(Some(ItemStack { block_type: dirt, count: 1 }), inventory.slots()[2]);
+
+        // new blocks only go into empty slots once every stack of their type is full
+        for index in 3..INVENTORY_SLOTS {
+            inventory.set_slot(index, Some(ItemStack { block_type: dirt, count: MAX_STACK_SIZE }));
+        }
+        inventory.set_slot(2, Some(ItemStack { block_type: dirt, count: 200 }));
+        assert!(inventory.add(stone));
+        assert!(!inventory.add(dirt));
+        assert_eq!(Some(ItemStack { block_type: dirt, count: MAX_STACK_SIZE }), inventory.slots()[2]);
+    }
+
+    #[test]
+    fn test_take_selected() {
+        let mut inventory = Inventory::new();
+        inventory.add(test_block("glass").block_type());
+        inventory.add(test_block("glass").block_type());
+
+        assert_eq!(Some(test_block("glass")), inventory.take_selected());
+        assert_eq!(1, inventory.selected().unwrap().count);
+        assert_eq!(Some(test_block("glass")), inventory.take_selected());
+        assert_eq!(None, inventory.selected());
+        assert_eq!(None, inventory.take_selected());
+
+        inventory.scroll(-1);
+        assert_eq!(INVENTORY_SLOTS - 1, inventory.selected_index());
+        inventory.select(INVENTORY_SLOTS);
+        assert_eq!(INVENTORY_SLOTS - 1, inventory.selected_index());
+    }
+}
@@ -0,0 +1,6 @@
+pub mod block_interaction;
+pub mod collision;
+pub mod game_mode;
+pub mod inventory;
+pub mod player;
+pub mod player_save;
@@ -0,0 +1,483 @@
+// foreign imports
+use bevy::input::mouse::MouseMotion;
+use bevy::prelude::*;
+use bevy::window::{CursorGrabMode, PrimaryWindow};
+
+// 'self' imports
+use crate::block_registry::BlockRegistry;
+use crate::chunk_logic::world::VoxelWorld;
+use crate::player_logic::collision::{block_colliders, move_body, Aabb, MoveOptions};
+use crate::player_logic::game_mode::GameMode;
+use crate::player_logic::inventory::Inventory;
+use crate::player_logic::player_save::{PlayerSave, PLAYER_SAVE_FILE};
+
+/// The size of the body of the player
+pub const PLAYER_WIDTH: f32 = 0.6;
+pub const PLAYER_HEIGHT: f32 = 1.8;
+/// How high above the feet the camera is, and how much lower it is while sneaking
+pub const EYE_HEIGHT: f32 = 1.62;
+pub const SNEAK_EYE_DROP: f32 = 0.3;
+
+/// The movement of the player in blocks per second (and per second squared for the gravity)
+pub const WALK_SPEED: f32 = 4.3;
+pub const SNEAK_SPEED: f32 = 1.3;
+pub const JUMP_SPEED: f32 = 10.0;
+pub const GRAVITY: f32 = 32.0;
+pub const MAX_FALL_SPEED: f32 = 78.4;
+/// Flying in creative moves horizontally and vertically at different speeds, spectators fly along the view direction
+pub const FLY_SPEED: f32 = 10.9;
+pub const FLY_VERTICAL_SPEED: f32 = 7.5;
+pub const SPECTATOR_SPEED: f32 = 12.0;
+
+/// Two presses of jump within this many seconds toggle flying in creative
+pub const DOUBLE_TAP_TIME: f32 = 0.3;
+
+/// The highest obstacle the player walks onto without jumping, slabs and stairs can be walked up
+pub const STEP_HEIGHT: f32 = 0.6;
+
+/// Where the player starts, and where it comes back after falling below ```FALL_LIMIT```
+pub const SPAWN_POINT: Vec3 = Vec3::new(4.5, 6.0, 28.5);
+pub const FALL_LIMIT: f32 = -64.0;
+
+/// How far the camera turns per pixel of mouse movement, relative to the window size
+const MOUSE_SENSITIVITY: f32 = 0.00010;
+/// Just short of straight up or down, so the view never flips over
+const MAX_PITCH: f32 = 1.54;
+
+/// # Description:
+/// What the player wants to do in one physics step, independent of the keys it comes from
+/// # Structure:
+/// ```
+/// pub struct MoveInput {
+///     pub forward: f32,
+///     pub right: f32,
+///     pub jump: bool,
+///     pub sneak: bool,
+/// }
+/// ```
+/// ```forward``` and ```right``` are between -1 and 1, relative to the direction the player looks in.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct MoveInput {
+    pub forward: f32,
+    pub right: f32,
+    pub jump: bool,
+    pub sneak: bool,
+}
+
+/// # Description:
+/// The player. The entity that holds it is the camera, which follows the body and looks where the player looks.
+/// # Structure:
+/// ```
+/// pub struct Player {
+///     pub feet: Vec3,
+///     pub velocity: Vec3,
+///     pub on_ground: bool,
+///     pub sneaking: bool,
+///     pub flying: bool,
+///     pub yaw: f32,
+///     pub pitch: f32,
+///     pub game_mode: GameMode,
+///     previous_feet: Vec3,
+///     last_jump_press: Option<f32>,
+/// }
+/// ```
+/// ```feet``` is the center of the bottom of the body, ```previous_feet``` where it was one physics step earlier, so the camera can move smoothly in between.
+/// ```game_mode``` is changed with ```set_game_mode()```, which keeps ```flying``` valid for it.
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub struct Player {
+    pub feet: Vec3,
+    pub velocity: Vec3,
+    pub on_ground: bool,
+    pub sneaking: bool,
+    pub flying: bool,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub game_mode: GameMode,
+    previous_feet: Vec3,
+    last_jump_press: Option<f32>,
+}
+
+impl Player {
+
+    /// # Description:
+    /// A player in survival standing still at ```feet```, looking along -z
+    pub fn new(feet: Vec3) -> Self {
+        Player {
+            feet,
+            velocity: Vec3::ZERO,
+            on_ground: false,
+            sneaking: false,
+            flying: false,
+            yaw: 0.0,
+            pitch: 0.0,
+            game_mode: GameMode::Survival,
+            previous_feet: feet,
+            last_jump_press: None,
+        }
+    }
+
+    /// # Description:
+    /// Switches to ```game_mode```. Spectators always fly and survival never does, a player that switches to creative keeps flying if it was.
+    pub fn set_game_mode(&mut self, game_mode: GameMode) {
+        self.flying = match game_mode {
+            GameMode::Survival => false,
+            GameMode::Creative => self.flying,
+            GameMode::Spectator => true,
+        };
+        if self.flying {
+            self.velocity = Vec3::ZERO;
+            self.on_ground = false;
+        }
+        self.game_mode = game_mode;
+    }
+
+    /// # Description:
+    /// Tells the player that jump was pressed at ```time``` (in seconds), pressing it twice within ```DOUBLE_TAP_TIME``` toggles flying in creative
+    pub fn press_jump(&mut self, time: f32) {
+        if self.game_mode != GameMode::Creative {
+            return;
+        }
+        if self.last_jump_press.is_some_and(|last| time - last <= DOUBLE_TAP_TIME) {
+            self.flying = !self.flying;
+            self.velocity.y = 0.0;
+            self.last_jump_press = None;
+        } else {
+            self.last_jump_press = Some(time);
+        }
+    }
+
+    /// # Description:
+    /// The body of the player in world space
+    pub fn body(&self) -> Aabb {
+        Aabb::from_feet(self.feet, PLAYER_WIDTH, PLAYER_HEIGHT)
+    }
+
+    /// # Description:
+    /// How high above the feet the camera is right now
+    pub fn eye_height(&self) -> f32 {
+        if self.sneaking { EYE_HEIGHT - SNEAK_EYE_DROP } else { EYE_HEIGHT }
+    }
+
+    /// # Description:
+    /// The rotation of the camera, turned by ```yaw``` around y first, so the view never rolls
+    pub fn look_rotation(&self) -> Quat {
+        Quat::from_axis_angle(Vec3::Y, self.yaw) * Quat::from_axis_angle(Vec3::X, self.pitch)
+    }
+
+    /// # Description:
+    /// Moves the player back to ```feet``` and stops it
+    pub fn teleport(&mut self, feet: Vec3) {
+        *self = Player { yaw: self.yaw, pitch: self.pitch, game_mode: self.game_mode, flying: self.flying, ..Player::new(feet) };
+    }
+}
+
+/// # Description:
+/// Advances the player by ```dt``` seconds: walking in the direction of ```input```, jumping off the ground, gravity,
+/// and the collision with the blocks of the ```VoxelWorld``` (see ```collision::move_body()```).
+///
+/// A flying player has no gravity, jump moves it up and sneak down. Creative flight ends when the player lands on a block,
+/// spectators fly where they look and pass through the blocks.
+/// Headless, so tests can run it on any ```VoxelWorld```.
+pub fn physics_step(player: &mut Player, input: MoveInput, world: &VoxelWorld, registry: &BlockRegistry, dt: f32) {
+    player.previous_feet = player.feet;
+    player.sneaking = input.sneak && !player.flying;
+
+    let forward = Vec3::new(-player.yaw.sin(), 0.0, -player.yaw.cos());
+    let right = Vec3::new(player.yaw.cos(), 0.0, -player.yaw.sin());
+    let wish = (forward * input.forward + right * input.right).clamp_length_max(1.0);
+    let vertical = input.jump as i32 as f32 - input.sneak as i32 as f32;
+
+    if !player.game_mode.collides() {
+        let look = player.look_rotation() * Vec3::NEG_Z;
+        let wish = (look * input.forward + right * input.right).clamp_length_max(1.0);
+        player.velocity = wish * SPECTATOR_SPEED + Vec3::Y * vertical * FLY_VERTICAL_SPEED;
+        player.feet += player.velocity * dt;
+        player.on_ground = false;
+        return;
+    }
+
+    if player.flying {
+        player.velocity = Vec3::new(wish.x * FLY_SPEED, vertical * FLY_VERTICAL_SPEED, wish.z * FLY_SPEED);
+    } else {
+        let speed = if input.sneak { SNEAK_SPEED } else { WALK_SPEED };
+        player.velocity.x = wish.x * speed;
+        player.velocity.z = wish.z * speed;
+
+        if input.jump && player.on_ground {
+            player.velocity.y = JUMP_SPEED;
+        }
+        player.velocity.y = (player.velocity.y - GRAVITY * dt).max(-MAX_FALL_SPEED);
+    }
+
+    let motion = player.velocity * dt;
+    let body = player.body();
+    let colliders = block_colliders(world, registry, body.expanded_by(motion).inflated(STEP_HEIGHT));
+    let movement = move_body(body, motion, &colliders, player.on_ground, MoveOptions { step_height: STEP_HEIGHT, sneaking: player.sneaking });
+
+    player.feet = movement.body.feet();
+    player.on_ground = movement.on_ground;
+    if player.on_ground {
+        player.flying = false;
+    }
+    let stopped = Vec3::select(movement.collided, Vec3::ZERO, Vec3::ONE);
+    player.velocity *= stopped;
+}
+
+/// # Description:
+/// Spawns the camera of the player where it was saved in ```PLAYER_SAVE_FILE```, or a new player in survival at ```SPAWN_POINT```
+pub fn spawn_player(mut commands: Commands, registry: Res<BlockRegistry>) {
+    let (player, inventory) = match PlayerSave::load(PLAYER_SAVE_FILE) {
+        Ok(Some(save)) => save.restore(&registry),
+        Ok(None) => (Player::new(SPAWN_POINT), Inventory::new()),
+        Err(error) => {
+            warn!("{error}, starting with a new player");
+            (Player::new(SPAWN_POINT), Inventory::new())
+        }
+    };
+    commands.spawn((Camera3dBundle {
+        transform: Transform::from_translation(player.feet + Vec3::Y * player.eye_height()).with_rotation(player.look_rotation()),
+        ..default()
+    }, player, inventory, Name::new("Player")));
+}
+
+/// # Description:
+/// System that switches the player to the next ```GameMode``` with F4
+pub fn switch_game_mode(input: Res<Input<KeyCode>>, mut query: Query<&mut Player>) {
+    if !input.just_pressed(KeyCode::F4) {
+        return;
+    }
+    for mut player in &mut query {
+        let next = player.game_mode.next();
+        player.set_game_mode(next);
+    }
+}
+
+/// # Description:
+/// System that passes presses of Space to ```Player::press_jump()```, it runs every frame so no short tap is missed between two physics steps
+pub fn toggle_flight(time: Res<Time>, input: Res<Input<KeyCode>>, window: Query<&Window, With<PrimaryWindow>>, mut query: Query<&mut Player>) {
+    if !input.just_pressed(KeyCode::Space) || !window.get_single().is_ok_and(is_cursor_grabbed) {
+        return;
+    }
+    for mut player in &mut query {
+        player.press_jump(time.elapsed_seconds());
+    }
+}
+
+/// # Description:
+/// Hides and holds the cursor when the game starts, so the mouse turns the camera right away
+pub fn grab_cursor(mut window: Query<&mut Window, With<PrimaryWindow>>) {
+    if let Ok(mut window) = window.get_single_mut() {
+        set_cursor_grab(&mut window, true);
+    }
+}
+
+/// # Description:
+/// System that releases the cursor (e.g. to use the UI) and grabs it again with Escape
+pub fn toggle_cursor_grab(mut window: Query<&mut Window, With<PrimaryWindow>>, input: Res<Input<KeyCode>>) {
+    if !input.just_pressed(KeyCode::Escape) {
+        return;
+    }
+    if let Ok(mut window) = window.get_single_mut() {
+        let grabbed = is_cursor_grabbed(&window);
+        set_cursor_grab(&mut window, !grabbed);
+    }
+}
+
+/// # Description:
+/// Returns ```true``` while the game holds the cursor, only then the mouse and the keys control the player
+pub fn is_cursor_grabbed(window: &Window) -> bool {
+    window.cursor.grab_mode != CursorGrabMode::None
+}
+
+/// # Description:
+/// System that turns the player with the mouse while the cursor is grabbed
+pub fn player_look(window: Query<&Window, With<PrimaryWindow>>, mut motion: EventReader<MouseMotion>, mut query: Query<&mut Player>) {
+    let delta: Vec2 = motion.iter().map(|event| event.delta).sum();
+    let Ok(window) = window.get_single() else { return };
+    if delta == Vec2::ZERO || !is_cursor_grabbed(window) {
+        return;
+    }
+    // the smaller side of the window keeps the horizontal and vertical sensitivity the same
+    let scale = window.height().min(window.width());
+    for mut player in &mut query {
+        player.yaw -= (MOUSE_SENSITIVITY * delta.x * scale).to_radians();
+        player.pitch = (player.pitch - (MOUSE_SENSITIVITY * delta.y * scale).to_radians()).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+}
+
+/// # Description:
+/// System that runs one ```physics_step()``` of the player with the keys that are held down, it belongs into the fixed timestep schedule.
+/// W, A, S and D walk, Space jumps and left Shift sneaks, while flying Space rises and left Shift sinks.
+pub fn player_physics(fixed_time: Res<FixedTime>, input: Res<Input<KeyCode>>, window: Query<&Window, With<PrimaryWindow>>, voxel_world: Res<VoxelWorld>, registry: Res<BlockRegistry>, mut query: Query<&mut Player>) {
+    let controlled = window.get_single().is_ok_and(is_cursor_grabbed);
+    let axis = |positive: KeyCode, negative: KeyCode| input.pressed(positive) as i32 as f32 - input.pressed(negative) as i32 as f32;
+    let move_input = if controlled {
+        MoveInput { forward: axis(KeyCode::W, KeyCode::S), right: axis(KeyCode::D, KeyCode::A), jump: input.pressed(KeyCode::Space), sneak: input.pressed(KeyCode::LShift) }
+    } else {
+        MoveInput::default()
+    };
+    for mut player in &mut query {
+        physics_step(&mut player, move_input, &voxel_world, &registry, fixed_time.period.as_secs_f32());
+        if player.feet.y < FALL_LIMIT && player.game_mode.collides() {
+            player.teleport(SPAWN_POINT);
+        }
+    }
+}
+
+/// # Description:
+/// System that places the camera at the eyes of the player. The physics runs at a lower rate than the frames,
+/// so the camera moves between the last two physics steps by how far the next step is already due.
+pub fn update_player_camera(fixed_time: Res<FixedTime>, mut query: Query<(&Player, &mut Transform)>) {
+    let progress = (fixed_time.accumulated().as_secs_f32() / fixed_time.period.as_secs_f32()).clamp(0.0, 1.0);
+    for (player, mut transform) in &mut query {
+        transform.translation = player.previous_feet.lerp(player.feet, progress) + Vec3::Y * player.eye_height();
+        transform.rotation = player.look_rotation();
+    }
+}
+
+fn set_cursor_grab(window: &mut Window, grab: bool) {
+    window.cursor.grab_mode = if grab { CursorGrabMode::Confined } else { CursorGrabMode::None };
+    window.cursor.visible = !grab;
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use crate::block_registry::{test_block, test_registry};
+    use crate::chunk_logic::position_handling::BlockPos;
+    use crate::chunk_logic::world::test_floor_world;
+    use crate::player_logic::collision::tests::assert_close;
+    use super::*;
+
+    const DT: f32 = 0.05;
+
+    fn run(player: &mut Player, input: MoveInput, world: &VoxelWorld, steps: usize) {
+        for _ in 0..steps {
+            physics_step(player, input, world, test_registry(), DT);
+        }
+    }
+
+    #[test]
+    fn test_falls_onto_the_ground() {
+        let world = test_floor_world([0]);
+        let mut player = Player::new(Vec3::new(8.5, 4.0, 8.5));
+        run(&mut player, MoveInput::default(), &world, 20);
+        assert_close(Vec3::new(8.5, 2.0, 8.5), player.feet);
+        assert!(player.on_ground);
+        assert_eq!(Vec3::ZERO, player.velocity);
+    }
+
+    #[test]
+    fn test_jump() {
+        let world = test_floor_world([0]);
+        let mut player = Player::new(Vec3::new(8.5, 2.0, 8.5));
+        run(&mut player, MoveInput::default(), &world, 1);
+        let jump = MoveInput { jump: true, ..default() };
+        let mut highest: f32 = 0.0;
+        for _ in 0..20 {
+            run(&mut player, jump, &world, 1);
+            highest = highest.max(player.feet.y);
+        }
+        // a jump clears one block but not two, and the player lands again
+        assert!(highest > 3.0 && highest < 4.0, "the jump reached {highest}");
+        run(&mut player, MoveInput::default(), &world, 20);
+        assert!(player.on_ground);
+        assert_close(Vec3::new(8.5, 2.0, 8.5), player.feet);
+    }
+
+    #[test]
+    fn test_walking_against_walls_and_up_steps() {
+        let mut world = test_floor_world([0]);
+        // slabs, then a wall two blocks high
+        for z in 0..16 {
+            world.set_block(BlockPos::new(10, 2, z), test_block("stone_slab")).unwrap();
+            world.set_block(BlockPos::new(11, 2, z), test_block("stone_slab")).unwrap();
+            world.set_block(BlockPos::new(12, 2, z), test_block("stone")).unwrap();
+            world.set_block(BlockPos::new(12, 3, z), test_block("stone")).unwrap();
+        }
+        // looking along +x
+        let mut player = Player::new(Vec3::new(8.5, 2.0, 8.5));
+        player.yaw = -std::f32::consts::FRAC_PI_2;
+        run(&mut player, MoveInput { forward: 1.0, ..default() }, &world, 40);
+        assert_close(Vec3::new(12.0 - PLAYER_WIDTH / 2.0, 2.5, 8.5), player.feet);
+        assert!(player.on_ground);
+    }
+
+    #[test]
+    fn test_sneaking_stops_at_the_edge() {
+        let mut world = test_floor_world([0]);
+        world.set_block(BlockPos::new(8, 2, 8), test_block("stone")).unwrap();
+        let mut player = Player::new(Vec3::new(8.5, 3.0, 8.5));
+        run(&mut player, MoveInput::default(), &world, 1);
+        run(&mut player, MoveInput { right: 1.0, sneak: true, ..default() }, &world, 40);
+        assert!((player.feet.y - 3.0).abs() < 1e-3);
+        assert!(player.body().min.x < 9.0);
+        assert!(player.eye_height() < EYE_HEIGHT);
+
+        run(&mut player, MoveInput { right: 1.0, ..default() }, &world, 10);
+        assert!((player.feet.y - 2.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_creative_flight() {
+        let world = test_floor_world([0]);
+        let mut player = Player::new(Vec3::new(8.5, 2.0, 8.5));
+        run(&mut player, MoveInput::default(), &world, 1);
+
+        // survival cannot fly, and in creative the presses have to be close together
+        player.press_jump(1.0);
+        player.press_jump(1.1);
+        assert!(!player.flying);
+        player.set_game_mode(GameMode::Creative);
+        player.press_jump(2.0);
+        player.press_jump(2.0 + DOUBLE_TAP_TIME * 2.0);
+        assert!(!player.flying);
+        player.press_jump(2.1 + DOUBLE_TAP_TIME * 2.0);
+        assert!(player.flying);
+
+        // no gravity, jump rises and sneak sinks until the player lands
+        run(&mut player, MoveInput { jump: true, ..default() }, &world, 4);
+        assert_close(Vec3::new(8.5, 2.0 + 4.0 * DT * FLY_VERTICAL_SPEED, 8.5), player.feet);
+        run(&mut player, MoveInput::default(), &world, 10);
+        assert!(player.flying && !player.sneaking);
+        assert_close(Vec3::new(8.5, 2.0 + 4.0 * DT * FLY_VERTICAL_SPEED, 8.5), player.feet);
+        run(&mut player, MoveInput { sneak: true, ..default() }, &world, 10);
+        assert!(!player.flying && player.on_ground);
+        assert_close(Vec3::new(8.5, 2.0, 8.5), player.feet);
+
+        player.press_jump(5.0);
+        player.press_jump(5.1);
+        player.set_game_mode(GameMode::Survival);
+        assert!(!player.flying);
+    }
+
+    #[test]
+    fn test_spectators_fly_through_blocks() {
+        let mut world = test_floor_world([0]);
+        for z in 0..16 {
+            world.set_block(BlockPos::new(10, 2, z), test_block("stone")).unwrap();
+            world.set_block(BlockPos::new(10, 3, z), test_block("stone")).unwrap();
+        }
+        let mut player = Player::new(Vec3::new(8.5, 2.0, 8.5));
+        player.set_game_mode(GameMode::Spectator);
+        assert!(player.flying);
+        // looking along +x and down, spectators fly where they look, through the wall and into the floor
+        player.yaw = -std::f32::consts::FRAC_PI_2;
+        player.pitch = -std::f32::consts::FRAC_PI_4;
+        run(&mut player, MoveInput { forward: 1.0, ..default() }, &world, 10);
+        let distance = 10.0 * DT * SPECTATOR_SPEED * std::f32::consts::FRAC_1_SQRT_2;
+        assert_close(Vec3::new(8.5 + distance, 2.0 - distance, 8.5), player.feet);
+        assert!(player.flying && !player.on_ground);
+
+        // leaving spectator in the air falls down in survival, but keeps flying in creative
+        player.teleport(Vec3::new(4.5, 4.0, 4.5));
+        player.set_game_mode(GameMode::Creative);
+        run(&mut player, MoveInput::default(), &world, 10);
+        assert_close(Vec3::new(4.5, 4.0, 4.5), player.feet);
+        player.set_game_mode(GameMode::Survival);
+        run(&mut player, MoveInput::default(), &world, 20);
+        assert_close(Vec3::new(4.5, 2.0, 4.5), player.feet);
+    }
+}
@@ -0,0 +1,177 @@
+// foreign imports
+use std::path::Path;
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// 'self' imports
+use crate::block_registry::BlockRegistry;
+use crate::player_logic::game_mode::GameMode;
+use crate::player_logic::inventory::{Inventory, ItemStack};
+use crate::player_logic::player::Player;
+use crate::save::{load_ron, write_ron, SaveError, AUTOSAVE_INTERVAL};
+
+/// Where the player is saved, relative to the working directory like ```BLOCK_DIRECTORY```
+pub const PLAYER_SAVE_FILE: &str = "saves/player.ron";
+
+/// # Description:
+/// Everything about the player that is kept from one run of the game to the next
+/// # Format:
+/// ```
+/// (
+///     game_mode: Creative,
+///     feet: (4.5, 5.0, 28.5),
+///     yaw: 0.0,
+///     pitch: -0.3,
+///     flying: true,
+///     inventory: [Some(("stone", 12)), None, Some(("glass", 1))],
+/// )
+/// ```
+/// The inventory holds one entry per slot. Its blocks are saved by name, so the save survives new block types shifting the IDs,
+/// names that are not in the ```BlockRegistry``` anymore are dropped when the save is restored.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PlayerSave {
+    pub game_mode: GameMode,
+    pub feet: (f32, f32, f32),
+    pub yaw: f32,
+    pub pitch: f32,
+    #[serde(default)]
+    pub flying: bool,
+    #[serde(default)]
+    pub inventory: Vec<Option<(String, u32)>>,
+}
+
+impl PlayerSave {
+
+    /// # Description:
+    /// The save of ```player``` and its ```inventory```
+    pub fn new(player: &Player, inventory: &Inventory, registry: &BlockRegistry) -> Self {
+        PlayerSave {
+            game_mode: player.game_mode,
+            feet: player.feet.into(),
+            yaw: player.yaw,
+            pitch: player.pitch,
+            flying: player.flying,
+            inventory: inventory.slots().iter()
+                .map(|slot| slot.map(|stack| (registry.definition(stack.block_type).name.clone(), stack.count)))
+                .collect(),
+        }
+    }
+
+    /// # Description:
+    /// The player and inventory that were saved, standing still. See ```Inventory::set_slot()``` for how the saved stacks are cut down.
+    pub fn restore(&self, registry: &BlockRegistry) -> (Player, Inventory) {
+        let mut player = Player::new(self.feet.into());
+        player.yaw = self.yaw;
+        player.pitch = self.pitch;
+        player.flying = self.flying;
+        player.set_game_mode(self.game_mode);
+
+        let mut inventory = Inventory::new();
+        for (index, slot) in self.inventory.iter().enumerate() {
+            let stack = slot.as_ref().and_then(|(name, count)| Some(ItemStack { block_type: registry.id(name)?, count: *count }));
+            inventory.set_slot(index, stack);
+        }
+        (player, inventory)
+    }
+
+    /// # Description:
+    /// Reads the save at ```path```, ```Ok(None)``` if there is none yet
+    /// # Errors:
+    /// fails if the file exists but cannot be read or parsed
+    pub fn load(path: impl AsRef<Path>) -> Result<Option<Self>, SaveError> {
+        load_ron(path.as_ref())
+    }
+
+    /// # Description:
+    /// Writes the save to ```path```, see ```save::write_ron()```
+    /// # Errors:
+    /// fails if the directory or the file cannot be written
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), SaveError> {
+        write_ron(self, path.as_ref())
+    }
+}
+
+/// # Description:
+/// System that writes the player to ```PLAYER_SAVE_FILE``` every ```AUTOSAVE_INTERVAL```, when its game mode changes and when the game closes.
+/// It belongs at the end of the frame, after the window sent ```AppExit```.
+pub fn save_player(time: Res<Time>, mut exit: EventReader<AppExit>, mut since_save: Local<f32>, mut saved_game_mode: Local<Option<GameMode>>, registry: Res<BlockRegistry>, query: Query<(&Player, &Inventory)>) {
+    let exiting = exit.iter().count() > 0;
+    let Ok((player, inventory)) = query.get_single() else { return };
+    *since_save += time.delta_seconds();
+    let game_mode_changed = saved_game_mode.replace(player.game_mode).is_some_and(|game_mode| game_mode != player.game_mode);
+    if !exiting && !game_mode_changed && *since_save < AUTOSAVE_INTERVAL {
+        return;
+    }
+    *since_save = 0.0;
+    if let Err(error) = PlayerSave::new(player, inventory, &registry).write(PLAYER_SAVE_FILE) {
+        warn!("{error}");
+    }
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use crate::block_registry::{test_block, test_registry};
+    use super::*;
+
+    /// a directory of its own for every test, so they can run in parallel
+    fn test_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("voxel_rust_{name}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        directory
+    }
+
+    #[test]
+    fn test_save_round_trip() {
+        let registry = test_registry();
+        let mut player = Player::new(Vec3::new(4.5, 3.0, -20.25));
+        player.yaw = 1.25;
+        player.pitch = -0.5;
+        player.set_game_mode(GameMode::Spectator);
+        player.set_game_mode(GameMode::Creative);
+        let mut inventory = Inventory::new();
+        inventory.add(test_block("glass").block_type());
+        inventory.set_slot(4, Some(ItemStack { block_type: test_block("redstone_torch").block_type(), count: 30 }));
+
+        let directory = test_directory("save_round_trip");
+        let path = directory.join("players").join("player.ron");
+        assert_eq!(Ok(None), PlayerSave::load(&path));
+        PlayerSave::new(&player, &inventory, registry).write(&path).unwrap();
+
+        let save = PlayerSave::load(&path).unwrap().unwrap();
+        assert_eq!(GameMode::Creative, save.game_mode);
+        assert_eq!(Some(("redstone_torch".to_string(), 30)), save.inventory[4]);
+        let (restored, restored_inventory) = save.restore(registry);
+        assert_eq!(player, restored);
+        assert!(restored.flying);
+        assert_eq!(inventory, restored_inventory);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_restore_drops_what_it_does_not_know() {
+        let text = r#"(game_mode: Survival, feet: (1.0, 2.0, 3.0), yaw: 0.0, pitch: 0.0, flying: true, inventory: [Some(("stone", 500)), Some(("diamond", 3)), None, Some(("dirt", 0))])"#;
+        let save: PlayerSave = ron::from_str(text).unwrap();
+        let (player, inventory) = save.restore(test_registry());
+        // survival never flies
+        assert!(!player.flying);
+        assert_eq!(Vec3::new(1.0, 2.0, 3.0), player.feet);
+        assert_eq!(Some(ItemStack { block_type: test_block("stone").block_type(), count: 64 }), inventory.slots()[0]);
+        assert!(inventory.slots()[1..].iter().all(Option::is_none));
+
+        // flying and the inventory may be left out
+        let save: PlayerSave = ron::from_str("(game_mode: Spectator, feet: (0.0, 0.0, 0.0), yaw: 0.0, pitch: 0.0)").unwrap();
+        assert!(save.restore(test_registry()).0.flying);
+
+        let directory = test_directory("restore_drops");
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("player.ron");
+        std::fs::write(&path, "(game_mode: Hardcore)").unwrap();
+        assert!(matches!(PlayerSave::load(&path), Err(SaveError::Parse(..))));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
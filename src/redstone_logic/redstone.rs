@@ -0,0 +1,842 @@
+// foreign imports
+use std::collections::{BTreeSet, HashMap, HashSet};
+use bevy::prelude::{IVec3, Resource};
+use serde::Deserialize;
+
+// 'self' imports
+use crate::block_registry::BlockRegistry;
+use crate::block_state::PropertyValue;
+use crate::blocks::{Block, Facing, RedstonePowerLvl, RenderLayer};
+use crate::chunk_logic::position_handling::BlockPos;
+use crate::chunk_logic::world::{VoxelWorld, WorldError};
+use crate::light_logic::light;
+use crate::tick_logic::neighbour_updates::{BlockBehaviour, BlockBehaviours, BlockContext, NeedsSupport, Support};
+use crate::tick_logic::scheduler::{TickScheduler, DEFAULT_PRIORITY};
+
+/// The strongest redstone power level
+pub const MAX_POWER: u8 = 15;
+
+/// How many ticks a torch takes to react to its input
+pub const TORCH_DELAY: u8 = 1;
+
+/// How many ticks a comparator takes to react to its inputs
+pub const COMPARATOR_DELAY: u8 = 1;
+
+/// Repeaters run before the other components that are due in the same tick
+const REPEATER_PRIORITY: i8 = -1;
+
+/// # Description:
+/// How a block type takes part in the redstone simulation, as declared in its block definition file.
+///
+/// Blocks with a ```redstone_power_lvl``` are power sources on their own, no matter which component they are.
+/// Every other component needs the state properties listed by ```required_properties()```.
+/// # Format:
+/// ```
+/// pub enum RedstoneComponent {
+///     None,
+///     Wire,
+///     Torch,
+///     Repeater,
+///     Comparator,
+///     Lamp
+/// }
+/// ```
+/// - A ```Wire``` carries the power of the sources next to it, losing one level per block. Its current power is stored in the ```power_lvl``` of its ```Block```.
+/// - A ```Torch``` (```lit```) is attached to the block behind it (opposite of its ```Facing```) and is lit while that block is unpowered.
+///   A lit torch powers every side but the one it is attached to.
+/// - A ```Repeater``` (```delay```, ```powered```) passes the power at its back to its front (its ```Facing```) as full power, ```delay``` ticks later.
+///   Pulses shorter than the delay are extended to it. A powered repeater or comparator pointing into one of its sides locks it in its current state.
+/// - A ```Comparator``` (```mode```) outputs the power at its back to its front, compared against the strongest of its two sides:
+///   ```compare``` passes the back power if it is at least as strong as the sides, ```subtract``` passes the difference. Its output is stored in the ```power_lvl```.
+/// - A ```Lamp``` (```lit```) is lit while any of its sides is powered, see ```BlockRegistry::light_emission()```.
+///
+/// Solid full blocks without a component conduct: torches, repeaters (from behind), comparators (from behind) and lamps read the power
+/// of such a block as the strongest power that is sent into it. Wires only read power sent into them directly.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Deserialize)]
+pub enum RedstoneComponent {
+    #[default]
+    None,
+    Wire,
+    Torch,
+    Repeater,
+    Comparator,
+    Lamp
+}
+
+impl RedstoneComponent {
+
+    /// # Description:
+    /// The state properties every block type of this component has to declare
+    pub fn required_properties(self) -> &'static [&'static str] {
+        match self {
+            RedstoneComponent::None | RedstoneComponent::Wire => &[],
+            RedstoneComponent::Torch | RedstoneComponent::Lamp => &[LIT],
+            RedstoneComponent::Repeater => &[DELAY, POWERED],
+            RedstoneComponent::Comparator => &[MODE],
+        }
+    }
+}
+
+/// the state property names of the components
+const LIT: &str = "lit";
+const DELAY: &str = "delay";
+const POWERED: &str = "powered";
+const MODE: &str = "mode";
+
+/// # Description:
+/// Simulates redstone power on the ```VoxelWorld``` as part of the game tick (see ```tick_logic::game_tick```), independent of the frame rate.
+///
+/// Block changes are reported with ```notify()``` and settled at the end of the next tick: every wire network around a change is recomputed as a whole
+/// (so the result does not depend on the order of the changes or on the chunks the network spans) and lamps switch right away,
+/// while torches, repeaters and comparators schedule an update with the ```TickScheduler``` that runs after their delay.
+/// # Structure:
+/// ```
+/// pub struct RedstoneSimulation {
+///     pending: BTreeSet<[i32; 3]>,
+/// }
+/// ```
+#[derive(Resource, Clone, Default, Debug)]
+pub struct RedstoneSimulation {
+    pending: BTreeSet<[i32; 3]>,
+}
+
+impl RedstoneSimulation {
+
+    /// # Description:
+    /// Creates a simulation without any pending updates
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Description:
+    /// The amount of positions that are updated on the next tick
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// # Description:
+    /// Reports that the ```Block``` at ```block_pos``` changed. Every wire and component that may be affected by it is updated on the next tick.
+    pub fn notify(&mut self, block_pos: BlockPos) {
+        self.pending.insert(block_pos.0.to_array());
+        for facing in Facing::ALL {
+            self.pending.insert(block_pos.neighbour(facing).0.to_array());
+        }
+        for pos in wire_connections(block_pos) {
+            self.pending.insert(pos.0.to_array());
+        }
+    }
+
+    /// # Description:
+    /// Handles the changes reported since the last call until nothing changes anymore. Only wires and lamps react within the tick, so this always ends.
+    pub fn settle(&mut self, world: &mut VoxelWorld, registry: &BlockRegistry, scheduler: &mut TickScheduler) {
+        while !self.pending.is_empty() {
+            let mut solved = HashSet::new();
+            for pos in std::mem::take(&mut self.pending).into_iter().map(|pos| BlockPos(IVec3::from_array(pos))) {
+                let Ok(block) = world.get_block(pos).copied() else { continue };
+                match component(registry, &block) {
+                    RedstoneComponent::Wire => if !solved.contains(&pos) {
+                        let (network, changed) = update_network(world, registry, pos);
+                        solved.extend(network);
+                        for wire in changed {
+                            self.notify(wire);
+                        }
+                    },
+                    // the power of a conductor reaches the components around it
+                    RedstoneComponent::None if is_conductor(registry, &block) => for facing in Facing::ALL {
+                        self.check_component(world, registry, scheduler, pos.neighbour(facing));
+                    },
+                    _ => self.check_component(world, registry, scheduler, pos),
+                }
+            }
+        }
+    }
+
+    /// Switches a lamp right away, or schedules an update of a delayed component whose output does not match its inputs anymore
+    fn check_component(&mut self, world: &mut VoxelWorld, registry: &BlockRegistry, scheduler: &mut TickScheduler, block_pos: BlockPos) {
+        let Ok(block) = world.get_block(block_pos).copied() else { return };
+        match component(registry, &block) {
+            RedstoneComponent::Torch => if torch_lit(world, registry, block_pos, &block) != flag(registry, &block, LIT) {
+                schedule(world, scheduler, block_pos, TORCH_DELAY, DEFAULT_PRIORITY);
+            },
+            RedstoneComponent::Repeater => {
+                let input = repeater_input(world, registry, block_pos, &block);
+                if input != flag(registry, &block, POWERED) && !is_locked(world, registry, block_pos, &block) {
+                    schedule(world, scheduler, block_pos, repeater_delay(registry, &block), REPEATER_PRIORITY);
+                }
+            },
+            RedstoneComponent::Comparator => if comparator_output(world, registry, block_pos, &block) != block.power_lvl() {
+                schedule(world, scheduler, block_pos, COMPARATOR_DELAY, DEFAULT_PRIORITY);
+            },
+            RedstoneComponent::Lamp => {
+                let lit = Facing::ALL.into_iter().any(|side| received_power(world, registry, block_pos, side) > 0);
+                if lit != flag(registry, &block, LIT) {
+                    self.apply(world, registry, block_pos, with_flag(registry, block, LIT, lit));
+                }
+            },
+            RedstoneComponent::Wire | RedstoneComponent::None => {},
+        }
+    }
+
+    fn apply(&mut self, world: &mut VoxelWorld, registry: &BlockRegistry, block_pos: BlockPos, block: Block) {
+        set_block(world, registry, self, block_pos, block).expect("components are only updated in loaded chunks");
+    }
+}
+
+/// # Description:
+/// Runs the scheduled update of the delayed component at ```block_pos```, with its inputs as they are now.
+/// Its changes reach the ```RedstoneSimulation``` like every other block change of the tick, see ```game_tick::run_tick()```.
+pub fn run_scheduled(context: &mut BlockContext, block_pos: BlockPos) {
+    let (world, registry, scheduler) = (&mut *context.world, context.registry, &mut *context.scheduler);
+    let Ok(block) = world.get_block(block_pos).copied() else { return };
+    match component(registry, &block) {
+        RedstoneComponent::Torch => {
+            let lit = torch_lit(world, registry, block_pos, &block);
+            if lit != flag(registry, &block, LIT) {
+                set_component(world, registry, block_pos, with_flag(registry, block, LIT, lit));
+            }
+        },
+        RedstoneComponent::Repeater => {
+            if is_locked(world, registry, block_pos, &block) {
+                return;
+            }
+            let input = repeater_input(world, registry, block_pos, &block);
+            if !flag(registry, &block, POWERED) {
+                set_component(world, registry, block_pos, with_flag(registry, block, POWERED, true));
+                // the input was already gone again, the pulse still lasts for the whole delay
+                if !input {
+                    schedule(world, scheduler, block_pos, repeater_delay(registry, &block), REPEATER_PRIORITY);
+                }
+            } else if !input {
+                set_component(world, registry, block_pos, with_flag(registry, block, POWERED, false));
+            }
+        },
+        RedstoneComponent::Comparator => {
+            let output = comparator_output(world, registry, block_pos, &block);
+            if output != block.power_lvl() {
+                set_component(world, registry, block_pos, block.with_power_lvl(output));
+            }
+        },
+        RedstoneComponent::Lamp | RedstoneComponent::Wire | RedstoneComponent::None => {},
+    }
+}
+
+/// # Description:
+/// The ```BlockBehaviour``` of the redstone components with a delay (torches, repeaters and comparators). They pop off like their ```NeedsSupport``` says
+/// and their scheduled updates run through ```run_scheduled()```.
+/// # Format:
+/// ```
+/// pub struct RedstoneBehaviour(pub NeedsSupport);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RedstoneBehaviour(pub NeedsSupport);
+
+impl BlockBehaviour for RedstoneBehaviour {
+    fn neighbour_changed(&self, context: &mut BlockContext, block_pos: BlockPos, side: Facing) {
+        self.0.neighbour_changed(context, block_pos, side);
+    }
+
+    fn scheduled_update(&self, context: &mut BlockContext, block_pos: BlockPos) {
+        run_scheduled(context, block_pos);
+    }
+
+    fn can_place(&self, world: &VoxelWorld, registry: &BlockRegistry, block_pos: BlockPos, block: &Block) -> bool {
+        self.0.can_place(world, registry, block_pos, block)
+    }
+}
+
+/// # Description:
+/// Registers the ```RedstoneBehaviour``` of every torch, repeater and comparator of ```registry```,
+/// in place of the plain ```NeedsSupport``` they get from ```BlockBehaviours::with_defaults()```
+pub fn register_behaviours(behaviours: &mut BlockBehaviours, registry: &BlockRegistry) {
+    for (id, definition) in registry.iter() {
+        let support = match definition.redstone {
+            RedstoneComponent::Torch => Support::Behind,
+            RedstoneComponent::Repeater | RedstoneComponent::Comparator => Support::Below,
+            RedstoneComponent::Wire | RedstoneComponent::Lamp | RedstoneComponent::None => continue,
+        };
+        behaviours.register(id, RedstoneBehaviour(NeedsSupport(support)));
+    }
+}
+
+/// # Description:
+/// Replaces the ```Block``` at the world position like ```light::set_block()``` does and reports the change to the ```simulation```.
+/// Returns the ```Block``` that was there before.
+/// # Errors:
+/// ```WorldError::ChunkNotLoaded``` if the containing chunk is not loaded
+pub fn set_block(world: &mut VoxelWorld, registry: &BlockRegistry, simulation: &mut RedstoneSimulation, block_pos: BlockPos, block: Block) -> Result<Block, WorldError> {
+    let previous = light::set_block(world, registry, block_pos, block)?;
+    if previous != block {
+        simulation.notify(block_pos);
+    }
+    Ok(previous)
+}
+
+/// # Description:
+/// The redstone power the ```Block``` at the world position sends out, the strongest over all of its sides. 0 for unpowered blocks or unloaded chunks.
+pub fn power_at(world: &VoxelWorld, registry: &BlockRegistry, block_pos: BlockPos) -> u8 {
+    Facing::ALL.into_iter().map(|facing| emitted(world, registry, block_pos, facing)).max().unwrap_or(0)
+}
+
+/// changes a component during its scheduled update, the chunk it is in was loaded when the update became due
+fn set_component(world: &mut VoxelWorld, registry: &BlockRegistry, block_pos: BlockPos, block: Block) {
+    light::set_block(world, registry, block_pos, block).expect("components are only updated in loaded chunks");
+}
+
+/// schedules an update of a component ```delay``` ticks from now, unless it already has one
+fn schedule(world: &mut VoxelWorld, scheduler: &mut TickScheduler, block_pos: BlockPos, delay: u8, priority: i8) {
+    scheduler.schedule(world, block_pos, delay as u32, priority).expect("components are only checked in loaded chunks");
+}
+
+fn component(registry: &BlockRegistry, block: &Block) -> RedstoneComponent {
+    registry.definition(block.block_type()).redstone
+}
+
+fn flag(registry: &BlockRegistry, block: &Block, property: &str) -> bool {
+    registry.property(block, property) == Ok(PropertyValue::Bool(true))
+}
+
+fn with_flag(registry: &BlockRegistry, block: Block, property: &str, value: bool) -> Block {
+    registry.with_property(block, property, PropertyValue::Bool(value)).expect("the registry checks the properties of redstone components")
+}
+
+fn repeater_delay(registry: &BlockRegistry, repeater: &Block) -> u8 {
+    match registry.property(repeater, DELAY) {
+        Ok(PropertyValue::Int(delay)) => delay.max(1),
+        _ => 1,
+    }
+}
+
+/// solid full blocks without any redstone behaviour pass the power sent into them on to the components around them
+fn is_conductor(registry: &BlockRegistry, block: &Block) -> bool {
+    let definition = registry.definition(block.block_type());
+    definition.redstone == RedstoneComponent::None
+        && definition.redstone_power_lvl == RedstonePowerLvl::None
+        && definition.render_layer == RenderLayer::Solid
+        && registry.shape(block).is_full_cube()
+}
+
+/// the power the block at ```block_pos``` sends into its neighbour in the direction ```toward```
+fn emitted(world: &VoxelWorld, registry: &BlockRegistry, block_pos: BlockPos, toward: Facing) -> u8 {
+    let Ok(block) = world.get_block(block_pos) else { return 0 };
+    let definition = registry.definition(block.block_type());
+    let source = match definition.redstone_power_lvl {
+        RedstonePowerLvl::Some(level) => level.min(MAX_POWER),
+        RedstonePowerLvl::None => 0,
+    };
+    let facing = block.face_direction();
+    let output = match definition.redstone {
+        // wires lie on the ground, they power what they point at and the block below them
+        RedstoneComponent::Wire if toward != Facing::YPositive => block.power_lvl(),
+        RedstoneComponent::Torch if toward != facing.opposite() && flag(registry, block, LIT) => MAX_POWER,
+        RedstoneComponent::Repeater if toward == facing && flag(registry, block, POWERED) => MAX_POWER,
+        RedstoneComponent::Comparator if toward == facing => block.power_lvl(),
+        _ => 0,
+    };
+    source.max(output)
+}
+
+/// the power the block at ```block_pos``` receives from its ```side```, through a conductor if there is one
+fn received_power(world: &VoxelWorld, registry: &BlockRegistry, block_pos: BlockPos, side: Facing) -> u8 {
+    let neighbour_pos = block_pos.neighbour(side);
+    match world.get_block(neighbour_pos) {
+        Ok(neighbour) if is_conductor(registry, neighbour) => Facing::ALL.into_iter()
+            .filter(|facing| *facing != side.opposite())
+            .map(|facing| emitted(world, registry, neighbour_pos.neighbour(facing), facing.opposite()))
+            .max()
+            .unwrap_or(0),
+        Ok(_) => emitted(world, registry, neighbour_pos, side.opposite()),
+        Err(_) => 0,
+    }
+}
+
+/// the two horizontal sides of a component facing ```facing```
+fn sides(facing: Facing) -> [Facing; 2] {
+    [Facing::ZPositive, Facing::ZNegative].map(|side| side.rotated(facing))
+}
+
+fn torch_lit(world: &VoxelWorld, registry: &BlockRegistry, block_pos: BlockPos, torch: &Block) -> bool {
+    received_power(world, registry, block_pos, torch.face_direction().opposite()) == 0
+}
+
+fn repeater_input(world: &VoxelWorld, registry: &BlockRegistry, block_pos: BlockPos, repeater: &Block) -> bool {
+    received_power(world, registry, block_pos, repeater.face_direction().opposite()) > 0
+}
+
+fn is_locked(world: &VoxelWorld, registry: &BlockRegistry, block_pos: BlockPos, repeater: &Block) -> bool {
+    sides(repeater.face_direction()).into_iter().any(|side| {
+        let side_pos = block_pos.neighbour(side);
+        world.get_block(side_pos).is_ok_and(|block| matches!(component(registry, block), RedstoneComponent::Repeater | RedstoneComponent::Comparator))
+            && emitted(world, registry, side_pos, side.opposite()) > 0
+    })
+}
+
+fn comparator_output(world: &VoxelWorld, registry: &BlockRegistry, block_pos: BlockPos, comparator: &Block) -> u8 {
+    let facing = comparator.face_direction();
+    let back = received_power(world, registry, block_pos, facing.opposite());
+    // the sides only read what is sent into them directly
+    let side = sides(facing).into_iter().map(|side| emitted(world, registry, block_pos.neighbour(side), side.opposite())).max().unwrap_or(0);
+    if registry.property(comparator, MODE) == Ok(PropertyValue::Enum("subtract")) {
+        back.saturating_sub(side)
+    } else if back >= side {
+        back
+    } else {
+        0
+    }
+}
+
+fn is_wire(world: &VoxelWorld, registry: &BlockRegistry, block_pos: BlockPos) -> bool {
+    world.get_block(block_pos).is_ok_and(|block| component(registry, block) == RedstoneComponent::Wire)
+}
+
+/// the positions a wire at ```block_pos``` connects to: its four horizontal neighbours and the ones a step above and below them
+fn wire_connections(block_pos: BlockPos) -> impl Iterator<Item = BlockPos> {
+    [Facing::XPositive, Facing::XNegative, Facing::ZPositive, Facing::ZNegative].into_iter()
+        .flat_map(move |facing| [IVec3::ZERO, IVec3::Y, IVec3::NEG_Y].map(|step| block_pos.neighbour(facing) + step))
+}
+
+/// Recomputes the power of the whole wire network that contains ```start```, returns its wires and the ones whose power changed.
+///
+/// Every wire starts with the strongest power sent into it from outside the network, then the power spreads along the wires from the strongest level down,
+/// losing one level per wire. Only wires whose power changed are written back.
+fn update_network(world: &mut VoxelWorld, registry: &BlockRegistry, start: BlockPos) -> (Vec<BlockPos>, Vec<BlockPos>) {
+    let mut network = vec![start];
+    let mut seen = HashSet::from([start]);
+    let mut index = 0;
+    while let Some(pos) = network.get(index).copied() {
+        index += 1;
+        for next in wire_connections(pos) {
+            if is_wire(world, registry, next) && seen.insert(next) {
+                network.push(next);
+            }
+        }
+    }
+
+    let mut power: HashMap<BlockPos, u8> = HashMap::new();
+    let mut levels = vec![Vec::new(); MAX_POWER as usize + 1];
+    for pos in &network {
+        let level = Facing::ALL.into_iter()
+            .filter(|facing| !is_wire(world, registry, pos.neighbour(*facing)))
+            .map(|facing| emitted(world, registry, pos.neighbour(facing), facing.opposite()))
+            .max()
+            .unwrap_or(0);
+        power.insert(*pos, level);
+        levels[level as usize].push(*pos);
+    }
+    for level in (2..=MAX_POWER).rev() {
+        for pos in std::mem::take(&mut levels[level as usize]) {
+            if power[&pos] != level {
+                continue;
+            }
+            for next in wire_connections(pos) {
+                if let Some(next_power) = power.get_mut(&next).filter(|next_power| **next_power < level - 1) {
+                    *next_power = level - 1;
+                    levels[level as usize - 1].push(next);
+                }
+            }
+        }
+    }
+
+    let mut changed = Vec::new();
+    for pos in &network {
+        let block = *world.get_block(*pos).expect("the network only contains loaded wires");
+        if block.power_lvl() != power[pos] {
+            world.set_block(*pos, block.with_power_lvl(power[pos])).expect("the network only contains loaded wires");
+            changed.push(*pos);
+        }
+    }
+    (network, changed)
+}
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_registry::{test_block, test_registry};
+    use crate::chunk_logic::position_handling::ChunkPos;
+    use crate::chunk_logic::world::test_floor_world;
+    use crate::tick_logic::game_tick;
+
+    /// runs the scheduled updates like ```RedstoneBehaviour``` does, but never pops off, so the test circuits need no support blocks
+    struct Floating;
+
+    impl BlockBehaviour for Floating {
+        fn neighbour_changed(&self, _context: &mut BlockContext, _block_pos: BlockPos, _side: Facing) {}
+
+        fn scheduled_update(&self, context: &mut BlockContext, block_pos: BlockPos) {
+            run_scheduled(context, block_pos);
+        }
+    }
+
+    fn floating_behaviours() -> BlockBehaviours {
+        let mut behaviours = BlockBehaviours::new();
+        for name in ["redstone_torch", "repeater", "comparator"] {
+            behaviours.register(test_block(name).block_type(), Floating);
+        }
+        behaviours
+    }
+
+    fn place(world: &mut VoxelWorld, simulation: &mut RedstoneSimulation, pos: BlockPos, name: &str) {
+        set_block(world, test_registry(), simulation, pos, test_block(name)).unwrap();
+    }
+
+    fn wire_line(world: &mut VoxelWorld, simulation: &mut RedstoneSimulation, from: BlockPos, length: i32) {
+        for step in 0..length {
+            place(world, simulation, from + IVec3::X * step, "redstone_wire");
+        }
+    }
+
+    fn power(world: &VoxelWorld, pos: BlockPos) -> u8 {
+        power_at(world, test_registry(), pos)
+    }
+
+    fn place_block(world: &mut VoxelWorld, simulation: &mut RedstoneSimulation, pos: BlockPos, block: Block) {
+        set_block(world, test_registry(), simulation, pos, block).unwrap();
+    }
+
+    fn oriented(name: &str, facing: Facing) -> Block {
+        Block::new(test_block(name).block_type(), facing)
+    }
+
+    fn repeater(facing: Facing, delay: u8) -> Block {
+        test_registry().with_property(oriented("repeater", facing), DELAY, PropertyValue::Int(delay)).unwrap()
+    }
+
+    fn is_on(world: &VoxelWorld, pos: BlockPos, property: &str) -> bool {
+        flag(test_registry(), world.get_block(pos).unwrap(), property)
+    }
+
+    fn step(world: &mut VoxelWorld, simulation: &mut RedstoneSimulation, scheduler: &mut TickScheduler, ticks: u32) {
+        game_tick::step(world, test_registry(), &floating_behaviours(), scheduler, simulation, ticks);
+    }
+
+    /// the ticks (counted from now) in which the ```property``` of the block at ```pos``` changed during the next ```ticks``` ticks
+    fn transitions(world: &mut VoxelWorld, simulation: &mut RedstoneSimulation, scheduler: &mut TickScheduler, pos: BlockPos, property: &str, ticks: u32) -> Vec<u32> {
+        let mut state = is_on(world, pos, property);
+        let mut changes = Vec::new();
+        for tick in 1..=ticks {
+            step(world, simulation, scheduler, 1);
+            if is_on(world, pos, property) != state {
+                state = !state;
+                changes.push(tick);
+            }
+        }
+        changes
+    }
+
+    #[test]
+    fn test_power_decays_along_wire() {
+        let mut world = test_floor_world(0..=1);
+        let mut simulation = RedstoneSimulation::new();
+        let mut scheduler = TickScheduler::new();
+        place(&mut world, &mut simulation, BlockPos::new(0, 2, 5), "redstone_block");
+        wire_line(&mut world, &mut simulation, BlockPos::new(1, 2, 5), 20);
+        step(&mut world, &mut simulation, &mut scheduler, 1);
+
+        assert_eq!(MAX_POWER, power(&world, BlockPos::new(0, 2, 5)));
+        for step in 0..20 {
+            assert_eq!(15u8.saturating_sub(step as u8), power(&world, BlockPos::new(1 + step, 2, 5)), "wire {step}");
+        }
+        assert_eq!(0, simulation.pending_count());
+    }
+
+    #[test]
+    fn test_updates_wait_for_the_next_tick() {
+        let mut world = test_floor_world([0]);
+        let mut simulation = RedstoneSimulation::new();
+        let mut scheduler = TickScheduler::new();
+        wire_line(&mut world, &mut simulation, BlockPos::new(1, 2, 5), 3);
+        step(&mut world, &mut simulation, &mut scheduler, 1);
+        place(&mut world, &mut simulation, BlockPos::new(0, 2, 5), "redstone_block");
+        assert_eq!(0, power(&world, BlockPos::new(1, 2, 5)));
+        step(&mut world, &mut simulation, &mut scheduler, 1);
+        assert_eq!(15, power(&world, BlockPos::new(1, 2, 5)));
+        assert_eq!(2, scheduler.tick());
+    }
+
+    #[test]
+    fn test_removing_the_source_depowers() {
+        let mut world = test_floor_world([0]);
+        let mut simulation = RedstoneSimulation::new();
+        let mut scheduler = TickScheduler::new();
+        place(&mut world, &mut simulation, BlockPos::new(0, 2, 5), "redstone_block");
+        wire_line(&mut world, &mut simulation, BlockPos::new(1, 2, 5), 5);
+        step(&mut world, &mut simulation, &mut scheduler, 1);
+        assert_eq!(11, power(&world, BlockPos::new(5, 2, 5)));
+
+        place(&mut world, &mut simulation, BlockPos::new(0, 2, 5), "air");
+        step(&mut world, &mut simulation, &mut scheduler, 1);
+        for x in 1..=5 {
+            assert_eq!(0, power(&world, BlockPos::new(x, 2, 5)));
+        }
+
+        // cutting the wire only depowers the part behind the cut
+        place(&mut world, &mut simulation, BlockPos::new(0, 2, 5), "redstone_block");
+        place(&mut world, &mut simulation, BlockPos::new(3, 2, 5), "air");
+        step(&mut world, &mut simulation, &mut scheduler, 1);
+        assert_eq!(14, power(&world, BlockPos::new(2, 2, 5)));
+        assert_eq!(0, power(&world, BlockPos::new(4, 2, 5)));
+    }
+
+    #[test]
+    fn test_strongest_source_wins() {
+        let mut world = test_floor_world([0]);
+        let mut simulation = RedstoneSimulation::new();
+        let mut scheduler = TickScheduler::new();
+        wire_line(&mut world, &mut simulation, BlockPos::new(1, 2, 5), 10);
+        place(&mut world, &mut simulation, BlockPos::new(0, 2, 5), "redstone_block");
+        place(&mut world, &mut simulation, BlockPos::new(11, 2, 5), "redstone_block");
+        step(&mut world, &mut simulation, &mut scheduler, 1);
+        assert_eq!(15, power(&world, BlockPos::new(1, 2, 5)));
+        assert_eq!(11, power(&world, BlockPos::new(5, 2, 5)));
+        assert_eq!(11, power(&world, BlockPos::new(6, 2, 5)));
+        assert_eq!(15, power(&world, BlockPos::new(10, 2, 5)));
+    }
+
+    #[test]
+    fn test_wire_steps_up_and_down() {
+        let mut world = test_floor_world([0]);
+        let mut simulation = RedstoneSimulation::new();
+        let mut scheduler = TickScheduler::new();
+        place(&mut world, &mut simulation, BlockPos::new(0, 2, 5), "redstone_block");
+        place(&mut world, &mut simulation, BlockPos::new(1, 2, 5), "redstone_wire");
+        place(&mut world, &mut simulation, BlockPos::new(2, 2, 5), "stone");
+        place(&mut world, &mut simulation, BlockPos::new(2, 3, 5), "redstone_wire");
+        place(&mut world, &mut simulation, BlockPos::new(3, 2, 5), "redstone_wire");
+        step(&mut world, &mut simulation, &mut scheduler, 1);
+        assert_eq!(14, power(&world, BlockPos::new(2, 3, 5)));
+        assert_eq!(13, power(&world, BlockPos::new(3, 2, 5)));
+    }
+
+    #[test]
+    fn test_power_crosses_chunk_borders() {
+        let mut world = test_floor_world(-1..=0);
+        let mut simulation = RedstoneSimulation::new();
+        let mut scheduler = TickScheduler::new();
+        place(&mut world, &mut simulation, BlockPos::new(-3, 2, 5), "redstone_block");
+        wire_line(&mut world, &mut simulation, BlockPos::new(-2, 2, 5), 6);
+        step(&mut world, &mut simulation, &mut scheduler, 1);
+        assert_eq!(14, power(&world, BlockPos::new(-1, 2, 5)));
+        assert_eq!(13, power(&world, BlockPos::new(0, 2, 5)));
+        assert_eq!(10, power(&world, BlockPos::new(3, 2, 5)));
+
+        // the same circuit built in the opposite order gives the same result
+        let mut mirrored = test_floor_world(-1..=0);
+        let mut simulation = RedstoneSimulation::new();
+        let mut scheduler = TickScheduler::new();
+        for x in (-2..=3).rev() {
+            place(&mut mirrored, &mut simulation, BlockPos::new(x, 2, 5), "redstone_wire");
+            step(&mut mirrored, &mut simulation, &mut scheduler, 1);
+        }
+        place(&mut mirrored, &mut simulation, BlockPos::new(-3, 2, 5), "redstone_block");
+        step(&mut mirrored, &mut simulation, &mut scheduler, 1);
+        for x in -3..=3 {
+            assert_eq!(power(&world, BlockPos::new(x, 2, 5)), power(&mirrored, BlockPos::new(x, 2, 5)));
+        }
+    }
+
+    #[test]
+    fn test_unloaded_chunks_cut_the_network() {
+        let mut world = test_floor_world(0..=1);
+        let mut simulation = RedstoneSimulation::new();
+        let mut scheduler = TickScheduler::new();
+        place(&mut world, &mut simulation, BlockPos::new(12, 2, 5), "redstone_block");
+        wire_line(&mut world, &mut simulation, BlockPos::new(13, 2, 5), 6);
+        step(&mut world, &mut simulation, &mut scheduler, 1);
+        let chunk = world.remove_chunk(ChunkPos::ZERO).unwrap();
+        simulation.notify(BlockPos::new(16, 2, 5));
+        step(&mut world, &mut simulation, &mut scheduler, 1);
+        assert_eq!(0, power(&world, BlockPos::new(16, 2, 5)));
+
+        world.insert_chunk(ChunkPos::ZERO, chunk);
+        simulation.notify(BlockPos::new(15, 2, 5));
+        step(&mut world, &mut simulation, &mut scheduler, 1);
+        assert_eq!(12, power(&world, BlockPos::new(16, 2, 5)));
+    }
+
+    #[test]
+    fn test_not_gate_switches_a_lamp() {
+        let mut world = test_floor_world([0]);
+        let mut simulation = RedstoneSimulation::new();
+        let mut scheduler = TickScheduler::new();
+        let [input, block, torch, lamp] = [1, 2, 3, 4].map(|x| BlockPos::new(x, 2, 5));
+        place(&mut world, &mut simulation, block, "stone");
+        place_block(&mut world, &mut simulation, torch, oriented("redstone_torch", Facing::XPositive));
+        place(&mut world, &mut simulation, lamp, "redstone_lamp");
+        step(&mut world, &mut simulation, &mut scheduler, 2);
+        assert!(is_on(&world, torch, LIT));
+        assert!(is_on(&world, lamp, LIT));
+        assert_eq!(Ok(15), light::block_light(&world, lamp));
+
+        // the stone passes the input on to the torch, which goes out one tick after it noticed, the lamp follows right away
+        place(&mut world, &mut simulation, input, "redstone_block");
+        step(&mut world, &mut simulation, &mut scheduler, 1);
+        assert!(is_on(&world, torch, LIT));
+        step(&mut world, &mut simulation, &mut scheduler, 1);
+        assert!(!is_on(&world, torch, LIT));
+        assert!(!is_on(&world, lamp, LIT));
+        assert_eq!(Ok(0), light::block_light(&world, lamp));
+
+        place(&mut world, &mut simulation, input, "air");
+        step(&mut world, &mut simulation, &mut scheduler, 2);
+        assert!(is_on(&world, lamp, LIT));
+    }
+
+    #[test]
+    fn test_repeater_delay_and_direction() {
+        for delay in 1..=4 {
+            let mut world = test_floor_world([0]);
+            let mut simulation = RedstoneSimulation::new();
+            let mut scheduler = TickScheduler::new();
+            // a weak input through a long wire comes out at full power
+            wire_line(&mut world, &mut simulation, BlockPos::new(1, 2, 5), 13);
+            place_block(&mut world, &mut simulation, BlockPos::new(14, 2, 5), repeater(Facing::XPositive, delay));
+            place(&mut world, &mut simulation, BlockPos::new(15, 2, 5), "redstone_wire");
+            // the same repeater turned around does not pass anything
+            place_block(&mut world, &mut simulation, BlockPos::new(1, 2, 7), repeater(Facing::XNegative, delay));
+            place(&mut world, &mut simulation, BlockPos::new(2, 2, 7), "redstone_wire");
+            step(&mut world, &mut simulation, &mut scheduler, 1);
+
+            place(&mut world, &mut simulation, BlockPos::new(0, 2, 5), "redstone_block");
+            place(&mut world, &mut simulation, BlockPos::new(0, 2, 7), "redstone_block");
+            // one tick to notice the new blocks, then the delay
+            let changes = transitions(&mut world, &mut simulation, &mut scheduler, BlockPos::new(14, 2, 5), POWERED, 10);
+            assert_eq!(vec![1 + delay as u32], changes);
+            assert_eq!(3, power(&world, BlockPos::new(13, 2, 5)));
+            assert_eq!(15, power(&world, BlockPos::new(15, 2, 5)));
+            assert_eq!(0, power(&world, BlockPos::new(2, 2, 7)));
+        }
+    }
+
+    #[test]
+    fn test_pulse_extender() {
+        let mut world = test_floor_world([0]);
+        let mut simulation = RedstoneSimulation::new();
+        let mut scheduler = TickScheduler::new();
+        let output = BlockPos::new(4, 2, 5);
+        place(&mut world, &mut simulation, BlockPos::new(2, 2, 5), "redstone_wire");
+        place_block(&mut world, &mut simulation, BlockPos::new(3, 2, 5), repeater(Facing::XPositive, 4));
+        place(&mut world, &mut simulation, output, "redstone_wire");
+        step(&mut world, &mut simulation, &mut scheduler, 1);
+
+        // a single tick pulse
+        place(&mut world, &mut simulation, BlockPos::new(1, 2, 5), "redstone_block");
+        step(&mut world, &mut simulation, &mut scheduler, 1);
+        place(&mut world, &mut simulation, BlockPos::new(1, 2, 5), "air");
+        let powered: Vec<bool> = (0..12).map(|_| {
+            step(&mut world, &mut simulation, &mut scheduler, 1);
+            power(&world, output) > 0
+        }).collect();
+        assert_eq!(4, powered.iter().filter(|on| **on).count());
+        // the extended pulse is one piece
+        assert_eq!(1, powered.windows(2).filter(|pair| pair[0] && !pair[1]).count());
+        assert_eq!(0, scheduler.queue_len(&world));
+    }
+
+    #[test]
+    fn test_scheduled_updates_run_by_block_type() {
+        let mut redstone_behaviours = BlockBehaviours::with_defaults(test_registry());
+        register_behaviours(&mut redstone_behaviours, test_registry());
+        for (behaviours, lit) in [(BlockBehaviours::with_defaults(test_registry()), false), (redstone_behaviours, true)] {
+            let mut world = test_floor_world([0]);
+            let mut simulation = RedstoneSimulation::new();
+            let mut scheduler = TickScheduler::new();
+            let torch = BlockPos::new(3, 2, 5);
+            place(&mut world, &mut simulation, BlockPos::new(2, 2, 5), "stone");
+            place_block(&mut world, &mut simulation, torch, oriented("redstone_torch", Facing::XPositive));
+            game_tick::step(&mut world, test_registry(), &behaviours, &mut scheduler, &mut simulation, 1);
+            assert!(scheduler.is_scheduled(&world, torch));
+
+            // without the redstone behaviour for its block type the due update is dropped
+            game_tick::step(&mut world, test_registry(), &behaviours, &mut scheduler, &mut simulation, 2);
+            assert_eq!(lit, is_on(&world, torch, LIT));
+        }
+    }
+
+    #[test]
+    fn test_torch_clock() {
+        for delay in [1, 3] {
+            // the torch powers a wire that loops back through a repeater into the block the torch is attached to
+            let mut world = test_floor_world([0]);
+            let mut simulation = RedstoneSimulation::new();
+            let mut scheduler = TickScheduler::new();
+            let torch = BlockPos::new(3, 2, 5);
+            place(&mut world, &mut simulation, BlockPos::new(2, 2, 5), "stone");
+            place_block(&mut world, &mut simulation, torch, oriented("redstone_torch", Facing::XPositive));
+            for (x, z) in [(3, 6), (3, 7), (2, 7)] {
+                place(&mut world, &mut simulation, BlockPos::new(x, 2, z), "redstone_wire");
+            }
+            place_block(&mut world, &mut simulation, BlockPos::new(2, 2, 6), repeater(Facing::ZNegative, delay));
+            step(&mut world, &mut simulation, &mut scheduler, 10);
+
+            let changes = transitions(&mut world, &mut simulation, &mut scheduler, torch, LIT, 60);
+            assert!(changes.len() >= 8, "{changes:?}");
+            // the torch and the repeater delay every half period
+            assert!(changes.windows(2).all(|pair| pair[1] - pair[0] == delay as u32 + TORCH_DELAY as u32), "{changes:?}");
+        }
+    }
+
+    #[test]
+    fn test_comparator_modes() {
+        let mut world = test_floor_world([0]);
+        let mut simulation = RedstoneSimulation::new();
+        let mut scheduler = TickScheduler::new();
+        let comparator = BlockPos::new(5, 2, 5);
+        let output = BlockPos::new(6, 2, 5);
+        // 12 at the back and 10 at the side
+        place(&mut world, &mut simulation, BlockPos::new(0, 2, 5), "redstone_block");
+        wire_line(&mut world, &mut simulation, BlockPos::new(1, 2, 5), 4);
+        place(&mut world, &mut simulation, BlockPos::new(5, 2, 12), "redstone_block");
+        for z in 6..12 {
+            place(&mut world, &mut simulation, BlockPos::new(5, 2, z), "redstone_wire");
+        }
+        place_block(&mut world, &mut simulation, comparator, oriented("comparator", Facing::XPositive));
+        place(&mut world, &mut simulation, output, "redstone_wire");
+        step(&mut world, &mut simulation, &mut scheduler, 2);
+        assert_eq!(12, power(&world, output));
+
+        let subtract = test_registry().with_property(oriented("comparator", Facing::XPositive), MODE, PropertyValue::Enum("subtract")).unwrap();
+        place_block(&mut world, &mut simulation, comparator, subtract);
+        step(&mut world, &mut simulation, &mut scheduler, 2);
+        assert_eq!(2, power(&world, output));
+
+        // a stronger side switches a comparing comparator off
+        place_block(&mut world, &mut simulation, comparator, oriented("comparator", Facing::XPositive));
+        place(&mut world, &mut simulation, BlockPos::new(5, 2, 7), "redstone_block");
+        step(&mut world, &mut simulation, &mut scheduler, 2);
+        assert_eq!(0, power(&world, output));
+    }
+
+    #[test]
+    fn test_t_flip_flop() {
+        // a master and a slave repeater, locked in turns by the input and its inverse, with a torch inverting the output back into the master
+        let mut world = test_floor_world([0]);
+        let mut simulation = RedstoneSimulation::new();
+        let mut scheduler = TickScheduler::new();
+        let at = |x: i32, z: i32| BlockPos::new(x, 2, z);
+        let (input, slave) = (at(10, 4), at(11, 6));
+        place(&mut world, &mut simulation, at(8, 6), "stone");
+        place_block(&mut world, &mut simulation, at(9, 6), oriented("redstone_torch", Facing::XPositive));
+        place_block(&mut world, &mut simulation, at(10, 6), repeater(Facing::XPositive, 2));
+        place_block(&mut world, &mut simulation, slave, repeater(Facing::XPositive, 1));
+        for (x, z) in [(12, 6), (12, 7), (12, 8), (11, 8), (10, 8), (9, 8), (8, 8), (7, 8), (7, 7), (7, 6)] {
+            place(&mut world, &mut simulation, at(x, z), "redstone_wire");
+        }
+        place_block(&mut world, &mut simulation, at(10, 5), repeater(Facing::ZPositive, 1));
+        place_block(&mut world, &mut simulation, at(11, 5), repeater(Facing::ZPositive, 1));
+        place_block(&mut world, &mut simulation, at(11, 4), oriented("redstone_torch", Facing::XPositive));
+        step(&mut world, &mut simulation, &mut scheduler, 20);
+        assert!(!is_on(&world, slave, POWERED));
+
+        let mut outputs = Vec::new();
+        for _ in 0..4 {
+            place(&mut world, &mut simulation, input, "redstone_block");
+            // the output toggles on the rising edge, always after the same time
+            assert_eq!(vec![4], transitions(&mut world, &mut simulation, &mut scheduler, slave, POWERED, 4));
+            place(&mut world, &mut simulation, input, "air");
+            assert_eq!(Vec::<u32>::new(), transitions(&mut world, &mut simulation, &mut scheduler, slave, POWERED, 10));
+            outputs.push(is_on(&world, slave, POWERED));
+        }
+        assert_eq!(vec![true, false, true, false], outputs);
+    }
+}
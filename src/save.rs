@@ -0,0 +1,90 @@
+// foreign imports
+use std::fmt;
+use std::path::{Path, PathBuf};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// How often the game is saved while it runs, in seconds. Closing the game saves it right away.
+pub const AUTOSAVE_INTERVAL: f32 = 30.0;
+
+/// # Description:
+/// Everything that can go wrong while reading or writing a save
+/// # Format:
+/// ```
+/// pub enum SaveError {
+///     Io(PathBuf, String),
+///     Parse(PathBuf, String),
+///     Serialize(String)
+/// }
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub enum SaveError {
+    Io(PathBuf, String),
+    Parse(PathBuf, String),
+    Serialize(String)
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(path, error) => write!(f, "could not access the save {}: {error}", path.display()),
+            SaveError::Parse(path, error) => write!(f, "could not parse the save {}: {error}", path.display()),
+            SaveError::Serialize(error) => write!(f, "could not serialize the save: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+/// # Description:
+/// Reads the RON save at ```path```, ```Ok(None)``` if there is none yet
+/// # Errors:
+/// fails if the file exists but cannot be read or parsed
+pub fn load_ron<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, SaveError> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(SaveError::Io(path.to_path_buf(), error.to_string())),
+    };
+    ron::from_str(&text).map(Some).map_err(|error| SaveError::Parse(path.to_path_buf(), error.to_string()))
+}
+
+/// # Description:
+/// Writes ```save``` as RON to ```path```, creating its directory if needed. The file is written next to it first and then moved over the old save,
+/// so closing the game while saving never leaves half a save behind.
+/// # Errors:
+/// fails if the directory or the file cannot be written
+pub fn write_ron<T: Serialize>(save: &T, path: &Path) -> Result<(), SaveError> {
+    let io_error = |error: std::io::Error| SaveError::Io(path.to_path_buf(), error.to_string());
+    let text = ron::ser::to_string_pretty(save, ron::ser::PrettyConfig::default()).map_err(|error| SaveError::Serialize(error.to_string()))?;
+    if let Some(directory) = path.parent().filter(|directory| !directory.as_os_str().is_empty()) {
+        std::fs::create_dir_all(directory).map_err(io_error)?;
+    }
+    let temporary = path.with_extension("ron.tmp");
+    std::fs::write(&temporary, text).map_err(io_error)?;
+    std::fs::rename(&temporary, path).map_err(io_error)
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ron_round_trip() {
+        let directory = std::env::temp_dir().join(format!("voxel_rust_ron_round_trip_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        let path = directory.join("nested").join("save.ron");
+        assert_eq!(Ok(None), load_ron::<Vec<u8>>(&path));
+
+        write_ron(&vec![1u8, 2, 3], &path).unwrap();
+        assert_eq!(Ok(Some(vec![1u8, 2, 3])), load_ron(&path));
+        assert!(!path.with_extension("ron.tmp").exists());
+
+        std::fs::write(&path, "[1, 2").unwrap();
+        assert!(matches!(load_ron::<Vec<u8>>(&path), Err(SaveError::Parse(..))));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
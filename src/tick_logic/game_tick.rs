@@ -0,0 +1,46 @@
+// foreign imports
+use std::time::Instant;
+use bevy::prelude::{Res, ResMut};
+
+// 'self' imports
+use crate::block_registry::BlockRegistry;
+use crate::chunk_logic::position_handling::BlockPos;
+use crate::chunk_logic::world::VoxelWorld;
+use crate::redstone_logic::redstone::RedstoneSimulation;
+use crate::tick_logic::neighbour_updates::{BlockBehaviours, BlockContext};
+use crate::tick_logic::scheduler::TickScheduler;
+
+/// # Description:
+/// Runs one game tick on the ```VoxelWorld```: the scheduled block updates that are due, the neighbour updates of every block changed since the last tick,
+/// then the redstone changes of the tick. This is all a tick does, so tests can step a world headless by calling it directly.
+pub fn run_tick(world: &mut VoxelWorld, registry: &BlockRegistry, behaviours: &BlockBehaviours, scheduler: &mut TickScheduler, simulation: &mut RedstoneSimulation) {
+    let start = Instant::now();
+    for update in scheduler.advance(world) {
+        run_block_update(world, registry, behaviours, scheduler, update.block_pos);
+    }
+    for block_pos in behaviours.propagate(world, registry, scheduler).changed {
+        simulation.notify(block_pos);
+    }
+    simulation.settle(world, registry, scheduler);
+    scheduler.finish(world, start.elapsed());
+}
+
+/// # Description:
+/// Runs ```ticks``` game ticks in a row, see ```run_tick()```
+pub fn step(world: &mut VoxelWorld, registry: &BlockRegistry, behaviours: &BlockBehaviours, scheduler: &mut TickScheduler, simulation: &mut RedstoneSimulation, ticks: u32) {
+    for _ in 0..ticks {
+        run_tick(world, registry, behaviours, scheduler, simulation);
+    }
+}
+
+/// # Description:
+/// System that runs one game tick, it belongs into the fixed timestep schedule so the simulation speed does not depend on the frame rate
+pub fn game_tick(mut scheduler: ResMut<TickScheduler>, mut simulation: ResMut<RedstoneSimulation>, mut voxel_world: ResMut<VoxelWorld>, registry: Res<BlockRegistry>, behaviours: Res<BlockBehaviours>) {
+    run_tick(&mut voxel_world, &registry, &behaviours, &mut scheduler, &mut simulation);
+}
+
+/// hands a due update to the ```BlockBehaviour``` of the block type that is at its position now, block types without one ignore it
+fn run_block_update(world: &mut VoxelWorld, registry: &BlockRegistry, behaviours: &BlockBehaviours, scheduler: &mut TickScheduler, block_pos: BlockPos) {
+    let Some(behaviour) = world.get_block(block_pos).ok().and_then(|block| behaviours.get(block.block_type())) else { return };
+    behaviour.scheduled_update(&mut BlockContext { world, registry, scheduler }, block_pos);
+}
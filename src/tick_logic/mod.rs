@@ -0,0 +1,3 @@
+pub mod game_tick;
+pub mod neighbour_updates;
+pub mod scheduler;
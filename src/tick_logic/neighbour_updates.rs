@@ -0,0 +1,413 @@
+// foreign imports
+use std::collections::{HashMap, HashSet, VecDeque};
+use bevy::prelude::Resource;
+
+// 'self' imports
+use crate::block_registry::BlockRegistry;
+use crate::blocks::{Block, BlockId, Facing};
+use crate::chunk_logic::position_handling::BlockPos;
+use crate::chunk_logic::world::{VoxelWorld, WorldError};
+use crate::light_logic::light;
+use crate::tick_logic::scheduler::TickScheduler;
+
+/// How many changes a chain of neighbour updates may lead through, changes deeper than that do not notify their neighbours anymore
+pub const MAX_UPDATE_DEPTH: u32 = 64;
+
+/// How often the behaviour of a single block may run during one chain, this stops blocks that keep changing each other
+pub const MAX_UPDATES_PER_BLOCK: u32 = 16;
+
+/// # Description:
+/// What a ```BlockBehaviour``` may act on while it reacts to a change or runs a scheduled update. Blocks changed through ```set_block()``` notify their own neighbours in turn.
+/// # Structure:
+/// ```
+/// pub struct BlockContext<'a> {
+///     pub world: &'a mut VoxelWorld,
+///     pub registry: &'a BlockRegistry,
+///     pub scheduler: &'a mut TickScheduler,
+/// }
+/// ```
+pub struct BlockContext<'a> {
+    pub world: &'a mut VoxelWorld,
+    pub registry: &'a BlockRegistry,
+    pub scheduler: &'a mut TickScheduler,
+}
+
+impl BlockContext<'_> {
+
+    /// # Description:
+    /// Replaces the ```Block``` at the world position and updates the light, see ```light::set_block()```
+    /// # Errors:
+    /// ```WorldError::ChunkNotLoaded``` if the containing chunk is not loaded
+    pub fn set_block(&mut self, block_pos: BlockPos, block: Block) -> Result<Block, WorldError> {
+        light::set_block(self.world, self.registry, block_pos, block)
+    }
+}
+
+/// # Description:
+/// The reaction of a block type to changes of the blocks next to it and to its scheduled updates, registered per block type in the ```BlockBehaviours```.
+pub trait BlockBehaviour: Send + Sync {
+    /// # Description:
+    /// Called when the block on the ```side``` of the block at ```block_pos``` changed
+    fn neighbour_changed(&self, context: &mut BlockContext, block_pos: BlockPos, side: Facing);
+
+    /// # Description:
+    /// Called when an update that was scheduled for ```block_pos``` (see ```TickScheduler::schedule()```) is due and the block there is of this type.
+    /// Block types that never schedule updates keep the default, which does nothing.
+    fn scheduled_update(&self, _context: &mut BlockContext, _block_pos: BlockPos) {}
+
+    /// # Description:
+    /// Returns ```true``` if ```block``` may be placed at ```block_pos```, which is still empty. Block types that stand anywhere keep the default.
+    fn can_place(&self, _world: &VoxelWorld, _registry: &BlockRegistry, _block_pos: BlockPos, _block: &Block) -> bool {
+        true
+    }
+}
+
+/// # Description:
+/// Which neighbour holds a block that cannot stand on its own
+/// # Format:
+/// ```
+/// pub enum Support {
+///     Below,
+///     Behind
+/// }
+/// ```
+/// ```Behind``` is the block on the opposite side of the ```Facing``` of the ```Block```, e.g. the wall a torch is attached to.
+/// Nothing hangs from the block above it, so a torch facing down is never supported.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Support {
+    Below,
+    Behind
+}
+
+/// # Description:
+/// The behaviour of blocks that pop off when the face they rest on is gone, like torches, wires and flowers
+/// # Format:
+/// ```
+/// pub struct NeedsSupport(pub Support);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct NeedsSupport(pub Support);
+
+impl NeedsSupport {
+
+    /// # Description:
+    /// Returns ```true``` if the block at ```block_pos``` rests on a full, visible face of its supporting block
+    pub fn is_supported(&self, world: &VoxelWorld, registry: &BlockRegistry, block_pos: BlockPos) -> bool {
+        let Ok(block) = world.get_block(block_pos) else { return true };
+        self.supports(world, registry, block_pos, block)
+    }
+
+    /// # Description:
+    /// Returns ```true``` if ```block``` would rest on a full, visible face of its supporting block at ```block_pos```, whatever is there now
+    pub fn supports(&self, world: &VoxelWorld, registry: &BlockRegistry, block_pos: BlockPos, block: &Block) -> bool {
+        let side = self.side(block);
+        side != Facing::YPositive && world.get_block(block_pos.neighbour(side)).is_ok_and(|support| {
+            registry.render_layer(support).is_visible() && registry.shape(support).side(side.opposite()).is_full()
+        })
+    }
+
+    fn side(&self, block: &Block) -> Facing {
+        match self.0 {
+            Support::Below => Facing::YNegative,
+            Support::Behind => block.face_direction().opposite(),
+        }
+    }
+}
+
+impl BlockBehaviour for NeedsSupport {
+    fn neighbour_changed(&self, context: &mut BlockContext, block_pos: BlockPos, side: Facing) {
+        let Ok(block) = context.world.get_block(block_pos).copied() else { return };
+        if side == self.side(&block) && !self.is_supported(context.world, context.registry, block_pos) {
+            context.set_block(block_pos, Block::new(BlockId::AIR, Facing::XPositive)).expect("the block was just read from a loaded chunk");
+        }
+    }
+
+    fn can_place(&self, world: &VoxelWorld, registry: &BlockRegistry, block_pos: BlockPos, block: &Block) -> bool {
+        self.supports(world, registry, block_pos, block)
+    }
+}
+
+/// # Description:
+/// The outcome of one chain of neighbour updates
+/// # Structure:
+/// ```
+/// pub struct UpdateReport {
+///     pub changed: Vec<BlockPos>,
+///     pub behaviours_run: usize,
+///     pub depth_limited: usize,
+///     pub loops_cut: usize,
+/// }
+/// ```
+/// ```changed``` holds every changed position once, in the order the chain reached it. ```depth_limited``` changes were too deep to notify their neighbours,
+/// ```loops_cut``` behaviours were skipped because their block already reacted ```MAX_UPDATES_PER_BLOCK``` times.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct UpdateReport {
+    pub changed: Vec<BlockPos>,
+    pub behaviours_run: usize,
+    pub depth_limited: usize,
+    pub loops_cut: usize,
+}
+
+/// # Description:
+/// Holds the ```BlockBehaviour``` of every block type that reacts to its neighbours, together with the limits of a chain of updates.
+/// Block types without a behaviour ignore their neighbours.
+/// # Structure:
+/// ```
+/// pub struct BlockBehaviours {
+///     behaviours: HashMap<BlockId, Box<dyn BlockBehaviour>>,
+///     max_depth: u32,
+///     max_updates_per_block: u32,
+/// }
+/// ```
+#[derive(Resource)]
+pub struct BlockBehaviours {
+    behaviours: HashMap<BlockId, Box<dyn BlockBehaviour>>,
+    max_depth: u32,
+    max_updates_per_block: u32,
+}
+
+impl Default for BlockBehaviours {
+    /// # Description:
+    /// No behaviours with the default limits
+    fn default() -> Self {
+        Self::with_limits(MAX_UPDATE_DEPTH, MAX_UPDATES_PER_BLOCK)
+    }
+}
+
+impl BlockBehaviours {
+
+    /// # Description:
+    /// Creates an empty set of behaviours with the default limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Description:
+    /// Creates an empty set of behaviours with custom limits, see ```MAX_UPDATE_DEPTH``` and ```MAX_UPDATES_PER_BLOCK```
+    pub fn with_limits(max_depth: u32, max_updates_per_block: u32) -> Self {
+        BlockBehaviours { behaviours: HashMap::new(), max_depth, max_updates_per_block }
+    }
+
+    /// # Description:
+    /// The behaviours of the built-in block types of ```registry```: torches, wires, repeaters, comparators and flowers pop off without support
+    pub fn with_defaults(registry: &BlockRegistry) -> Self {
+        let mut behaviours = Self::new();
+        for (name, support) in [
+            ("redstone_torch", Support::Behind),
+            ("redstone_wire", Support::Below),
+            ("repeater", Support::Below),
+            ("comparator", Support::Below),
+            ("flower", Support::Below),
+        ] {
+            if let Some(id) = registry.id(name) {
+                behaviours.register(id, NeedsSupport(support));
+            }
+        }
+        behaviours
+    }
+
+    /// # Description:
+    /// Sets the behaviour of the block type ```id```, replacing its previous one
+    pub fn register(&mut self, id: BlockId, behaviour: impl BlockBehaviour + 'static) {
+        self.behaviours.insert(id, Box::new(behaviour));
+    }
+
+    /// # Description:
+    /// The behaviour of the block type ```id```, if it has one
+    pub fn get(&self, id: BlockId) -> Option<&dyn BlockBehaviour> {
+        self.behaviours.get(&id).map(|behaviour| behaviour.as_ref())
+    }
+
+    /// # Description:
+    /// Returns ```true``` if ```block``` may be placed at ```block_pos```, see ```BlockBehaviour::can_place()```. Block types without a behaviour stand anywhere.
+    pub fn can_place(&self, world: &VoxelWorld, registry: &BlockRegistry, block_pos: BlockPos, block: &Block) -> bool {
+        self.get(block.block_type()).is_none_or(|behaviour| behaviour.can_place(world, registry, block_pos, block))
+    }
+
+    /// # Description:
+    /// Runs the chain of neighbour updates for every change recorded by ```VoxelWorld::set_block()``` since the last chain.
+    ///
+    /// The changes are handled breadth first: every changed block notifies the behaviours of its six neighbours,
+    /// whose own changes are handled after the ones that are already queued. Headless, so tests can run it on any ```VoxelWorld```.
+    pub fn propagate(&self, world: &mut VoxelWorld, registry: &BlockRegistry, scheduler: &mut TickScheduler) -> UpdateReport {
+        let mut report = UpdateReport::default();
+        let mut queue: VecDeque<(BlockPos, u32)> = world.take_changes().into_iter().map(|block_pos| (block_pos, 0)).collect();
+        let mut seen = HashSet::new();
+        let mut runs: HashMap<BlockPos, u32> = HashMap::new();
+
+        while let Some((block_pos, depth)) = queue.pop_front() {
+            if seen.insert(block_pos) {
+                report.changed.push(block_pos);
+            }
+            if depth >= self.max_depth {
+                report.depth_limited += 1;
+                continue;
+            }
+            for facing in Facing::ALL {
+                let neighbour_pos = block_pos.neighbour(facing);
+                let Some(behaviour) = world.get_block(neighbour_pos).ok().and_then(|neighbour| self.get(neighbour.block_type())) else { continue };
+                let count = runs.entry(neighbour_pos).or_default();
+                if *count >= self.max_updates_per_block {
+                    report.loops_cut += 1;
+                    continue;
+                }
+                *count += 1;
+                report.behaviours_run += 1;
+
+                let mut context = BlockContext { world, registry, scheduler };
+                behaviour.neighbour_changed(&mut context, neighbour_pos, facing.opposite());
+                queue.extend(world.take_changes().into_iter().map(|changed| (changed, depth + 1)));
+            }
+        }
+        report
+    }
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use crate::block_registry::{test_block, test_registry};
+    use crate::block_state::PropertyValue;
+    use crate::chunk_logic::world::test_floor_world;
+    use super::*;
+
+    fn propagate(behaviours: &BlockBehaviours, world: &mut VoxelWorld) -> UpdateReport {
+        behaviours.propagate(world, test_registry(), &mut TickScheduler::new())
+    }
+
+    /// records every call it gets
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<(BlockPos, Facing)>>);
+
+    impl BlockBehaviour for &'static Recorder {
+        fn neighbour_changed(&self, _context: &mut BlockContext, block_pos: BlockPos, side: Facing) {
+            self.0.lock().unwrap().push((block_pos, side));
+        }
+    }
+
+    /// falls over (turns into air) when the block on its negative x side is gone
+    struct Domino;
+
+    impl BlockBehaviour for Domino {
+        fn neighbour_changed(&self, context: &mut BlockContext, block_pos: BlockPos, side: Facing) {
+            if side == Facing::XNegative && context.world.get_block(block_pos + side.offset()).is_ok_and(Block::is_air) {
+                context.set_block(block_pos, test_block("air")).unwrap();
+            }
+        }
+    }
+
+    /// flips its ```persistent``` state whenever a neighbour changes, two of them keep each other busy forever
+    struct Flipper;
+
+    impl BlockBehaviour for Flipper {
+        fn neighbour_changed(&self, context: &mut BlockContext, block_pos: BlockPos, _side: Facing) {
+            let block = *context.world.get_block(block_pos).unwrap();
+            let persistent = context.registry.property(&block, "persistent") == Ok(PropertyValue::Bool(true));
+            let flipped = context.registry.with_property(block, "persistent", PropertyValue::Bool(!persistent)).unwrap();
+            context.set_block(block_pos, flipped).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_behaviours_see_the_changed_side() {
+        static RECORDER: Recorder = Recorder(Mutex::new(Vec::new()));
+        let mut world = test_floor_world([0]);
+        let mut behaviours = BlockBehaviours::new();
+        behaviours.register(test_block("planks").block_type(), &RECORDER);
+        for pos in [BlockPos::new(4, 2, 5), BlockPos::new(5, 3, 5)] {
+            world.set_block(pos, test_block("planks")).unwrap();
+        }
+        world.take_changes();
+
+        world.set_block(BlockPos::new(5, 2, 5), test_block("glass")).unwrap();
+        let report = propagate(&behaviours, &mut world);
+        assert_eq!(vec![BlockPos::new(5, 2, 5)], report.changed);
+        assert_eq!(2, report.behaviours_run);
+        // the stone below has no behaviour
+        assert_eq!(vec![(BlockPos::new(4, 2, 5), Facing::XPositive), (BlockPos::new(5, 3, 5), Facing::YNegative)], *RECORDER.0.lock().unwrap());
+    }
+
+    #[test]
+    fn test_unsupported_blocks_pop_off() {
+        let mut world = test_floor_world([0]);
+        let behaviours = BlockBehaviours::with_defaults(test_registry());
+        let torch = Block::new(test_block("redstone_torch").block_type(), Facing::XPositive);
+        world.set_block(BlockPos::new(3, 2, 5), test_block("stone")).unwrap();
+        world.set_block(BlockPos::new(4, 2, 5), torch).unwrap();
+        world.set_block(BlockPos::new(3, 3, 5), test_block("redstone_wire")).unwrap();
+        world.set_block(BlockPos::new(8, 2, 5), test_block("flower")).unwrap();
+        world.set_block(BlockPos::new(8, 2, 6), test_block("stone_slab")).unwrap();
+        world.set_block(BlockPos::new(8, 3, 6), test_block("redstone_wire")).unwrap();
+        // the wire hears about the slab below it, whose top is not a full face
+        let report = propagate(&behaviours, &mut world);
+        assert_eq!(6, report.changed.len());
+        assert!(world.get_block(BlockPos::new(8, 3, 6)).unwrap().is_air());
+        assert_eq!(torch, *world.get_block(BlockPos::new(4, 2, 5)).unwrap());
+        assert_eq!(test_block("redstone_wire"), *world.get_block(BlockPos::new(3, 3, 5)).unwrap());
+
+        // removing the stone takes the torch on its side and the wire on top with it
+        world.set_block(BlockPos::new(3, 2, 5), test_block("air")).unwrap();
+        let report = propagate(&behaviours, &mut world);
+        assert_eq!(vec![BlockPos::new(3, 2, 5), BlockPos::new(4, 2, 5), BlockPos::new(3, 3, 5)], report.changed);
+        assert!(world.get_block(BlockPos::new(4, 2, 5)).unwrap().is_air());
+        assert!(world.get_block(BlockPos::new(3, 3, 5)).unwrap().is_air());
+
+        // the flower only cares about the block below it
+        world.set_block(BlockPos::new(8, 2, 4), test_block("stone")).unwrap();
+        world.set_block(BlockPos::new(8, 1, 5), test_block("dirt")).unwrap();
+        propagate(&behaviours, &mut world);
+        assert_eq!(test_block("flower"), *world.get_block(BlockPos::new(8, 2, 5)).unwrap());
+        world.set_block(BlockPos::new(8, 1, 5), test_block("glass")).unwrap();
+        propagate(&behaviours, &mut world);
+        assert_eq!(test_block("flower"), *world.get_block(BlockPos::new(8, 2, 5)).unwrap());
+        world.set_block(BlockPos::new(8, 1, 5), test_block("air")).unwrap();
+        propagate(&behaviours, &mut world);
+        assert!(world.get_block(BlockPos::new(8, 2, 5)).unwrap().is_air());
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let mut world = test_floor_world([0]);
+        let mut behaviours = BlockBehaviours::with_limits(5, MAX_UPDATES_PER_BLOCK);
+        behaviours.register(test_block("planks").block_type(), Domino);
+        for x in 1..15 {
+            world.set_block(BlockPos::new(x, 2, 5), test_block("planks")).unwrap();
+        }
+        world.take_changes();
+
+        world.set_block(BlockPos::new(1, 2, 5), test_block("air")).unwrap();
+        let report = propagate(&behaviours, &mut world);
+        // the first change has depth 0, every fallen domino is one deeper
+        assert_eq!(6, report.changed.len());
+        assert_eq!(1, report.depth_limited);
+        assert!(world.get_block(BlockPos::new(6, 2, 5)).unwrap().is_air());
+        assert_eq!(test_block("planks"), *world.get_block(BlockPos::new(7, 2, 5)).unwrap());
+
+        // the next chain picks up where nothing changes anymore, the rest only falls with a new change
+        assert_eq!(UpdateReport::default(), propagate(&behaviours, &mut world));
+        let mut unlimited = BlockBehaviours::new();
+        unlimited.register(test_block("planks").block_type(), Domino);
+        world.set_block(BlockPos::new(6, 2, 5), test_block("glass")).unwrap();
+        world.set_block(BlockPos::new(6, 2, 5), test_block("air")).unwrap();
+        propagate(&unlimited, &mut world);
+        assert!((1..15).all(|x| world.get_block(BlockPos::new(x, 2, 5)).unwrap().is_air()));
+    }
+
+    #[test]
+    fn test_loops_are_cut() {
+        let mut world = test_floor_world([0]);
+        let mut behaviours = BlockBehaviours::with_limits(1000, 3);
+        behaviours.register(test_block("leaves").block_type(), Flipper);
+        world.set_block(BlockPos::new(4, 2, 5), test_block("leaves")).unwrap();
+        world.set_block(BlockPos::new(5, 2, 5), test_block("leaves")).unwrap();
+
+        let report = propagate(&behaviours, &mut world);
+        assert_eq!(6, report.behaviours_run);
+        assert!(report.loops_cut > 0);
+        assert_eq!(0, report.depth_limited);
+        assert!(!world.has_changes());
+    }
+}
@@ -0,0 +1,378 @@
+// foreign imports
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashSet};
+use std::time::Duration;
+use bevy::prelude::Resource;
+use serde::{Deserialize, Serialize};
+
+// 'self' imports
+use crate::chunk_logic::position_handling::{BlockPos, ChunkPos};
+use crate::chunk_logic::world::{VoxelWorld, WorldError};
+
+/// How many game ticks run per second
+pub const TICKS_PER_SECOND: f64 = 20.0;
+
+/// How many scheduled updates run per tick at most, the rest waits for the following ticks
+pub const DEFAULT_TICK_BUDGET: usize = 4096;
+
+/// The priority of an update that does not need to run before or after the others of its tick
+pub const DEFAULT_PRIORITY: i8 = 0;
+
+/// # Description:
+/// A block update that is due at a certain game tick. Whatever block is at ```block_pos``` when the update runs decides what happens.
+///
+/// Updates run ordered by their ```due``` tick, then by their ```priority``` (lower values first), then in the order they were scheduled.
+/// # Structure:
+/// ```
+/// pub struct ScheduledUpdate {
+///     pub block_pos: BlockPos,
+///     pub due: u64,
+///     pub priority: i8,
+///     sequence: u64,
+/// }
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct ScheduledUpdate {
+    pub block_pos: BlockPos,
+    pub due: u64,
+    pub priority: i8,
+    sequence: u64,
+}
+
+impl Ord for ScheduledUpdate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // the sequence is unique, so the position never decides
+        (self.due, self.priority, self.sequence).cmp(&(other.due, other.priority, other.sequence))
+    }
+}
+
+impl PartialOrd for ScheduledUpdate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// # Description:
+/// The scheduled updates of the blocks in one chunk. They are stored with the ```ChunkComp``` and saved with its blocks (see ```ChunkSave```),
+/// so they stay with the chunk while it is unloaded and continue once it is loaded again. Updates that became due in the meantime run on the next tick.
+///
+/// Every position has at most one scheduled update. It is saved as the list of its updates in the order they run.
+/// # Structure:
+/// ```
+/// pub struct ChunkTicks {
+///     queue: BinaryHeap<Reverse<ScheduledUpdate>>,
+///     positions: HashSet<BlockPos>,
+/// }
+/// ```
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+#[serde(from = "Vec<ScheduledUpdate>", into = "Vec<ScheduledUpdate>")]
+pub struct ChunkTicks {
+    queue: BinaryHeap<Reverse<ScheduledUpdate>>,
+    positions: HashSet<BlockPos>,
+}
+
+impl ChunkTicks {
+
+    /// # Description:
+    /// Creates an empty queue
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Description:
+    /// The amount of scheduled updates
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// # Description:
+    /// Returns ```true``` if no update is scheduled
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// # Description:
+    /// Returns ```true``` if the block at the world position has a scheduled update
+    pub fn contains(&self, block_pos: BlockPos) -> bool {
+        self.positions.contains(&block_pos)
+    }
+
+    /// # Description:
+    /// Every scheduled update, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = &ScheduledUpdate> {
+        self.queue.iter().map(|Reverse(update)| update)
+    }
+
+    /// queues ```update```, unless its position already has one
+    fn push(&mut self, update: ScheduledUpdate) -> bool {
+        let added = self.positions.insert(update.block_pos);
+        if added {
+            self.queue.push(Reverse(update));
+        }
+        added
+    }
+
+    /// moves every update that is due at ```tick``` into ```due```
+    fn pop_due(&mut self, tick: u64, due: &mut Vec<ScheduledUpdate>) {
+        while let Some(Reverse(update)) = self.queue.peek().copied().filter(|Reverse(update)| update.due <= tick) {
+            self.queue.pop();
+            self.positions.remove(&update.block_pos);
+            due.push(update);
+        }
+    }
+}
+
+impl From<Vec<ScheduledUpdate>> for ChunkTicks {
+    /// # Description:
+    /// Queues the saved ```updates```, only the first one of each position is kept
+    fn from(updates: Vec<ScheduledUpdate>) -> Self {
+        let mut ticks = ChunkTicks::new();
+        for update in updates {
+            ticks.push(update);
+        }
+        ticks
+    }
+}
+
+impl From<ChunkTicks> for Vec<ScheduledUpdate> {
+    /// # Description:
+    /// Every scheduled update in the order they run
+    fn from(ticks: ChunkTicks) -> Self {
+        let mut updates: Vec<ScheduledUpdate> = ticks.queue.into_iter().map(|Reverse(update)| update).collect();
+        updates.sort();
+        updates
+    }
+}
+
+/// # Description:
+/// What happened in the last game tick, for the debug UI
+/// # Structure:
+/// ```
+/// pub struct TickStats {
+///     pub ran: usize,
+///     pub deferred: usize,
+///     pub queued: usize,
+///     pub duration: Duration,
+/// }
+/// ```
+/// ```deferred``` updates were due but did not fit into the budget, ```queued``` is the amount of updates left in the loaded chunks after the tick.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct TickStats {
+    pub ran: usize,
+    pub deferred: usize,
+    pub queued: usize,
+    pub duration: Duration,
+}
+
+/// # Description:
+/// The game tick clock together with the scheduled block updates, shared by everything that acts on the blocks over time (redstone, later fluids and falling blocks).
+///
+/// The updates themselves are stored per chunk (see ```ChunkTicks```), the scheduler hands out the ones that are due across all loaded chunks
+/// in a deterministic order, at most ```budget``` per tick. See ```game_tick::run_tick()``` for a whole tick.
+///
+/// The clock is saved with the world (see ```WorldSave```), so the ```due``` ticks of the saved updates still mean the same once it is loaded again.
+/// The ```stats``` are not saved.
+/// # Structure:
+/// ```
+/// pub struct TickScheduler {
+///     tick: u64,
+///     sequence: u64,
+///     budget: usize,
+///     stats: TickStats,
+/// }
+/// ```
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct TickScheduler {
+    tick: u64,
+    sequence: u64,
+    budget: usize,
+    #[serde(skip)]
+    stats: TickStats,
+}
+
+impl Default for TickScheduler {
+    /// # Description:
+    /// A scheduler at tick 0 with the ```DEFAULT_TICK_BUDGET```
+    fn default() -> Self {
+        Self::with_budget(DEFAULT_TICK_BUDGET)
+    }
+}
+
+impl TickScheduler {
+
+    /// # Description:
+    /// Creates a scheduler at tick 0 with the ```DEFAULT_TICK_BUDGET```
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Description:
+    /// Creates a scheduler at tick 0 that runs at most ```budget``` updates per tick (at least 1)
+    pub fn with_budget(budget: usize) -> Self {
+        TickScheduler { tick: 0, sequence: 0, budget: budget.max(1), stats: TickStats::default() }
+    }
+
+    /// # Description:
+    /// The number of the current game tick, 0 before the first tick
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// # Description:
+    /// How many updates run per tick at most
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// # Description:
+    /// What happened in the last tick
+    pub fn stats(&self) -> TickStats {
+        self.stats
+    }
+
+    /// # Description:
+    /// Schedules an update of the block at ```block_pos``` in ```delay``` ticks (at least 1). Returns ```false``` if the position already has a scheduled update,
+    /// which is kept as it is.
+    /// # Errors:
+    /// ```WorldError::ChunkNotLoaded``` if the containing chunk is not loaded
+    pub fn schedule(&mut self, world: &mut VoxelWorld, block_pos: BlockPos, delay: u32, priority: i8) -> Result<bool, WorldError> {
+        let chunk_pos = block_pos.chunk();
+        let chunk = world.get_chunk_mut(chunk_pos).ok_or(WorldError::ChunkNotLoaded(chunk_pos))?;
+        let update = ScheduledUpdate { block_pos, due: self.tick + delay.max(1) as u64, priority, sequence: self.sequence };
+        let added = chunk.scheduled_mut().push(update);
+        if added {
+            self.sequence += 1;
+        }
+        Ok(added)
+    }
+
+    /// # Description:
+    /// Returns ```true``` if the block at ```block_pos``` has a scheduled update, ```false``` for unloaded chunks
+    pub fn is_scheduled(&self, world: &VoxelWorld, block_pos: BlockPos) -> bool {
+        world.get_chunk(block_pos.chunk()).is_some_and(|chunk| chunk.scheduled().contains(block_pos))
+    }
+
+    /// # Description:
+    /// The amount of scheduled updates in all loaded chunks
+    pub fn queue_len(&self, world: &VoxelWorld) -> usize {
+        world.chunk_positions().filter_map(|chunk_pos| world.get_chunk(chunk_pos)).map(|chunk| chunk.scheduled().len()).sum()
+    }
+
+    /// # Description:
+    /// Starts the next tick: takes the updates of the loaded chunks that are due, in the order they have to run.
+    /// Updates over the budget are put back and run first on one of the next ticks.
+    pub fn advance(&mut self, world: &mut VoxelWorld) -> Vec<ScheduledUpdate> {
+        self.tick += 1;
+        let mut due = Vec::new();
+        let chunk_positions: Vec<ChunkPos> = world.chunk_positions().collect();
+        for chunk_pos in chunk_positions {
+            if let Some(chunk) = world.get_chunk_mut(chunk_pos) {
+                chunk.scheduled_mut().pop_due(self.tick, &mut due);
+            }
+        }
+        due.sort();
+
+        let deferred = due.split_off(due.len().min(self.budget));
+        self.stats = TickStats { ran: due.len(), deferred: deferred.len(), ..TickStats::default() };
+        for update in deferred {
+            world.get_chunk_mut(update.block_pos.chunk())
+                .expect("the update was just taken from this chunk")
+                .scheduled_mut()
+                .push(update);
+        }
+        due
+    }
+
+    /// # Description:
+    /// Finishes the tick started by ```advance()```, recording how long it took for the ```stats()```
+    pub fn finish(&mut self, world: &VoxelWorld, duration: Duration) {
+        self.stats.queued = self.queue_len(world);
+        self.stats.duration = duration;
+    }
+}
+
+
+
+// UNIT TESTS //
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_registry::test_block;
+    use crate::chunk_logic::chunk::ChunkComp;
+
+    fn air_world() -> VoxelWorld {
+        let mut world = VoxelWorld::new();
+        world.insert_chunk(ChunkPos::ZERO, ChunkComp::new_simple(test_block("air")));
+        world.insert_chunk(ChunkPos::new(1, 0, 0), ChunkComp::new_simple(test_block("air")));
+        world
+    }
+
+    fn positions(updates: &[ScheduledUpdate]) -> Vec<BlockPos> {
+        updates.iter().map(|update| update.block_pos).collect()
+    }
+
+    #[test]
+    fn test_updates_run_by_due_tick_priority_and_order() {
+        let mut world = air_world();
+        let mut scheduler = TickScheduler::new();
+        let [a, b, c, d] = [1, 2, 3, 20].map(|x| BlockPos::new(x, 1, 1));
+        assert_eq!(Ok(true), scheduler.schedule(&mut world, d, 2, DEFAULT_PRIORITY));
+        assert_eq!(Ok(true), scheduler.schedule(&mut world, a, 2, DEFAULT_PRIORITY));
+        assert_eq!(Ok(true), scheduler.schedule(&mut world, b, 2, -1));
+        assert_eq!(Ok(true), scheduler.schedule(&mut world, c, 1, 5));
+        // a position only has one update at a time
+        assert_eq!(Ok(false), scheduler.schedule(&mut world, a, 1, -5));
+        assert!(scheduler.is_scheduled(&world, a));
+        assert_eq!(4, scheduler.queue_len(&world));
+
+        assert_eq!(vec![c], positions(&scheduler.advance(&mut world)));
+        // the chunk of d does not matter, it was scheduled before a
+        assert_eq!(vec![b, d, a], positions(&scheduler.advance(&mut world)));
+        assert!(scheduler.advance(&mut world).is_empty());
+        assert_eq!(3, scheduler.tick());
+        assert!(!scheduler.is_scheduled(&world, a));
+        assert_eq!(0, scheduler.queue_len(&world));
+    }
+
+    #[test]
+    fn test_budget_defers_updates() {
+        let mut world = air_world();
+        let mut scheduler = TickScheduler::with_budget(4);
+        for x in 0..10 {
+            scheduler.schedule(&mut world, BlockPos::new(x, 0, 0), 1, DEFAULT_PRIORITY).unwrap();
+        }
+        let late = BlockPos::new(0, 1, 0);
+        scheduler.schedule(&mut world, late, 2, -10).unwrap();
+
+        let first = scheduler.advance(&mut world);
+        scheduler.finish(&world, Duration::from_millis(3));
+        assert_eq!((0..4).map(|x| BlockPos::new(x, 0, 0)).collect::<Vec<_>>(), positions(&first));
+        assert_eq!(TickStats { ran: 4, deferred: 6, queued: 7, duration: Duration::from_millis(3) }, scheduler.stats());
+
+        // deferred updates are overdue, so they run before the ones that only became due now
+        let second = scheduler.advance(&mut world);
+        assert_eq!((4..8).map(|x| BlockPos::new(x, 0, 0)).collect::<Vec<_>>(), positions(&second));
+        let third = scheduler.advance(&mut world);
+        assert_eq!(vec![BlockPos::new(8, 0, 0), BlockPos::new(9, 0, 0), late], positions(&third));
+    }
+
+    #[test]
+    fn test_updates_are_stored_with_the_chunk() {
+        let mut world = air_world();
+        let mut scheduler = TickScheduler::new();
+        let pos = BlockPos::new(18, 2, 3);
+        scheduler.schedule(&mut world, pos, 3, DEFAULT_PRIORITY).unwrap();
+        assert_eq!(Err(WorldError::ChunkNotLoaded(ChunkPos::new(-1, 0, 0))), scheduler.schedule(&mut world, BlockPos::new(-1, 0, 0), 1, DEFAULT_PRIORITY));
+
+        let chunk = world.remove_chunk(ChunkPos::new(1, 0, 0)).unwrap();
+        assert_eq!(1, chunk.scheduled().len());
+        for _ in 0..5 {
+            assert!(scheduler.advance(&mut world).is_empty());
+        }
+
+        // the update became due while the chunk was unloaded and runs right after it is back
+        world.insert_chunk(ChunkPos::new(1, 0, 0), chunk);
+        assert!(scheduler.is_scheduled(&world, pos));
+        assert_eq!(vec![pos], positions(&scheduler.advance(&mut world)));
+    }
+}